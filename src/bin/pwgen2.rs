@@ -7,6 +7,22 @@ async fn main() -> Result<()> {
 
     match action {
         Action::Run { .. } => actions::run::handle(action).await?,
+        Action::Check { .. } => actions::check::handle(action)?,
+        Action::Calibrate { .. } => actions::calibrate::handle(action)?,
+        Action::Hash { .. } => actions::hash::handle(action)?,
+        Action::Verify { .. } => match actions::verify::handle(action) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(1),
+            Err(e) => {
+                eprintln!("Error: {:?}", e);
+                std::process::exit(actions::verify::ERROR_EXIT_CODE);
+            }
+        },
+        Action::Scan { .. } => {
+            if actions::scan::handle(action)? {
+                std::process::exit(1);
+            }
+        }
     }
 
     Ok(())
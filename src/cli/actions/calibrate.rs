@@ -0,0 +1,90 @@
+use crate::cli::actions::Action;
+use crate::pwgen::calibrate::{calibrate, Calibration};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Times the hash algorithms and prints the recommended costs
+pub fn handle(action: Action) -> Result<()> {
+    let Action::Calibrate { target_ms, json } = action else {
+        return Ok(());
+    };
+
+    let calibrations = calibrate(Duration::from_millis(target_ms))?;
+
+    if json {
+        let reports: Vec<Value> = calibrations.iter().map(to_json).collect();
+        println!("{}", Value::Array(reports));
+    } else {
+        println!("Target: {} ms", target_ms);
+        for calibration in &calibrations {
+            println!("{}", to_text(calibration));
+        }
+    }
+
+    Ok(())
+}
+
+fn to_text(calibration: &Calibration) -> String {
+    let option = format!("--{} {}", calibration.parameter, calibration.value);
+    let mut text = format!(
+        "--{:<9} {:<17} {:>8.1} ms",
+        calibration.algorithm,
+        option,
+        calibration.duration.as_secs_f64() * 1000.0
+    );
+
+    if let Some(memory) = calibration.memory_kib {
+        text.push_str(&format!(" ({} KiB)", memory));
+    }
+
+    text
+}
+
+fn to_json(calibration: &Calibration) -> Value {
+    json!({
+        "algorithm": calibration.algorithm.name(),
+        "parameter": calibration.parameter,
+        "value": calibration.value,
+        "memory_kib": calibration.memory_kib,
+        "duration_ms": calibration.duration.as_secs_f64() * 1000.0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pwgen::hash::HashAlgorithm;
+
+    fn calibration() -> Calibration {
+        Calibration {
+            algorithm: HashAlgorithm::Argon2,
+            parameter: "rounds",
+            value: 3,
+            memory_kib: Some(19456),
+            duration: Duration::from_micros(240_500),
+        }
+    }
+
+    #[test]
+    fn test_to_text() {
+        assert_eq!(
+            to_text(&calibration()),
+            "--argon2    --rounds 3           240.5 ms (19456 KiB)"
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json(&calibration()),
+            json!({
+                "algorithm": "argon2",
+                "parameter": "rounds",
+                "value": 3,
+                "memory_kib": 19456,
+                "duration_ms": 240.5
+            })
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::cli::actions::Action;
+use crate::pwgen::strength::{estimate_strength, Strength};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::io::{self, BufRead};
+
+/// Scores the passwords read from stdin, one per line
+pub fn handle(action: Action) -> Result<()> {
+    let Action::Check { json } = action else {
+        return Ok(());
+    };
+
+    let mut reports = Vec::new();
+
+    for line in io::stdin().lock().lines() {
+        let password = line.context("Failed to read password from stdin")?;
+
+        // Empty lines are skipped
+        if password.is_empty() {
+            continue;
+        }
+
+        let strength = estimate_strength(&password);
+
+        if json {
+            reports.push(to_json(&password, &strength));
+        } else {
+            print!("{}", to_text(&password, &strength));
+        }
+    }
+
+    if json {
+        println!("{}", Value::Array(reports));
+    }
+
+    Ok(())
+}
+
+fn to_text(password: &str, strength: &Strength) -> String {
+    let mut text = format!(
+        "{}\n  score: {}/4\n  guesses: 10^{:.2}\n",
+        password,
+        strength.score,
+        strength.guesses_log10()
+    );
+
+    if let Some(warning) = strength.feedback.warning {
+        text.push_str(&format!("  warning: {}\n", warning));
+    }
+
+    for suggestion in &strength.feedback.suggestions {
+        text.push_str(&format!("  suggestion: {}\n", suggestion));
+    }
+
+    text
+}
+
+fn to_json(password: &str, strength: &Strength) -> Value {
+    let sequence: Vec<Value> = strength
+        .sequence
+        .iter()
+        .map(|m| {
+            json!({
+                "pattern": m.pattern.name(),
+                "token": m.token,
+                "guesses": m.guesses
+            })
+        })
+        .collect();
+
+    json!({
+        "password": password,
+        "score": strength.score,
+        "guesses": strength.guesses,
+        "guesses_log10": strength.guesses_log10(),
+        "warning": strength.feedback.warning,
+        "suggestions": strength.feedback.suggestions,
+        "sequence": sequence
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_text() {
+        let text = to_text("qwerty", &estimate_strength("qwerty"));
+
+        assert!(text.starts_with("qwerty\n  score: 0/4\n"));
+        assert!(text.contains("  warning: This is a top-10 common password\n"));
+        assert!(text.contains("  suggestion: Add another word or two"));
+    }
+
+    #[test]
+    fn test_to_json() {
+        let value = to_json("1990-05-13", &estimate_strength("1990-05-13"));
+
+        assert_eq!(value["password"], "1990-05-13");
+        assert_eq!(value["score"], 1);
+        assert_eq!(value["warning"], "Dates are often easy to guess");
+        assert_eq!(value["sequence"][0]["pattern"], "date");
+        assert!(value["guesses"].as_f64().unwrap() > 1.0);
+    }
+}
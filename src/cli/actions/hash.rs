@@ -0,0 +1,61 @@
+use crate::cli::actions::{input::read_passwords, Action};
+use crate::pwgen::hash::{hash, hash_peppered, HashAlgorithm};
+use anyhow::Result;
+use serde_json::{json, Value};
+
+/// Hashes the passwords read from stdin or prompted for, one hash per line
+pub fn handle(action: Action) -> Result<()> {
+    let Action::Hash {
+        hash_algorithm,
+        hash_params,
+        pepper,
+        json,
+    } = action
+    else {
+        return Ok(());
+    };
+
+    // Out of range costs are reported before reading any password
+    hash_params.validate(hash_algorithm)?;
+
+    let mut hashes = Vec::new();
+
+    for password in read_passwords()? {
+        let hashed = match &pepper {
+            Some(pepper) => hash_peppered(&password, hash_algorithm, &hash_params, pepper)?,
+            None => hash(&password, hash_algorithm, &hash_params)?,
+        };
+
+        if json {
+            hashes.push(to_json(hash_algorithm, &hashed));
+        } else {
+            println!("{}", hashed);
+        }
+    }
+
+    if json {
+        println!("{}", Value::Array(hashes));
+    }
+
+    Ok(())
+}
+
+fn to_json(algorithm: HashAlgorithm, hashed: &str) -> Value {
+    json!({
+        "algorithm": algorithm.name(),
+        "hash": hashed
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json(HashAlgorithm::Sha256, "$5$salt$hash"),
+            json!({"algorithm": "sha256", "hash": "$5$salt$hash"})
+        );
+    }
+}
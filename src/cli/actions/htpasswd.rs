@@ -0,0 +1,91 @@
+use crate::cli::actions::HtpasswdUpdate;
+use crate::pwgen::{
+    hash::{HashAlgorithm, HashParams},
+    htpasswd::{self, validate_username, Htpasswd, DEFAULT_ALGORITHM},
+};
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// Adds the users with a generated password and deletes the others, then
+/// prints the new credentials, the only time the passwords are shown
+pub fn handle(
+    update: HtpasswdUpdate,
+    generate: impl Fn(usize) -> String,
+    hash_algorithm: Option<HashAlgorithm>,
+    hash_params: &HashParams,
+    json: bool,
+) -> Result<()> {
+    let HtpasswdUpdate {
+        file,
+        mut users,
+        delete,
+    } = update;
+
+    for user in users.iter().chain(&delete) {
+        validate_username(user)?;
+    }
+    if let Some(user) = users.iter().find(|user| delete.contains(user)) {
+        bail!("User '{}' can't be both added and deleted", user);
+    }
+
+    // A user listed twice gets a single password
+    let mut seen = HashSet::new();
+    users.retain(|user| seen.insert(user.clone()));
+
+    let algorithm = hash_algorithm.unwrap_or(DEFAULT_ALGORITHM);
+    let mut htpasswd = Htpasswd::load(&file)
+        .with_context(|| format!("Failed to read htpasswd file {}", file.display()))?;
+
+    for user in &delete {
+        if !htpasswd.remove(user) {
+            eprintln!("User '{}' not found in {}", user, file.display());
+        }
+    }
+
+    // Every password is hashed before the file is written, a failure leaves it untouched
+    let mut credentials = Vec::with_capacity(users.len());
+    for (index, user) in users.iter().enumerate() {
+        let password = generate(index);
+        htpasswd.set(user, &htpasswd::hash(&password, algorithm, hash_params)?);
+        credentials.push((user, password));
+    }
+
+    htpasswd
+        .save(&file)
+        .with_context(|| format!("Failed to write htpasswd file {}", file.display()))?;
+
+    if json {
+        let entries: Vec<Value> = credentials
+            .iter()
+            .map(|(user, password)| to_json(user, password))
+            .collect();
+        println!("{}", Value::Array(entries));
+    } else {
+        for (user, password) in &credentials {
+            println!("{}:{}", user, password);
+        }
+    }
+
+    Ok(())
+}
+
+fn to_json(user: &str, password: &str) -> Value {
+    json!({
+        "user": user,
+        "password": password
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json("alice", "Lb*vTVcDniz7q6Ki-P"),
+            json!({"user": "alice", "password": "Lb*vTVcDniz7q6Ki-P"})
+        );
+    }
+}
@@ -0,0 +1,61 @@
+use anyhow::{bail, Context, Result};
+use std::io::{self, BufRead, IsTerminal};
+
+/// Prompts for the password without echo on a terminal, otherwise reads the first line of stdin
+pub fn read_password() -> Result<String> {
+    if io::stdin().is_terminal() {
+        return rpassword::prompt_password("Password: ").context("Failed to read password");
+    }
+
+    let mut line = String::new();
+    io::stdin()
+        .lock()
+        .read_line(&mut line)
+        .context("Failed to read password from stdin")?;
+
+    Ok(trim_newline(&line).to_string())
+}
+
+/// Prompts twice for one password on a terminal, otherwise reads the non-empty lines of stdin
+pub fn read_passwords() -> Result<Vec<String>> {
+    if io::stdin().is_terminal() {
+        let password =
+            rpassword::prompt_password("Password: ").context("Failed to read password")?;
+        let confirmation =
+            rpassword::prompt_password("Retype password: ").context("Failed to read password")?;
+        if password != confirmation {
+            bail!("Passwords don't match");
+        }
+        return Ok(vec![password]);
+    }
+
+    let mut passwords = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let password = line.context("Failed to read password from stdin")?;
+
+        // Empty lines are skipped
+        if !password.is_empty() {
+            passwords.push(password);
+        }
+    }
+
+    Ok(passwords)
+}
+
+fn trim_newline(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_newline() {
+        assert_eq!(trim_newline("password\n"), "password");
+        assert_eq!(trim_newline("password\r\n"), "password");
+        assert_eq!(trim_newline(" password \n"), " password ");
+        assert_eq!(trim_newline("password"), "password");
+    }
+}
@@ -1,4 +1,20 @@
+use crate::pwgen::{
+    hash::{HashAlgorithm, HashParams},
+    pepper::Pepper,
+    shadow::AccountFormat,
+    PassphraseConfig, Pattern,
+};
+use std::path::PathBuf;
+
+pub mod calibrate;
+pub mod check;
+pub mod hash;
+pub mod htpasswd;
+pub mod input;
 pub mod run;
+pub mod scan;
+pub mod shadow;
+pub mod verify;
 
 #[derive(Debug)]
 pub enum Action {
@@ -7,10 +23,57 @@ pub enum Action {
         num_pw: usize,
         pin: bool,
         alphanumeric: bool,
-        bcrypt: bool,
-        pbkdf2: bool,
-        sha512: bool,
+        pronounceable: bool,
+        hash_algorithm: Option<HashAlgorithm>,
+        hash_params: HashParams,
+        pepper: Option<Pepper>,
         charset: Option<String>,
+        pattern: Option<Pattern>,
+        regex: Option<String>,
+        passphrase: Option<PassphraseConfig>,
+        seed: Option<u64>,
+        entropy: bool,
+        htpasswd: Option<Box<HtpasswdUpdate>>,
+        accounts: Option<Box<AccountsOutput>>,
+        json: bool,
+    },
+    Check {
+        json: bool,
+    },
+    Calibrate {
+        target_ms: u64,
+        json: bool,
+    },
+    Verify {
+        hash: String,
+        pepper: Option<Pepper>,
         json: bool,
     },
+    Hash {
+        hash_algorithm: HashAlgorithm,
+        hash_params: HashParams,
+        pepper: Option<Pepper>,
+        json: bool,
+    },
+    Scan {
+        file: PathBuf,
+        hash_algorithm: Option<HashAlgorithm>,
+        hash_params: HashParams,
+        json: bool,
+    },
+}
+
+/// Users to add or delete in an htpasswd file, added users get a generated password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HtpasswdUpdate {
+    pub file: PathBuf,
+    pub users: Vec<String>,
+    pub delete: Vec<String>,
+}
+
+/// Users to print local account lines for, each with a generated password
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountsOutput {
+    pub format: AccountFormat,
+    pub users: Vec<String>,
 }
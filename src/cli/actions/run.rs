@@ -1,26 +1,102 @@
-use crate::cli::actions::Action;
+use crate::cli::actions::{htpasswd, shadow, Action};
 use crate::pwgen::{
     config::PasswordConfig,
-    generate_password,
-    hash::{hash_bcrypt, hash_pbkdf2, hash_sha512},
+    generator::PasswordGenerator,
+    hash::{hash, hash_peppered, HashError},
+    passphrase::{generate_passphrase_with_rng, PassphraseConfig},
+    pronounceable::PronounceableGenerator,
+    regex::{generate_from_regex_with_rng, RegexPattern},
 };
-use anyhow::{Error, Result};
+use anyhow::{bail, Result};
 use crossbeam::channel;
-use serde_json::json;
+use rand::{rng, rngs::StdRng, CryptoRng, Rng, SeedableRng};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    io::{self, BufWriter, Write},
+    num::NonZeroUsize,
+    panic::{self, AssertUnwindSafe},
+    thread,
+};
 use tokio::task;
 
+/// Password generator selected from the command line options
+#[derive(Debug, Clone)]
+enum Generator {
+    Password(Box<PasswordGenerator>),
+    Pronounceable(PronounceableGenerator),
+    Passphrase(PassphraseConfig),
+    Regex(RegexPattern),
+}
+
+impl Generator {
+    fn generate<R: Rng + CryptoRng + ?Sized>(&self, rng: &mut R) -> String {
+        match self {
+            Self::Password(generator) => generator.generate_with_rng(rng),
+            Self::Pronounceable(generator) => generator.generate_with_rng(rng),
+            Self::Passphrase(config) => generate_passphrase_with_rng(config, rng),
+            Self::Regex(pattern) => generate_from_regex_with_rng(pattern, rng),
+        }
+    }
+
+    /// Generates the password at `index`, with a seed every password gets its own reproducible RNG
+    ///
+    /// The RNG is seeded with the SHA-256 of both the seed and the index, so
+    /// consecutive seeds don't produce the same passwords shifted by one.
+    fn generate_nth(&self, seed: Option<u64>, index: usize) -> String {
+        seed.map_or_else(
+            || self.generate(&mut rng()),
+            |seed| {
+                let digest = Sha256::new()
+                    .chain_update(seed.to_le_bytes())
+                    .chain_update((index as u64).to_le_bytes())
+                    .finalize();
+                self.generate(&mut StdRng::from_seed(digest.into()))
+            },
+        )
+    }
+
+    /// Bits of entropy of the generated passwords, unknown for pronounceable ones
+    /// so `--entropy` conflicts with `--pronounceable`
+    fn entropy_bits(&self) -> Option<f64> {
+        match self {
+            Self::Password(generator) => Some(generator.config().entropy_bits()),
+            Self::Passphrase(config) => Some(config.entropy_bits()),
+            Self::Regex(pattern) => Some(pattern.entropy_bits()),
+            Self::Pronounceable(_) => None,
+        }
+    }
+}
+
+/// Generates the passwords on a blocking thread, out of the async runtime
 pub async fn handle(action: Action) -> Result<()> {
+    task::spawn_blocking(move || run(action)).await?
+}
+
+fn run(action: Action) -> Result<()> {
     let Action::Run {
         pw_length,
         num_pw,
         pin,
         alphanumeric,
-        bcrypt,
-        pbkdf2,
-        sha512,
+        pronounceable,
+        hash_algorithm,
+        hash_params,
+        pepper,
         charset,
+        pattern,
+        regex,
+        passphrase,
+        seed,
+        entropy,
+        htpasswd,
+        accounts,
         json, // Check for JSON flag
-    } = action;
+    } = action
+    else {
+        return Ok(());
+    };
 
     let config = if pin {
         PasswordConfig::pin(pw_length)?
@@ -32,80 +108,139 @@ pub async fn handle(action: Action) -> Result<()> {
         PasswordConfig::new(pw_length)?
     };
 
-    match config.validate() {
+    let config = match pattern {
+        Some(pattern) => config.with_pattern(pattern),
+        None => config,
+    };
+
+    let validation = config.validate();
+
+    let generator = if let Some(passphrase) = passphrase {
+        if let Err(e) = passphrase.validate() {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Generator::Passphrase(passphrase)
+    } else if let Some(regex) = regex {
+        Generator::Regex(RegexPattern::parse(&regex, pw_length)?)
+    } else if pronounceable {
+        match PronounceableGenerator::new(config) {
+            Ok(generator) => Generator::Pronounceable(generator),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    } else {
+        match PasswordGenerator::new(config) {
+            Ok(generator) => Generator::Password(Box::new(generator)),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+
+    let entropy_bits = generator.entropy_bits();
+
+    // Report out of range costs before generating any password
+    if let Some(algorithm) = hash_algorithm {
+        hash_params.validate(algorithm)?;
+    }
+
+    match validation {
         Ok(()) => {
-            //
+            if let Some(update) = htpasswd {
+                return htpasswd::handle(
+                    *update,
+                    |index| generator.generate_nth(seed, index),
+                    hash_algorithm,
+                    &hash_params,
+                    json,
+                );
+            }
+
+            if let Some(output) = accounts {
+                return shadow::handle(
+                    *output,
+                    |index| generator.generate_nth(seed, index),
+                    hash_algorithm,
+                    &hash_params,
+                    json,
+                );
+            }
+
+            let mut out = BufWriter::new(io::stdout().lock());
+
             if json {
-                print!("[");
+                write!(out, "[")?;
                 if num_pw == 0 {
-                    print!("]");
+                    write!(out, "]")?;
                 }
             }
 
-            // Create a crossbeam channel
-            let (tx, rx) = channel::bounded::<Result<(String, Option<String>), Error>>(32);
-
-            for _ in 0..num_pw {
-                let config = config.clone();
-                let tx = tx.clone();
-
-                task::spawn_blocking(move || {
-                    let result = {
-                        let password = generate_password(&config);
-
-                        // Apply hashing if requested
-                        let hashed = if bcrypt {
-                            hash_bcrypt(&password).ok()
-                        } else if pbkdf2 {
-                            hash_pbkdf2(&password).ok()
-                        } else if sha512 {
-                            hash_sha512(&password).ok()
-                        } else {
-                            None
-                        };
-
-                        Ok((password, hashed))
-                    };
-
-                    let _ = tx.send(result);
-                });
-            }
+            // Passwords whose hashing failed, reported with a non-zero exit code
+            let mut failed = 0;
+
+            let generate = |index| {
+                let password = generator.generate_nth(seed, index);
+
+                // Apply hashing if requested
+                let hashed = hash_algorithm
+                    .map(|algorithm| {
+                        pepper.as_ref().map_or_else(
+                            || hash(&password, algorithm, &hash_params),
+                            |pepper| hash_peppered(&password, algorithm, &hash_params, pepper),
+                        )
+                    })
+                    .transpose();
+
+                (password, hashed)
+            };
 
-            // Drop the sender to signal that no more messages will be sent
-            drop(tx);
+            let written = generate_ordered(num_pw, generate, |index, (password, hashed)| {
+                if hashed.is_err() {
+                    failed += 1;
+                }
 
-            // Track number of items processed for JSON formatting
-            let mut processed = 0;
+                if json {
+                    if index > 0 {
+                        write!(out, ",")?;
+                    }
+                    write!(out, "{}", to_json(&password, &hashed, entropy_bits))?;
 
-            // Process results as they arrive
-            while let Ok(result) = rx.recv() {
-                match result {
-                    Ok((password, hashed)) => {
-                        if json {
-                            if processed > 0 {
-                                print!(",");
+                    // If this is the last item, print the closing bracket
+                    if index + 1 == num_pw {
+                        writeln!(out, "]")?;
+                    }
+                } else {
+                    match hashed {
+                        Ok(hashed) => {
+                            write!(out, "{}", password)?;
+                            if let Some(hash) = hashed {
+                                write!(out, " {}", hash)?;
                             }
-                            let json_output = json!({
-                                "password": password,
-                                "hash": hashed
-                            });
-                            print!("{}", json_output);
-                            processed += 1;
-
-                            // If this is the last item, print the closing bracket
-                            if processed == num_pw {
-                                println!("]");
+                            if let (true, Some(bits)) = (entropy, entropy_bits) {
+                                write!(out, " {:.2}", bits)?;
                             }
-                        } else if let Some(hash) = hashed {
-                            println!("{} {}", password, hash);
-                        } else {
-                            println!("{}", password);
+                            writeln!(out)?;
                         }
-                    }
-                    Err(e) => {
-                        eprintln!("Error generating password: {}", e);
+                        Err(e) => eprintln!("Error: {}", e),
                     }
                 }
+
+                Ok(())
+            })
+            .and_then(|()| Ok(out.flush()?));
+
+            match written {
+                // The reader is gone, e.g. `pwgen2 18 1000000 | head`
+                Err(e) if is_broken_pipe(&e) => return Ok(()),
+                written => written?,
+            }
+
+            if failed > 0 {
+                bail!("Failed to hash {} of {} passwords", failed, num_pw);
             }
         }
         Err(e) => {
@@ -117,10 +252,126 @@ pub async fn handle(action: Action) -> Result<()> {
     Ok(())
 }
 
+/// Passwords generated by a worker at once, fewer when there are not enough for every worker
+const MAX_BATCH_SIZE: usize = 1024;
+
+/// Batches queued or waiting to be written per worker, bounds the memory of large runs
+const BATCHES_PER_WORKER: usize = 4;
+
+/// Runs `generate` for `0..count` on one worker thread per core and passes the
+/// results to `write` in index order, as soon as their turn comes
+///
+/// Indices are handed out in batches and only a few batches per worker are in
+/// flight at any time, so the memory use does not depend on `count`. A panic
+/// of `generate` is reported as an error as soon as its batch is received.
+fn generate_ordered<T, G, W>(count: usize, generate: G, mut write: W) -> Result<()>
+where
+    T: Send,
+    G: Fn(usize) -> T + Sync,
+    W: FnMut(usize, T) -> Result<()>,
+{
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    let window = workers * BATCHES_PER_WORKER;
+    let batch_size = (count / window).clamp(1, MAX_BATCH_SIZE);
+    let batches = count.div_ceil(batch_size);
+
+    thread::scope(|scope| {
+        // At most `window` batches are sent and not yet written, neither channel can block.
+        // Owned by the scope, an early return closes them and stops the workers.
+        let (batch_tx, batch_rx) = channel::bounded::<usize>(window);
+        let (result_tx, result_rx) = channel::bounded::<(usize, thread::Result<Vec<T>>)>(window);
+
+        for _ in 0..workers.min(batches) {
+            let batch_rx = batch_rx.clone();
+            let result_tx = result_tx.clone();
+            let generate = &generate;
+
+            scope.spawn(move || {
+                for batch in batch_rx {
+                    let start = batch * batch_size;
+                    let end = (start + batch_size).min(count);
+                    // A panic is sent as the result of the batch, the main loop can't wait for it forever
+                    let results = panic::catch_unwind(AssertUnwindSafe(|| {
+                        (start..end).map(generate).collect()
+                    }));
+                    if result_tx.send((batch, results)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut sent = 0;
+        while sent < batches.min(window) {
+            batch_tx.send(sent)?;
+            sent += 1;
+        }
+
+        // Batches are finished in any order, keep them until their turn comes
+        let mut pending = BTreeMap::new();
+        let mut written = 0;
+
+        while written < batches {
+            let (batch, results) = result_rx.recv()?;
+            let Ok(results) = results else {
+                bail!("A worker panicked while generating passwords");
+            };
+            pending.insert(batch, results);
+
+            while let Some(results) = pending.remove(&written) {
+                for (offset, result) in results.into_iter().enumerate() {
+                    write(written * batch_size + offset, result)?;
+                }
+                written += 1;
+
+                if sent < batches {
+                    batch_tx.send(sent)?;
+                    sent += 1;
+                }
+            }
+        }
+
+        Ok(())
+    })
+}
+
+/// Whether writing failed because the reading end of stdout was closed
+fn is_broken_pipe(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+/// JSON object of a generated password, with an `error` field when its hashing failed
+/// and an `entropy_bits` field when the entropy is known
+fn to_json(
+    password: &str,
+    hashed: &Result<Option<String>, HashError>,
+    entropy_bits: Option<f64>,
+) -> Value {
+    let mut value = match hashed {
+        Ok(hashed) => json!({
+            "password": password,
+            "hash": hashed,
+        }),
+        Err(e) => json!({
+            "password": password,
+            "hash": null,
+            "error": e.to_string(),
+        }),
+    };
+    if let Some(bits) = entropy_bits {
+        value["entropy_bits"] = json!(bits);
+    }
+    value
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::cli::actions::Action;
+    use crate::pwgen::hash::{HashAlgorithm, HashParams};
 
     #[tokio::test]
     async fn test_handle() {
@@ -129,10 +380,18 @@ mod tests {
             num_pw: 1,
             pin: false,
             alphanumeric: false,
-            bcrypt: false,
-            pbkdf2: false,
-            sha512: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
             charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
             json: false,
         };
 
@@ -147,10 +406,18 @@ mod tests {
             num_pw: 1,
             pin: true,
             alphanumeric: false,
-            bcrypt: false,
-            pbkdf2: false,
-            sha512: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
             charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
             json: false,
         };
 
@@ -165,10 +432,18 @@ mod tests {
             num_pw: 1,
             pin: false,
             alphanumeric: true,
-            bcrypt: false,
-            pbkdf2: false,
-            sha512: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
             charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
             json: false,
         };
 
@@ -183,14 +458,377 @@ mod tests {
             num_pw: 1,
             pin: false,
             alphanumeric: false,
-            bcrypt: false,
-            pbkdf2: false,
-            sha512: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: false,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_passphrase() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 2,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: Some(PassphraseConfig::new(4).unwrap()),
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: true,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_pronounceable() {
+        let action = Action::Run {
+            pw_length: 12,
+            num_pw: 1,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: true,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
             charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
             json: false,
         };
 
         let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_pattern() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 1,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: Some("~".to_string()),
+            pattern: Some("Aaaa-9999-!!".parse().unwrap()),
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: false,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_regex() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 2,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: Some(r"[a-z]{4}\d+".to_string()),
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: true,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_regex_invalid() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 1,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: Some("[a-z".to_string()),
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: false,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_handle_seed() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 3,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: None,
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: Some(42),
+            entropy: true,
+            htpasswd: None,
+            accounts: None,
+            json: false,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[test]
+    fn test_generator_seed() {
+        let generator = Generator::Password(Box::new(
+            PasswordGenerator::new(PasswordConfig::new(18).unwrap()).unwrap(),
+        ));
+
+        let a = generator.generate(&mut StdRng::seed_from_u64(7));
+        let b = generator.generate(&mut StdRng::seed_from_u64(7));
+
+        assert_eq!(a, b);
+
+        // Consecutive seeds are not the same sequence shifted by one
+        assert_eq!(
+            generator.generate_nth(Some(7), 1),
+            generator.generate_nth(Some(7), 1)
+        );
+        assert_ne!(
+            generator.generate_nth(Some(8), 0),
+            generator.generate_nth(Some(7), 1)
+        );
+        assert_ne!(
+            generator.generate_nth(Some(7), 0),
+            generator.generate_nth(Some(7), 1)
+        );
+    }
+
+    #[test]
+    fn test_generator_entropy_bits() {
+        let generator = Generator::Password(Box::new(
+            PasswordGenerator::new(PasswordConfig::new(18).unwrap()).unwrap(),
+        ));
+        assert!(generator.entropy_bits().unwrap() > 80.0);
+
+        let generator = Generator::Pronounceable(
+            PronounceableGenerator::new(PasswordConfig::new(18).unwrap()).unwrap(),
+        );
+        assert!(generator.entropy_bits().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_handle_argon2() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 2,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: Some(HashAlgorithm::Argon2),
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: true,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_yescrypt() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 1,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: Some(HashAlgorithm::Yescrypt),
+            hash_params: HashParams::new(),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: false,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_handle_invalid_cost() {
+        let action = Action::Run {
+            pw_length: 18,
+            num_pw: 1,
+            pin: false,
+            alphanumeric: false,
+            pronounceable: false,
+            hash_algorithm: Some(HashAlgorithm::Bcrypt),
+            hash_params: HashParams::new().with_cost(32),
+            pepper: None,
+            charset: None,
+            pattern: None,
+            regex: None,
+            passphrase: None,
+            seed: None,
+            entropy: false,
+            htpasswd: None,
+            accounts: None,
+            json: false,
+        };
+
+        let rs = handle(action).await;
+        assert!(rs.is_err());
+    }
+
+    #[test]
+    fn test_to_json_error() {
+        let error = HashError::Hashing {
+            algorithm: HashAlgorithm::Scrypt,
+            reason: "out of memory".to_string(),
+        };
+
+        assert_eq!(
+            to_json("password", &Err(error), Some(52.0)),
+            json!({
+                "password": "password",
+                "hash": null,
+                "error": "Failed to hash password using scrypt: out of memory",
+                "entropy_bits": 52.0
+            })
+        );
+        assert_eq!(
+            to_json("password", &Ok(None), None),
+            json!({"password": "password", "hash": null})
+        );
+    }
+
+    #[test]
+    fn test_generate_ordered() {
+        for count in [0, 1, 7, 10_000] {
+            let mut written = Vec::new();
+            generate_ordered(
+                count,
+                |index| {
+                    // Uneven work so batches finish out of order
+                    if index % 97 == 0 {
+                        thread::sleep(std::time::Duration::from_millis(1));
+                    }
+                    index * 2
+                },
+                |index, value| {
+                    written.push((index, value));
+                    Ok(())
+                },
+            )
+            .unwrap();
+
+            assert_eq!(written, (0..count).map(|i| (i, i * 2)).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn test_generate_ordered_panic() {
+        let rs = generate_ordered(
+            10_000,
+            |index| {
+                assert_ne!(index, 5000, "generation failed");
+                index
+            },
+            |_, _| Ok(()),
+        );
+
+        assert_eq!(
+            rs.unwrap_err().to_string(),
+            "A worker panicked while generating passwords"
+        );
+    }
+
+    #[test]
+    fn test_generate_ordered_write_error() {
+        let mut written = 0;
+        let rs = generate_ordered(
+            100_000,
+            |index| index,
+            |index, _| {
+                if index == 5 {
+                    bail!("Broken pipe");
+                }
+                written += 1;
+                Ok(())
+            },
+        );
+
         assert!(rs.is_err());
+        assert_eq!(written, 5);
     }
 }
@@ -0,0 +1,175 @@
+use crate::cli::actions::Action;
+use crate::pwgen::hash::{needs_rehash, HashAlgorithm, RehashPolicy};
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader},
+};
+
+/// Lists the stored hashes below the policy, returns whether any was found
+pub fn handle(action: Action) -> Result<bool> {
+    let Action::Scan {
+        file,
+        hash_algorithm,
+        hash_params,
+        json,
+    } = action
+    else {
+        return Ok(false);
+    };
+
+    // An algorithm option makes it the only accepted one, e.g. to migrate to it
+    let policy = match hash_algorithm {
+        Some(algorithm) => RehashPolicy::new()
+            .with_algorithms(&[algorithm])
+            .with_params(algorithm, &hash_params)?,
+        None => RehashPolicy::new(),
+    };
+
+    let reader: Box<dyn BufRead> = if file.as_os_str() == "-" {
+        Box::new(io::stdin().lock())
+    } else {
+        let input =
+            File::open(&file).with_context(|| format!("Failed to open {}", file.display()))?;
+        Box::new(BufReader::new(input))
+    };
+
+    let mut found = false;
+    let mut weak = Vec::new();
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("Failed to read {}", file.display()))?;
+        let record = line.trim_end();
+
+        // Empty lines and comments are skipped
+        if record.is_empty() || record.starts_with('#') {
+            continue;
+        }
+
+        let hashed = find_hash(record);
+        match needs_rehash(hashed, &policy) {
+            Ok(true) => {
+                found = true;
+                if json {
+                    weak.push(to_json(index + 1, record, hashed));
+                } else {
+                    println!("{}", record);
+                }
+            }
+            Ok(false) => {}
+            Err(e) => eprintln!("Line {}: {}", index + 1, e),
+        }
+    }
+
+    if json {
+        println!("{}", Value::Array(weak));
+    }
+
+    Ok(found)
+}
+
+/// Returns the hash of a `user:hash` record, like htpasswd or shadow lines, or the line itself
+///
+/// The `!` prefixes of locked shadow entries are skipped, a locked hash is still weak.
+fn find_hash(record: &str) -> &str {
+    let mut start = 0;
+
+    for field in record.split(':') {
+        let unlocked = field.trim_start_matches('!');
+        let rest = &record[start + field.len() - unlocked.len()..];
+        // Werkzeug and Keycloak hashes are only detected with the fields after them
+        if let Some(algorithm) =
+            HashAlgorithm::detect(unlocked).or_else(|| HashAlgorithm::detect(rest))
+        {
+            return &rest[..hash_len(algorithm, rest)];
+        }
+        start += field.len() + 1;
+    }
+
+    record
+}
+
+/// Length of the hash at the start of a record, some hashes contain `:`
+fn hash_len(algorithm: HashAlgorithm, rest: &str) -> usize {
+    let fields = match algorithm {
+        // A JSON credential is the whole record
+        HashAlgorithm::Keycloak => return rest.len(),
+        // `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>`
+        HashAlgorithm::ScramSha256 => 3,
+        // `pbkdf2:<digest>[:<iterations>]$<salt>$<key>`
+        HashAlgorithm::Werkzeug => {
+            rest.split('$')
+                .next()
+                .unwrap_or_default()
+                .matches(':')
+                .count()
+                + 1
+        }
+        _ => 1,
+    };
+
+    rest.split(':')
+        .take(fields)
+        .map(|field| field.len() + 1)
+        .sum::<usize>()
+        - 1
+}
+
+fn to_json(line: usize, record: &str, hashed: &str) -> Value {
+    json!({
+        "line": line,
+        "algorithm": HashAlgorithm::detect(hashed).map(HashAlgorithm::name),
+        "record": record
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_hash() {
+        assert_eq!(find_hash("$2b$12$hash"), "$2b$12$hash");
+        assert_eq!(find_hash("alice:$apr1$salt$hash"), "$apr1$salt$hash");
+        assert_eq!(
+            find_hash("bob:$6$salt$hash:19000:0:99999:7:::"),
+            "$6$salt$hash"
+        );
+        assert_eq!(find_hash("carol:!:19000"), "carol:!:19000");
+        assert_eq!(
+            find_hash("carol:!$6$salt$hash:19000:0:99999:7:::"),
+            "$6$salt$hash"
+        );
+        assert_eq!(find_hash("carol:!!$1$salt$hash"), "$1$salt$hash");
+        assert_eq!(find_hash("!$2b$12$hash"), "$2b$12$hash");
+        assert_eq!(
+            find_hash("dave:SCRAM-SHA-256$4096:c2FsdA==$a2V5:a2V5"),
+            "SCRAM-SHA-256$4096:c2FsdA==$a2V5:a2V5"
+        );
+        assert_eq!(
+            find_hash("SCRAM-SHA-256$4096:c2FsdA==$a2V5:a2V5"),
+            "SCRAM-SHA-256$4096:c2FsdA==$a2V5:a2V5"
+        );
+        assert_eq!(
+            find_hash("erin:pbkdf2:sha256:600000$salt$key:extra"),
+            "pbkdf2:sha256:600000$salt$key"
+        );
+        assert_eq!(
+            find_hash("pbkdf2:sha256$salt$key"),
+            "pbkdf2:sha256$salt$key"
+        );
+        assert_eq!(
+            find_hash(r#"{"credentialData":"{}","type":"password"}"#),
+            r#"{"credentialData":"{}","type":"password"}"#
+        );
+    }
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json(3, "alice:$apr1$salt$hash", "$apr1$salt$hash"),
+            json!({"line": 3, "algorithm": "apr1", "record": "alice:$apr1$salt$hash"})
+        );
+    }
+}
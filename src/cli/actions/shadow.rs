@@ -0,0 +1,78 @@
+use crate::cli::actions::AccountsOutput;
+use crate::pwgen::{
+    hash::{hash, HashAlgorithm, HashParams},
+    shadow::{days_since_epoch, validate_algorithm, validate_username, DEFAULT_ALGORITHM},
+};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// Prints an account line per user with a generated password, the plaintext
+/// credentials go to stderr so stdout can be piped to `chpasswd -e`
+pub fn handle(
+    output: AccountsOutput,
+    generate: impl Fn(usize) -> String,
+    hash_algorithm: Option<HashAlgorithm>,
+    hash_params: &HashParams,
+    json: bool,
+) -> Result<()> {
+    let AccountsOutput { format, mut users } = output;
+
+    for user in &users {
+        validate_username(user)?;
+    }
+
+    // A user listed twice gets a single password
+    let mut seen = HashSet::new();
+    users.retain(|user| seen.insert(user.clone()));
+
+    let algorithm = hash_algorithm.unwrap_or(DEFAULT_ALGORITHM);
+    validate_algorithm(algorithm)?;
+    hash_params.validate(algorithm)?;
+
+    let last_change = days_since_epoch();
+
+    // Every password is hashed before printing, a failure prints no partial batch
+    let mut accounts = Vec::with_capacity(users.len());
+    for (index, user) in users.iter().enumerate() {
+        let password = generate(index);
+        let line = format.line(user, &hash(&password, algorithm, hash_params)?, last_change);
+        accounts.push((user, password, line));
+    }
+
+    if json {
+        let entries: Vec<Value> = accounts
+            .iter()
+            .map(|(user, password, line)| to_json(user, password, line))
+            .collect();
+        println!("{}", Value::Array(entries));
+    } else {
+        for (user, password, line) in &accounts {
+            println!("{}", line);
+            eprintln!("{}:{}", user, password);
+        }
+    }
+
+    Ok(())
+}
+
+fn to_json(user: &str, password: &str, line: &str) -> Value {
+    json!({
+        "user": user,
+        "password": password,
+        "line": line
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_json() {
+        assert_eq!(
+            to_json("alice", "secret", "alice:$6$salt$hash"),
+            json!({"user": "alice", "password": "secret", "line": "alice:$6$salt$hash"})
+        );
+    }
+}
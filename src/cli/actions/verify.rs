@@ -0,0 +1,53 @@
+use crate::cli::actions::{input::read_password, Action};
+use crate::pwgen::hash::{verify, verify_peppered, HashAlgorithm, HashError};
+use anyhow::Result;
+use serde_json::json;
+
+/// Exit status of `verify` on errors, 1 is kept for a password that doesn't match
+pub const ERROR_EXIT_CODE: i32 = 2;
+
+/// Verifies a password against a hash, returns whether they match
+pub fn handle(action: Action) -> Result<bool> {
+    let Action::Verify { hash, pepper, json } = action else {
+        return Ok(false);
+    };
+
+    let algorithm = HashAlgorithm::detect(&hash).ok_or(HashError::UnknownFormat)?;
+    let password = read_password()?;
+    let matches = match &pepper {
+        Some(pepper) => verify_peppered(&password, &hash, pepper)?,
+        None => verify(&password, &hash)?,
+    };
+
+    if json {
+        println!(
+            "{}",
+            json!({
+                "algorithm": algorithm.name(),
+                "match": matches
+            })
+        );
+    } else if matches {
+        println!("Password matches the {} hash", algorithm);
+    } else {
+        println!("Password does not match the {} hash", algorithm);
+    }
+
+    Ok(matches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle_unknown_format() {
+        let action = Action::Verify {
+            hash: "$3$abc$def".to_string(),
+            pepper: None,
+            json: false,
+        };
+
+        assert!(handle(action).is_err());
+    }
+}
@@ -1,8 +1,9 @@
+use crate::pwgen::{shadow::AccountFormat, Pattern};
 use clap::{
     builder::styling::{AnsiColor, Effects, Styles},
     Arg, ArgAction, ArgGroup, ColorChoice, Command,
 };
-use std::env;
+use std::{env, path::PathBuf};
 
 pub fn new() -> Command {
     let styles = Styles::styled()
@@ -11,7 +12,7 @@ pub fn new() -> Command {
         .literal(AnsiColor::Blue.on_default() | Effects::BOLD)
         .placeholder(AnsiColor::Green.on_default());
 
-    Command::new("pwgen2")
+    let command = Command::new("pwgen2")
         .about("password generator")
         .version(env!("CARGO_PKG_VERSION"))
         .color(ColorChoice::Auto)
@@ -53,28 +54,127 @@ pub fn new() -> Command {
                 .action(ArgAction::SetTrue),
         )
         .arg(
-            Arg::new("bcrypt")
-                .short('b')
-                .long("bcrypt")
-                .help("Hash the generated password using Bcrypt")
+            Arg::new("pronounceable")
+                .long("pronounceable")
+                .help("Generate a pronounceable password")
                 .num_args(0)
-                .action(ArgAction::SetTrue),
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["pin", "words"]),
+        )
+        .arg(
+            Arg::new("pattern")
+                .long("pattern")
+                .help("Generate a password matching a template, e.g. Aaaa-9999-!!")
+                .value_name("template")
+                .value_parser(|s: &str| Pattern::parse(s).map_err(|e| e.to_string()))
+                .conflicts_with_all(["pin", "alphanumeric", "pronounceable", "words"]),
+        )
+        .arg(
+            Arg::new("regex")
+                .long("regex")
+                .help("Generate a password matching a regular expression, length is the maximum length")
+                .value_name("regex")
+                .conflicts_with_all([
+                    "pin",
+                    "alphanumeric",
+                    "pronounceable",
+                    "pattern",
+                    "words",
+                    "charset",
+                ]),
+        )
+        .arg(
+            Arg::new("words")
+                .short('w')
+                .long("words")
+                .help("Generate a passphrase with the given number of words")
+                .value_name("N")
+                .value_parser(clap::value_parser!(u8).range(1..)),
+        )
+        .arg(
+            Arg::new("separator")
+                .long("separator")
+                .help("Separator placed between passphrase words")
+                .value_name("separator")
+                .default_value("-")
+                .requires("words"),
         )
         .arg(
-            Arg::new("pbkdf2")
-                .short('k')
-                .long("pbkdf2")
-                .help("Hash the generated password using PBKDF2")
+            Arg::new("capitalize")
+                .long("capitalize")
+                .help("Capitalize every passphrase word")
                 .num_args(0)
-                .action(ArgAction::SetTrue),
+                .action(ArgAction::SetTrue)
+                .requires("words"),
         )
         .arg(
-            Arg::new("sha512")
-                .short('s')
-                .long("sha512")
-                .help("Hash the generated password using SHA512")
+            Arg::new("append-digit")
+                .long("append-digit")
+                .help("Append a random digit to the passphrase")
                 .num_args(0)
-                .action(ArgAction::SetTrue),
+                .action(ArgAction::SetTrue)
+                .requires("words"),
+        )
+        .arg(
+            Arg::new("append-symbol")
+                .long("append-symbol")
+                .help("Append a random symbol to the passphrase")
+                .num_args(0)
+                .action(ArgAction::SetTrue)
+                .requires("words"),
+        )
+        .arg(
+            Arg::new("wordlist")
+                .long("wordlist")
+                .help("Word list file for passphrases, one word per line (EFF diceware format accepted)")
+                .value_name("file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("words"),
+        )
+        .args(hash_args())
+        .arg(
+            Arg::new("htpasswd")
+                .long("htpasswd")
+                .help("Create or update an htpasswd file with a generated password per user")
+                .value_name("file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("htpasswd-users")
+                .conflicts_with("number"),
+        )
+        .arg(
+            Arg::new("user")
+                .long("user")
+                .help("User to generate a password for, can be repeated or comma-separated")
+                .value_name("name")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .requires("users-output"),
+        )
+        .arg(
+            Arg::new("users-file")
+                .long("users-file")
+                .help("File of users to generate a password for, one per line")
+                .value_name("file")
+                .value_parser(clap::value_parser!(PathBuf))
+                .requires("users-output"),
+        )
+        .arg(
+            Arg::new("delete")
+                .long("delete")
+                .help("User to delete from the htpasswd file, can be repeated or comma-separated")
+                .value_name("name")
+                .action(ArgAction::Append)
+                .value_delimiter(',')
+                .requires("htpasswd"),
+        )
+        .arg(
+            Arg::new("format")
+                .long("format")
+                .help("Print chpasswd -e lines or /etc/shadow entries for the users, passwords go to stderr")
+                .value_name("format")
+                .value_parser(|s: &str| s.parse::<AccountFormat>())
+                .requires("users")
+                .conflicts_with("number"),
         )
         .arg(
             Arg::new("charset")
@@ -84,32 +184,342 @@ pub fn new() -> Command {
                 .value_name("symbols")
                 .required(false),
         )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .help("Seed the RNG to get reproducible passwords, never use it for real passwords")
+                .value_name("seed")
+                .value_parser(clap::value_parser!(u64))
+                .hide(true),
+        )
+        .arg(
+            Arg::new("entropy")
+                .short('e')
+                .long("entropy")
+                .help("Show the bits of entropy of the generated passwords")
+                .conflicts_with("pronounceable")
+                .num_args(0)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("json")
                 .short('j')
                 .long("json")
                 .help("Output as JSON")
                 .num_args(0)
-                .action(ArgAction::SetTrue),
+                .action(ArgAction::SetTrue)
+                .global(true),
         )
         .group(
             ArgGroup::new("password-type")
-                .args(["pin", "alphanumeric", "charset"])
+                .args(["pin", "alphanumeric", "charset", "words"])
                 .required(false),
         )
         .group(
-            ArgGroup::new("hash-type")
-                .args(["bcrypt", "pbkdf2", "sha512"])
-                .required(false),
+            ArgGroup::new("htpasswd-users")
+                .args(["user", "users-file", "delete"])
+                .multiple(true),
+        )
+        .group(
+            ArgGroup::new("users")
+                .args(["user", "users-file"])
+                .multiple(true),
         )
+        // Users are written to an htpasswd file or printed as account lines
+        .group(ArgGroup::new("users-output").args(["htpasswd", "format"]))
+        .groups(hash_groups(false))
+        .args(pepper_args())
+        // A pepper only applies to the hashes printed with the passwords
+        .group(
+            pepper_group()
+                .requires("hash-type")
+                .conflicts_with("users-output"),
+        )
+        .args_conflicts_with_subcommands(true)
+        .subcommand(
+            Command::new("check")
+                .about("Check the strength of passwords read from stdin, one per line"),
+        )
+        .subcommand(
+            Command::new("calibrate")
+                .about("Time the hash algorithms and recommend costs for a target latency")
+                .arg(
+                    Arg::new("target")
+                        .short('t')
+                        .long("target")
+                        .help("Target time of one hash in milliseconds")
+                        .value_name("ms")
+                        .default_value("250")
+                        .value_parser(clap::value_parser!(u64).range(1..)),
+                ),
+        )
+        .subcommand(
+            Command::new("verify")
+                .about("Verify a password against a hash, the password is read from stdin or prompted for")
+                .after_help("Exit status: 0 if the password matches, 1 if it doesn't, 2 on errors like an invalid hash")
+                .arg(
+                    Arg::new("hash")
+                        .help("Hash to verify, the algorithm is detected from its prefix")
+                        .required(true),
+                )
+                .args(pepper_args())
+                .group(pepper_group()),
+        )
+        .subcommand(
+            Command::new("hash")
+                .about("Hash passwords read from stdin, one per line, or prompted for")
+                .args(hash_args())
+                .groups(hash_groups(true))
+                .args(pepper_args())
+                .group(pepper_group()),
+        )
+        .subcommand(
+            Command::new("scan")
+                .about("List the stored hashes that need a rehash, a hash option sets the target algorithm and cost")
+                .arg(
+                    Arg::new("file")
+                        .help("File of hashes or user:hash records, one per line, - reads stdin")
+                        .value_parser(clap::value_parser!(PathBuf))
+                        .required(true),
+                )
+                .args(hash_args())
+                .groups(hash_groups(false)),
+        );
+
+    #[cfg(feature = "openssl")]
+    let command = command.arg(
+        Arg::new("fips")
+            .long("fips")
+            .help("Hash with the OpenSSL FIPS provider, only FIPS approved algorithms are allowed")
+            .num_args(0)
+            .action(ArgAction::SetTrue)
+            .global(true),
+    );
+
+    command
+}
+
+/// Options selecting a hash algorithm and its cost, shared by the generator, `hash` and `scan`
+fn hash_args() -> [Arg; 25] {
+    [
+        Arg::new("bcrypt")
+            .short('b')
+            .long("bcrypt")
+            .help("Hash the password using Bcrypt")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("pbkdf2")
+            .short('k')
+            .long("pbkdf2")
+            .help("Hash the password using PBKDF2")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("sha512")
+            .short('s')
+            .long("sha512")
+            .help("Hash the password using SHA512")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("argon2")
+            .long("argon2")
+            .help("Hash the password using Argon2id")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("scrypt")
+            .long("scrypt")
+            .help("Hash the password using scrypt")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("yescrypt")
+            .long("yescrypt")
+            .help("Hash the password using yescrypt, the /etc/shadow default")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("apr1")
+            .long("apr1")
+            .help("Hash the password using APR1-MD5 (htpasswd -m)")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("md5-crypt")
+            .long("md5-crypt")
+            .help("Hash the password using MD5 crypt (openssl passwd -1, legacy)")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("sha256")
+            .long("sha256")
+            .help("Hash the password using SHA256 crypt")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("scram-sha256")
+            .long("scram-sha256")
+            .help("Hash the password into a PostgreSQL SCRAM-SHA-256 verifier")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("mysql-native")
+            .long("mysql-native")
+            .help("Hash the password using MySQL mysql_native_password (legacy)")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("mysql-sha2")
+            .long("mysql-sha2")
+            .help("Hash the password using MySQL caching_sha2_password")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("ssha")
+            .long("ssha")
+            .help("Hash the password using the LDAP {SSHA} scheme")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("ssha512")
+            .long("ssha512")
+            .help("Hash the password using the LDAP {SSHA512} scheme")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("ldap-crypt")
+            .long("ldap-crypt")
+            .help("Hash the password using the LDAP {CRYPT} scheme with SHA512 crypt")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("django")
+            .long("django")
+            .help("Hash the password using Django's pbkdf2_sha256 format")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("werkzeug")
+            .long("werkzeug")
+            .help("Hash the password using Werkzeug's pbkdf2:sha256 format (Flask)")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("phpass")
+            .long("phpass")
+            .help("Hash the password using the phpass $P$ format of WordPress (legacy)")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("keycloak")
+            .long("keycloak")
+            .help("Hash the password into a Keycloak pbkdf2-sha512 credential (JSON)")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("mosquitto")
+            .long("mosquitto")
+            .help("Hash the password using the $7$ PBKDF2-SHA512 format of mosquitto_passwd")
+            .num_args(0)
+            .action(ArgAction::SetTrue),
+        Arg::new("cost")
+            .long("cost")
+            .help("Cost of bcrypt (4-31), scrypt (log2 of N), yescrypt (1-11) or phpass (7-30)")
+            .value_name("cost")
+            .value_parser(clap::value_parser!(u32))
+            .requires("cost-hash"),
+        Arg::new("rounds")
+            .long("rounds")
+            .help("Rounds of PBKDF2, SHA crypt, SCRAM-SHA-256 and caching_sha2_password, or Argon2 iterations")
+            .value_name("rounds")
+            .value_parser(clap::value_parser!(u32))
+            .requires("rounds-hash"),
+        Arg::new("memory")
+            .long("memory")
+            .help("Memory of Argon2 in KiB")
+            .value_name("KiB")
+            .value_parser(clap::value_parser!(u32))
+            .requires("argon2-hash"),
+        Arg::new("parallelism")
+            .long("parallelism")
+            .help("Degree of parallelism of Argon2")
+            .value_name("lanes")
+            .value_parser(clap::value_parser!(u32))
+            .requires("argon2-hash"),
+        Arg::new("pbkdf2-alg")
+            .long("pbkdf2-alg")
+            .help("Pseudo-random function of PBKDF2")
+            .value_name("alg")
+            .value_parser(["sha256", "sha512"])
+            .requires("pbkdf2-hash"),
+    ]
+}
+
+/// Options of the server-side pepper, shared by the generator, `hash` and `verify`
+fn pepper_args() -> [Arg; 3] {
+    [
+        Arg::new("pepper-file")
+            .long("pepper-file")
+            .help("Hash the HMAC-SHA256 of the password keyed with the pepper in this file")
+            .value_name("file")
+            .value_parser(clap::value_parser!(PathBuf)),
+        Arg::new("pepper-env")
+            .long("pepper-env")
+            .help("Hash the HMAC-SHA256 of the password keyed with the pepper in this variable")
+            .value_name("var"),
+        Arg::new("pepper-encoding")
+            .long("pepper-encoding")
+            .help("Encoding of the HMAC that is hashed")
+            .value_name("encoding")
+            .value_parser(["base64", "hex"])
+            .default_value("base64")
+            .requires("pepper"),
+    ]
+}
+
+/// The pepper is read from a file or from the environment
+fn pepper_group() -> ArgGroup {
+    ArgGroup::new("pepper").args(["pepper-file", "pepper-env"])
+}
+
+/// Groups of the hash options, `required` makes one algorithm mandatory
+fn hash_groups(required: bool) -> [ArgGroup; 5] {
+    [
+        ArgGroup::new("hash-type")
+            .args([
+                "bcrypt",
+                "pbkdf2",
+                "sha512",
+                "argon2",
+                "scrypt",
+                "yescrypt",
+                "apr1",
+                "md5-crypt",
+                "sha256",
+                "scram-sha256",
+                "mysql-native",
+                "mysql-sha2",
+                "ssha",
+                "ssha512",
+                "ldap-crypt",
+                "django",
+                "werkzeug",
+                "phpass",
+                "keycloak",
+                "mosquitto",
+            ])
+            .required(required),
+        ArgGroup::new("cost-hash").args(["bcrypt", "scrypt", "yescrypt", "phpass"]),
+        ArgGroup::new("rounds-hash").args([
+            "pbkdf2",
+            "sha512",
+            "sha256",
+            "argon2",
+            "scram-sha256",
+            "mysql-sha2",
+            "ldap-crypt",
+            "django",
+            "werkzeug",
+            "keycloak",
+            "mosquitto",
+        ]),
+        // Flags always have a value, only a group tells whether they were passed
+        ArgGroup::new("argon2-hash").arg("argon2"),
+        ArgGroup::new("pbkdf2-hash").arg("pbkdf2"),
+    ]
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::pwgen::{hash::verify, htpasswd::Htpasswd};
     use anyhow::Result;
     use assert_cmd::Command;
     use predicates::prelude::*;
+    use sha_crypt::sha256_check;
 
     #[test]
     fn test_help() {
@@ -161,7 +571,8 @@ mod tests {
         assert_eq!(m.get_one::<usize>("number").copied(), Some(1));
         assert!(!m.get_flag("pin"));
         assert!(!m.get_flag("alphanumeric"));
-        assert_eq!(m.get_one::<String>("charset").as_deref(), None);
+        assert_eq!(m.get_one::<String>("charset"), None);
+        assert_eq!(m.get_one::<u8>("words"), None);
 
         Ok(())
     }
@@ -285,4 +696,858 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_create_passphrase() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["-w", "5", "--capitalize"]).assert();
+
+        assert.stdout(predicate::str::is_match(r"^([A-Z][a-z]+-){4}[A-Z][a-z]+\n$").unwrap());
+    }
+
+    #[test]
+    fn test_options_words() -> Result<()> {
+        let matches = new().try_get_matches_from(vec![
+            "pwgen2",
+            "-w",
+            "4",
+            "--separator",
+            " ",
+            "--append-digit",
+        ]);
+
+        assert!(matches.is_ok());
+
+        let m = matches?;
+
+        assert_eq!(m.get_one::<u8>("words").copied(), Some(4));
+        assert_eq!(
+            m.get_one::<String>("separator").map(|s| s.to_string()),
+            Some(String::from(" "))
+        );
+        assert!(m.get_flag("append-digit"));
+        assert!(!m.get_flag("append-symbol"));
+        assert!(!m.get_flag("capitalize"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_options_only_words_or_pin() -> Result<()> {
+        let matches = new().try_get_matches_from(vec!["pwgen2", "-w", "4", "-p"]);
+
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_options_passphrase_requires_words() -> Result<()> {
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--capitalize"]);
+
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_pronounceable() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--pronounceable", "-a", "12"]).assert();
+
+        assert.stdout(predicate::str::is_match(r"^[a-zA-Z0-9]{12}\n$").unwrap());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd.args(["--pronounceable", "--json"]).output().unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["password"].as_str().unwrap().len(), 18);
+        assert!(json[0].get("entropy_bits").is_none());
+    }
+
+    #[test]
+    fn test_options_pronounceable() -> Result<()> {
+        let m = new().try_get_matches_from(vec!["pwgen2", "--pronounceable", "-a"])?;
+
+        assert!(m.get_flag("pronounceable"));
+        assert!(m.get_flag("alphanumeric"));
+
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--pronounceable", "-p"]);
+        assert!(matches.is_err());
+
+        // The entropy of pronounceable passwords is not computed
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--pronounceable", "-e"]);
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_pattern() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--pattern", "Aaaa-9{4}-[xyz]"]).assert();
+
+        assert.stdout(predicate::str::is_match(r"^[A-Z][a-z]{3}-[0-9]{4}-[xyz]\n$").unwrap());
+    }
+
+    #[test]
+    fn test_options_pattern() -> Result<()> {
+        let m = new().try_get_matches_from(vec!["pwgen2", "--pattern", "Aaaa-9999-!!"])?;
+
+        assert_eq!(m.get_one::<Pattern>("pattern").map(|p| p.len()), Some(12));
+
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--pattern", "a[bc"]);
+        assert!(matches.is_err());
+
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--pattern", "a", "-p"]);
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_regex() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--regex", r"(foo|bar)-\d{4}"]).assert();
+
+        assert.stdout(predicate::str::is_match(r"^(foo|bar)-[0-9]{4}\n$").unwrap());
+    }
+
+    #[test]
+    fn test_create_regex_invalid() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["--regex", "[a-z"]).assert().failure();
+    }
+
+    #[test]
+    fn test_options_regex() -> Result<()> {
+        let m = new().try_get_matches_from(vec!["pwgen2", "--regex", "[a-z]+", "12"])?;
+
+        assert_eq!(
+            m.get_one::<String>("regex").map(|s| s.to_string()),
+            Some(String::from("[a-z]+"))
+        );
+        assert_eq!(m.get_one::<u8>("length").copied(), Some(12));
+
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--regex", "a", "-c", "~"]);
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_seed() {
+        let run = |seed| {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            let output = cmd.args(["--seed", seed, "18", "5"]).output().unwrap();
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let first = run("42");
+        assert_eq!(first.lines().count(), 5);
+        assert_eq!(first, run("42"));
+
+        let next = run("43");
+        assert!(first.lines().all(|password| !next.contains(password)));
+    }
+
+    #[test]
+    fn test_seed_is_hidden() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.arg("--help").assert();
+
+        assert.stdout(predicate::str::contains("--seed").not());
+    }
+
+    #[test]
+    fn test_entropy() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["-e", "-p", "6"]).assert();
+
+        assert.stdout(predicate::str::is_match(r"^\d{6} 19\.93\n$").unwrap());
+    }
+
+    #[test]
+    fn test_entropy_json() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd.args(["-j", "18", "2"]).output().unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        for entry in json.as_array().unwrap() {
+            assert!(entry["entropy_bits"].as_f64().unwrap() >= 80.0);
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd
+            .arg("check")
+            .write_stdin("password\n\nLb*vTVcDniz7q6Ki-P\n")
+            .assert();
+
+        assert
+            .success()
+            .stdout(predicate::str::contains("password\n  score: 0/4\n"))
+            .stdout(predicate::str::contains(
+                "Lb*vTVcDniz7q6Ki-P\n  score: 4/4\n",
+            ));
+    }
+
+    #[test]
+    fn test_check_json() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["check", "--json"])
+            .write_stdin("qwerty\n2024\n")
+            .output()
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["score"], 0);
+        assert_eq!(json[0]["warning"], "This is a top-10 common password");
+        assert_eq!(json[1]["sequence"][0]["pattern"], "year");
+    }
+
+    #[test]
+    fn test_calibrate_json() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["calibrate", "--target", "1", "--json"])
+            .output()
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        let algorithms: Vec<&str> = json
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["algorithm"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            algorithms,
+            ["bcrypt", "pbkdf2", "sha512", "argon2", "scrypt", "yescrypt"]
+        );
+        // A 1 ms target is below every minimum cost
+        assert_eq!(json[0]["value"], 4);
+        assert_eq!(json[3]["memory_kib"], 19456);
+        assert_eq!(json[4]["memory_kib"], 1024);
+        // OpenSSL's SHA-512 crypt can run the minimum rounds in less than 1 ms
+        #[cfg(not(feature = "openssl"))]
+        assert_eq!(json[2]["value"], 1000);
+    }
+
+    #[test]
+    fn test_verify() {
+        let hashed = "$5$saltstring$OH4IDuTlsuTYPdED1gsuiRMyTAwNlRWyA6Xr3I4/dQ5";
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", hashed])
+            .write_stdin("password\n")
+            .assert()
+            .success()
+            .stdout("Password matches the sha256 hash\n");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", hashed])
+            .write_stdin("wrong\n")
+            .assert()
+            .code(1)
+            .stdout("Password does not match the sha256 hash\n");
+    }
+
+    #[test]
+    fn test_verify_json() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "verify",
+            "--json",
+            "$y$j75$k2XAnEHBqQ1Ct2aMXFKNa/$m4lwJ4nFEuCl0FFCrU4dJtyuhT0Ai2jNWLnkYlySGEB",
+        ])
+        .write_stdin("password")
+        .assert()
+        .success()
+        .stdout("{\"algorithm\":\"yescrypt\",\"match\":true}\n");
+    }
+
+    #[test]
+    fn test_verify_unknown_format() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", "plaintext"])
+            .write_stdin("plaintext\n")
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("Unknown hash format"));
+
+        // Errors exit with 2, unlike a password that doesn't match
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", "$6$rounds=x$salt$hash"])
+            .write_stdin("password\n")
+            .assert()
+            .code(2);
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args([
+            "verify",
+            "$5$salt$hash",
+            "--pepper-file",
+            "/nonexistent/pepper",
+        ])
+        .write_stdin("password\n")
+        .assert()
+        .code(2);
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", "--help"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains(
+                "Exit status: 0 if the password matches",
+            ));
+    }
+
+    #[test]
+    fn test_hash() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["hash", "--sha256", "--rounds", "1000"])
+            .write_stdin("first password\n\nsecond\n")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let hashes: Vec<&str> = stdout.lines().collect();
+        assert_eq!(hashes.len(), 2);
+        assert!(hashes[0].starts_with("$5$rounds=1000$"));
+        assert!(sha256_check("first password", hashes[0]).is_ok());
+        assert!(sha256_check("second", hashes[1]).is_ok());
+    }
+
+    #[test]
+    fn test_hash_json() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["hash", "--apr1", "--json"])
+            .write_stdin("password\n")
+            .output()
+            .unwrap();
+
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json[0]["algorithm"], "apr1");
+        assert!(json[0]["hash"].as_str().unwrap().starts_with("$apr1$"));
+    }
+
+    #[test]
+    fn test_hash_md5_crypt() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["hash", "--md5-crypt"])
+            .write_stdin("password\n")
+            .output()
+            .unwrap();
+
+        let hashed = String::from_utf8(output.stdout).unwrap();
+        assert!(hashed.starts_with("$1$"));
+        assert!(verify("password", hashed.trim()).unwrap());
+    }
+
+    #[test]
+    fn test_hash_pepper() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args([
+                "hash",
+                "--bcrypt",
+                "--cost",
+                "4",
+                "--pepper-env",
+                "PWGEN2_PEPPER",
+            ])
+            .env("PWGEN2_PEPPER", "pepper")
+            .write_stdin("password\n")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        let hashed = String::from_utf8(output.stdout).unwrap();
+        let hashed = hashed.trim_end();
+        assert!(!verify("password", hashed).unwrap());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", hashed, "--pepper-env", "PWGEN2_PEPPER"])
+            .env("PWGEN2_PEPPER", "pepper")
+            .write_stdin("password\n")
+            .assert()
+            .success()
+            .stdout("Password matches the bcrypt hash\n");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", hashed, "--pepper-env", "PWGEN2_PEPPER"])
+            .env("PWGEN2_PEPPER", "salt")
+            .write_stdin("password\n")
+            .assert()
+            .code(1);
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["hash", "--bcrypt", "--pepper-env", "PWGEN2_PEPPER_UNSET"])
+            .write_stdin("password\n")
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("PWGEN2_PEPPER_UNSET is not set"));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["hash", "--bcrypt", "--pepper-encoding", "hex"])
+            .assert()
+            .failure();
+    }
+
+    #[test]
+    fn test_hash_database() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["hash", "--scram-sha256", "--rounds", "4096"])
+            .write_stdin("password\n")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        let hashed = String::from_utf8(output.stdout).unwrap();
+        assert!(hashed.starts_with("SCRAM-SHA-256$4096:"));
+        assert!(verify("password", hashed.trim_end()).unwrap());
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["hash", "--mysql-sha2", "--rounds", "5500"])
+            .write_stdin("password\n")
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "Invalid caching_sha2_password rounds 5500, they must be a multiple of 1000.",
+            ));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["verify", "{SSHA}yrht1iYXEIkejLVu42JWkadd80RzYWx0c2FsdA=="])
+            .write_stdin("password\n")
+            .assert()
+            .success()
+            .stdout("Password matches the ssha hash\n");
+    }
+
+    #[test]
+    fn test_hash_frameworks() {
+        for (flag, prefix) in [
+            ("--django", "pbkdf2_sha256$1000$"),
+            ("--werkzeug", "pbkdf2:sha256:1000$"),
+            ("--mosquitto", "$7$1000$"),
+            ("--keycloak", "{\"credentialData\":"),
+        ] {
+            let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+            let output = cmd
+                .args(["hash", flag, "--rounds", "1000"])
+                .write_stdin("password\n")
+                .output()
+                .unwrap();
+
+            assert!(output.status.success());
+            let hashed = String::from_utf8(output.stdout).unwrap();
+            assert!(hashed.starts_with(prefix), "{}", hashed);
+            assert!(verify("password", hashed.trim_end()).unwrap());
+        }
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["hash", "--phpass", "--cost", "6"])
+            .write_stdin("password\n")
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains(
+                "Invalid phpass cost 6, it must be between 7 and 30.",
+            ));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["scan", "-"])
+            .write_stdin("admin:$P$9IQRaTwmfeRo7ud9Fh4E2PdI0S3r.L0\n")
+            .assert()
+            .code(1)
+            .stdout("admin:$P$9IQRaTwmfeRo7ud9Fh4E2PdI0S3r.L0\n");
+    }
+
+    #[test]
+    fn test_options_hash() {
+        assert!(new().try_get_matches_from(["pwgen2", "hash"]).is_err());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "-b", "-s"])
+            .is_err());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "-s", "--cost", "10"])
+            .is_err());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "-b", "--cost", "10"])
+            .is_ok());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "--ssha", "--rounds", "1000"])
+            .is_err());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "--ldap-crypt", "--rounds", "1000"])
+            .is_ok());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "--phpass", "--rounds", "1000"])
+            .is_err());
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "--phpass", "--cost", "8"])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_options_check() -> Result<()> {
+        let m = new().try_get_matches_from(vec!["pwgen2", "check", "-j"])?;
+
+        let (name, sub) = m.subcommand().expect("check subcommand");
+        assert_eq!(name, "check");
+        assert!(sub.get_flag("json"));
+
+        let matches = new().try_get_matches_from(vec!["pwgen2", "-p", "check"]);
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_argon2() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--argon2", "-a"]).assert();
+
+        assert.stdout(
+            predicate::str::is_match(r"^[a-zA-Z0-9]{18} \$argon2id\$v=19\$m=19456,t=2,p=1\$\S+\n$")
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_scrypt() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--scrypt", "-a"]).assert();
+
+        assert.stdout(
+            predicate::str::is_match(r"^[a-zA-Z0-9]{18} \$scrypt\$ln=17,r=8,p=1\$\S+\n$").unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_yescrypt() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--yescrypt", "-a"]).assert();
+
+        assert.stdout(
+            predicate::str::is_match(
+                r"^[a-zA-Z0-9]{18} \$y\$j9T\$[./0-9A-Za-z]{22}\$[./0-9A-Za-z]{43}\n$",
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_apr1() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--apr1", "-a"]).assert();
+
+        assert.stdout(
+            predicate::str::is_match(
+                r"^[a-zA-Z0-9]{18} \$apr1\$[./0-9A-Za-z]{8}\$[./0-9A-Za-z]{22}\n$",
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_sha256() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["--sha256", "-a"]).assert();
+
+        assert.stdout(
+            predicate::str::is_match(
+                r"^[a-zA-Z0-9]{18} \$5\$[./0-9A-Za-z]{16}\$[./0-9A-Za-z]{43}\n$",
+            )
+            .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_cost() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["-b", "--cost", "5", "-a"]).assert();
+
+        assert.stdout(predicate::str::is_match(r"^[a-zA-Z0-9]{18} \$2b\$05\$\S{53}\n$").unwrap());
+    }
+
+    #[test]
+    fn test_cost_out_of_range() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd.args(["-b", "--cost", "32"]).assert();
+
+        assert.failure().stderr(predicate::str::contains(
+            "Invalid bcrypt cost 32, it must be between 4 and 31.",
+        ));
+    }
+
+    #[test]
+    fn test_rounds() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let assert = cmd
+            .args(["-k", "--rounds", "1000", "--pbkdf2-alg", "sha512", "-a"])
+            .assert();
+
+        assert.stdout(
+            predicate::str::is_match(r"^[a-zA-Z0-9]{18} \$pbkdf2-sha512\$i=1000,l=32\$\S+\n$")
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_options_hash_params_require_hash() {
+        for args in [
+            vec!["pwgen2", "--cost", "10"],
+            vec!["pwgen2", "--cost", "10", "-s"],
+            vec!["pwgen2", "--rounds", "10000", "-b"],
+            vec!["pwgen2", "--pbkdf2-alg", "sha512", "-s"],
+            vec!["pwgen2", "-k", "--pbkdf2-alg", "md5"],
+        ] {
+            assert!(new().try_get_matches_from(args).is_err());
+        }
+    }
+
+    #[test]
+    fn test_options_only_one_hash() -> Result<()> {
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--argon2", "-b"]);
+
+        assert!(matches.is_err());
+
+        let matches = new().try_get_matches_from(vec!["pwgen2", "--scrypt", "--yescrypt"]);
+
+        assert!(matches.is_err());
+
+        Ok(())
+    }
+
+    const SCAN_INPUT: &str = "# stored hashes
+alice:$5$saltstring$OH4IDuTlsuTYPdED1gsuiRMyTAwNlRWyA6Xr3I4/dQ5
+bob:$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1
+$2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie
+carol:!:19000
+dave:!!$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1:19000:0:99999:7:::
+";
+
+    #[test]
+    fn test_scan() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["scan", "-"])
+            .write_stdin(SCAN_INPUT)
+            .assert()
+            .code(1)
+            .stdout(
+                "bob:$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1\n\
+                 $2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie\n\
+                 dave:!!$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1:19000:0:99999:7:::\n",
+            )
+            .stderr("Line 5: Unknown hash format\n");
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["scan", "-", "--bcrypt", "--cost", "4"])
+            .write_stdin("$2b$04$EGdrhbKUv8Oc9vGiXX0HQOxSg445d458Muh7DAHskb6QbtCvdxcie\n")
+            .assert()
+            .success()
+            .stdout("");
+    }
+
+    #[test]
+    fn test_scan_json() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args(["scan", "-", "--sha256", "--json"])
+            .write_stdin(SCAN_INPUT)
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(1));
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 3);
+        assert_eq!(json[0]["line"], 3);
+        assert_eq!(json[0]["algorithm"], "apr1");
+        assert_eq!(json[1]["algorithm"], "bcrypt");
+        assert_eq!(json[2]["line"], 6);
+        assert_eq!(json[2]["algorithm"], "apr1");
+    }
+
+    #[test]
+    fn test_scan_missing_file() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["scan", "/nonexistent/hashes.txt"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "Failed to open /nonexistent/hashes.txt",
+            ));
+    }
+
+    #[test]
+    fn test_htpasswd() {
+        let path = std::env::temp_dir().join(format!("pwgen2-htpasswd-cli-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let file = path.to_str().unwrap();
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args([
+                "-a",
+                "12",
+                "--apr1",
+                "--htpasswd",
+                file,
+                "--user",
+                "alice,bob",
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let htpasswd = Htpasswd::load(&path).unwrap();
+        assert_eq!(htpasswd.users().collect::<Vec<_>>(), ["alice", "bob"]);
+        for line in stdout.lines() {
+            let (user, password) = line.split_once(':').unwrap();
+            assert_eq!(password.len(), 12);
+            assert!(verify(password, htpasswd.get(user).unwrap()).unwrap());
+        }
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["--htpasswd", file, "-b", "--cost", "4", "--user", "alice"])
+            .args(["--delete", "bob", "--json"])
+            .assert()
+            .success()
+            .stdout(predicate::str::starts_with("[{\"password\":"));
+
+        let htpasswd = Htpasswd::load(&path).unwrap();
+        assert_eq!(htpasswd.users().collect::<Vec<_>>(), ["alice"]);
+        assert!(htpasswd.get("alice").unwrap().starts_with("$2y$04$"));
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["--htpasswd", file, "--argon2", "--user", "carol"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "htpasswd files don't support argon2",
+            ));
+        assert_eq!(Htpasswd::load(&path).unwrap(), htpasswd);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_options_htpasswd() {
+        let m = new().try_get_matches_from(["pwgen2", "--htpasswd", ".htpasswd"]);
+        assert!(m.is_err());
+
+        let m = new().try_get_matches_from(["pwgen2", "--user", "alice"]);
+        assert!(m.is_err());
+
+        let m = new().try_get_matches_from([
+            "pwgen2",
+            "--htpasswd",
+            ".htpasswd",
+            "--user",
+            "alice",
+            "18",
+            "3",
+        ]);
+        assert!(m.is_err());
+
+        let m = new()
+            .try_get_matches_from([
+                "pwgen2",
+                "--htpasswd",
+                ".htpasswd",
+                "--user",
+                "a,b",
+                "--user",
+                "c",
+            ])
+            .unwrap();
+        assert_eq!(
+            m.get_many::<String>("user").unwrap().collect::<Vec<_>>(),
+            ["a", "b", "c"]
+        );
+    }
+
+    #[test]
+    fn test_format_chpasswd() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        let output = cmd
+            .args([
+                "--format",
+                "chpasswd",
+                "--user",
+                "alice,bob",
+                "--rounds",
+                "1000",
+                "-s",
+            ])
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+
+        let stdout = String::from_utf8(output.stdout).unwrap();
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        for (line, credentials) in stdout.lines().zip(stderr.lines()) {
+            let (user, hashed) = line.split_once(':').unwrap();
+            let (name, password) = credentials.split_once(':').unwrap();
+            assert_eq!(user, name);
+            assert!(hashed.starts_with("$6$rounds=1000$"));
+            assert!(verify(password, hashed).unwrap());
+        }
+        assert_eq!(stdout.lines().count(), 2);
+    }
+
+    #[test]
+    fn test_format_shadow() {
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.env("SOURCE_DATE_EPOCH", "1700000000")
+            .args([
+                "--format", "shadow", "--user", "alice", "--sha256", "--json",
+            ])
+            .assert()
+            .success()
+            .stdout(
+                predicate::str::is_match(r#""line":"alice:\$5\$[^:]+:19675:0:99999:7:::""#)
+                    .unwrap(),
+            );
+
+        let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
+        cmd.args(["--format", "shadow", "--user", "alice", "--argon2"])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(
+                "argon2 hashes are not crypt hashes",
+            ));
+    }
+
+    #[test]
+    fn test_options_format() {
+        let m = new().try_get_matches_from(["pwgen2", "--format", "shadow"]);
+        assert!(m.is_err());
+
+        let m = new().try_get_matches_from(["pwgen2", "--format", "passwd", "--user", "alice"]);
+        assert!(m.is_err());
+
+        let m = new().try_get_matches_from([
+            "pwgen2",
+            "--format",
+            "shadow",
+            "--htpasswd",
+            ".htpasswd",
+            "--user",
+            "alice",
+        ]);
+        assert!(m.is_err());
+
+        let m = new().try_get_matches_from(["pwgen2", "--format", "chpasswd", "--delete", "bob"]);
+        assert!(m.is_err());
+    }
 }
@@ -1,20 +1,200 @@
-use crate::cli::actions::Action;
-use anyhow::Result;
+use crate::cli::actions::{AccountsOutput, Action, HtpasswdUpdate};
+use crate::pwgen::{
+    calibrate::DEFAULT_TARGET,
+    hash::{HashAlgorithm, HashParams, Pbkdf2Algorithm},
+    passphrase::{load_word_list, PassphraseConfig},
+    pepper::{Pepper, PepperEncoding},
+    shadow::AccountFormat,
+    Pattern,
+};
+use anyhow::{Context, Result};
+use std::{fs, path::PathBuf};
 
 pub fn handler(matches: &clap::ArgMatches) -> Result<Action> {
+    match matches.subcommand() {
+        Some(("check", sub_matches)) => {
+            return Ok(Action::Check {
+                json: sub_matches.get_flag("json"),
+            })
+        }
+        Some(("calibrate", sub_matches)) => {
+            return Ok(Action::Calibrate {
+                target_ms: sub_matches
+                    .get_one::<u64>("target")
+                    .copied()
+                    .unwrap_or(DEFAULT_TARGET.as_millis() as u64),
+                json: sub_matches.get_flag("json"),
+            })
+        }
+        Some(("verify", sub_matches)) => {
+            return Ok(Action::Verify {
+                hash: sub_matches
+                    .get_one::<String>("hash")
+                    .cloned()
+                    .unwrap_or_default(),
+                pepper: pepper(sub_matches)?,
+                json: sub_matches.get_flag("json"),
+            })
+        }
+        Some(("hash", sub_matches)) => {
+            return Ok(Action::Hash {
+                hash_algorithm: hash_algorithm(sub_matches)
+                    .context("A hash algorithm is required")?,
+                hash_params: hash_params(sub_matches)?,
+                pepper: pepper(sub_matches)?,
+                json: sub_matches.get_flag("json"),
+            })
+        }
+        Some(("scan", sub_matches)) => {
+            return Ok(Action::Scan {
+                file: sub_matches
+                    .get_one::<PathBuf>("file")
+                    .cloned()
+                    .unwrap_or_else(|| PathBuf::from("-")),
+                hash_algorithm: hash_algorithm(sub_matches),
+                hash_params: hash_params(sub_matches)?,
+                json: sub_matches.get_flag("json"),
+            })
+        }
+        _ => {}
+    }
+
+    let passphrase = match matches.get_one::<u8>("words").copied() {
+        Some(words) => {
+            let mut config = PassphraseConfig::new(words)?
+                .with_capitalize(matches.get_flag("capitalize"))
+                .with_digit(matches.get_flag("append-digit"))
+                .with_symbol(matches.get_flag("append-symbol"));
+
+            if let Some(separator) = matches.get_one::<String>("separator") {
+                config = config.with_separator(separator.as_str());
+            }
+
+            if let Some(path) = matches.get_one::<PathBuf>("wordlist") {
+                let words = load_word_list(path)
+                    .with_context(|| format!("Failed to read word list {}", path.display()))?;
+                config = config.with_word_list(words);
+            }
+
+            Some(config)
+        }
+        None => None,
+    };
+
     Ok(Action::Run {
         pw_length: matches.get_one::<u8>("length").copied().unwrap_or(18),
         num_pw: matches.get_one::<usize>("number").copied().unwrap_or(1),
         pin: matches.get_flag("pin"),
         alphanumeric: matches.get_flag("alphanumeric"),
-        bcrypt: matches.get_flag("bcrypt"),
-        pbkdf2: matches.get_flag("pbkdf2"),
-        sha512: matches.get_flag("sha512"),
+        pronounceable: matches.get_flag("pronounceable"),
+        hash_algorithm: hash_algorithm(matches),
+        hash_params: hash_params(matches)?,
+        pepper: pepper(matches)?,
         charset: matches.get_one::<String>("charset").map(|s| s.to_string()),
+        pattern: matches.get_one::<Pattern>("pattern").cloned(),
+        regex: matches.get_one::<String>("regex").map(|s| s.to_string()),
+        passphrase,
+        seed: matches.get_one::<u64>("seed").copied(),
+        entropy: matches.get_flag("entropy"),
+        htpasswd: htpasswd_update(matches)?,
+        accounts: match matches.get_one::<AccountFormat>("format").copied() {
+            Some(format) => Some(Box::new(AccountsOutput {
+                format,
+                users: users(matches)?,
+            })),
+            None => None,
+        },
         json: matches.get_flag("json"),
     })
 }
 
+/// Every algorithm has a flag of the same name, at most one can be set
+fn hash_algorithm(matches: &clap::ArgMatches) -> Option<HashAlgorithm> {
+    HashAlgorithm::ALL
+        .into_iter()
+        .find(|algorithm| matches.get_flag(algorithm.name()))
+}
+
+fn hash_params(matches: &clap::ArgMatches) -> Result<HashParams> {
+    let mut hash_params = HashParams::new();
+    if let Some(cost) = matches.get_one::<u32>("cost").copied() {
+        hash_params = hash_params.with_cost(cost);
+    }
+    if let Some(rounds) = matches.get_one::<u32>("rounds").copied() {
+        hash_params = hash_params.with_rounds(rounds);
+    }
+    if let Some(memory) = matches.get_one::<u32>("memory").copied() {
+        hash_params = hash_params.with_memory(memory);
+    }
+    if let Some(parallelism) = matches.get_one::<u32>("parallelism").copied() {
+        hash_params = hash_params.with_parallelism(parallelism);
+    }
+    if let Some(algorithm) = matches.get_one::<String>("pbkdf2-alg") {
+        hash_params = hash_params.with_pbkdf2_algorithm(algorithm.parse::<Pbkdf2Algorithm>()?);
+    }
+
+    Ok(hash_params)
+}
+
+/// Pepper key of `--pepper-file` or `--pepper-env`, with its `--pepper-encoding`
+fn pepper(matches: &clap::ArgMatches) -> Result<Option<Pepper>> {
+    let pepper = if let Some(path) = matches.get_one::<PathBuf>("pepper-file") {
+        Pepper::from_file(path)?
+    } else if let Some(var) = matches.get_one::<String>("pepper-env") {
+        Pepper::from_env(var)?
+    } else {
+        return Ok(None);
+    };
+
+    let encoding = match matches.get_one::<String>("pepper-encoding") {
+        Some(encoding) => encoding.parse()?,
+        None => PepperEncoding::default(),
+    };
+    Ok(Some(pepper.with_encoding(encoding)))
+}
+
+/// Users to add or delete in the `--htpasswd` file
+fn htpasswd_update(matches: &clap::ArgMatches) -> Result<Option<Box<HtpasswdUpdate>>> {
+    let Some(file) = matches.get_one::<PathBuf>("htpasswd") else {
+        return Ok(None);
+    };
+
+    Ok(Some(Box::new(HtpasswdUpdate {
+        file: file.clone(),
+        users: users(matches)?,
+        delete: matches
+            .get_many::<String>("delete")
+            .unwrap_or_default()
+            .cloned()
+            .collect(),
+    })))
+}
+
+/// Users given with `--user` followed by the ones of the users file
+fn users(matches: &clap::ArgMatches) -> Result<Vec<String>> {
+    let mut users: Vec<String> = matches
+        .get_many::<String>("user")
+        .unwrap_or_default()
+        .cloned()
+        .collect();
+
+    if let Some(path) = matches.get_one::<PathBuf>("users-file") {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read users file {}", path.display()))?;
+
+        // Empty lines and comments are skipped
+        users.extend(
+            content
+                .lines()
+                .map(str::trim)
+                .filter(|user| !user.is_empty() && !user.starts_with('#'))
+                .map(str::to_string),
+        );
+    }
+
+    Ok(users)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,24 +220,213 @@ mod tests {
                 num_pw,
                 pin,
                 alphanumeric,
-                bcrypt,
-                pbkdf2,
-                sha512,
+                pronounceable,
+                hash_algorithm,
+                hash_params,
+                pepper,
                 charset,
+                pattern,
+                regex,
+                passphrase,
+                seed,
+                entropy,
+                htpasswd,
+                accounts,
                 json,
             } => {
                 assert_eq!(pw_length, 18);
                 assert_eq!(num_pw, 1);
                 assert!(!pin);
                 assert!(!alphanumeric);
-                assert!(!bcrypt);
-                assert!(!pbkdf2);
-                assert!(!sha512);
+                assert!(!pronounceable);
+                assert!(hash_algorithm.is_none());
+                assert_eq!(hash_params, HashParams::new());
+                assert!(pepper.is_none());
                 assert!(charset.is_none());
+                assert!(pattern.is_none());
+                assert!(regex.is_none());
+                assert!(passphrase.is_none());
+                assert!(seed.is_none());
+                assert!(!entropy);
+                assert!(htpasswd.is_none());
+                assert!(accounts.is_none());
                 assert!(!json);
             }
+            _ => panic!("expected a run action"),
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_passphrase() -> Result<()> {
+        let m = new().try_get_matches_from(["pwgen2", "-w", "4", "--capitalize"])?;
+
+        let Action::Run { passphrase, .. } = handler(&m)? else {
+            panic!("expected a run action");
+        };
+
+        let passphrase = passphrase.expect("passphrase config");
+        assert_eq!(passphrase.words, 4);
+        assert_eq!(passphrase.separator, "-");
+        assert!(passphrase.capitalize);
+        assert!(!passphrase.append_digit);
+        assert!(passphrase.word_list.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_check() -> Result<()> {
+        let m = new().try_get_matches_from(["pwgen2", "check", "--json"])?;
+
+        assert!(matches!(handler(&m)?, Action::Check { json: true }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_calibrate() -> Result<()> {
+        let m = new().try_get_matches_from(["pwgen2", "calibrate"])?;
+        assert!(matches!(
+            handler(&m)?,
+            Action::Calibrate {
+                target_ms: 250,
+                json: false
+            }
+        ));
+
+        let m = new().try_get_matches_from(["pwgen2", "calibrate", "--target", "100", "-j"])?;
+        assert!(matches!(
+            handler(&m)?,
+            Action::Calibrate {
+                target_ms: 100,
+                json: true
+            }
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_verify() -> Result<()> {
+        let m = new().try_get_matches_from(["pwgen2", "verify", "$5$salt$hash", "-j"])?;
+
+        let Action::Verify { hash, json, .. } = handler(&m)? else {
+            panic!("expected a verify action");
+        };
+        assert_eq!(hash, "$5$salt$hash");
+        assert!(json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_hash() -> Result<()> {
+        let m = new().try_get_matches_from(["pwgen2", "hash", "--yescrypt", "--cost", "3"])?;
+
+        let Action::Hash {
+            hash_algorithm,
+            hash_params,
+            pepper,
+            json,
+        } = handler(&m)?
+        else {
+            panic!("expected a hash action");
+        };
+        assert_eq!(hash_algorithm, HashAlgorithm::Yescrypt);
+        assert_eq!(hash_params, HashParams::new().with_cost(3));
+        assert!(pepper.is_none());
+        assert!(!json);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_hash_params() -> Result<()> {
+        let m = new().try_get_matches_from([
+            "pwgen2",
+            "-k",
+            "--rounds",
+            "10000",
+            "--pbkdf2-alg",
+            "sha512",
+        ])?;
+
+        let Action::Run {
+            hash_algorithm,
+            hash_params,
+            ..
+        } = handler(&m)?
+        else {
+            panic!("expected Action::Run");
+        };
+        assert_eq!(hash_algorithm, Some(HashAlgorithm::Pbkdf2));
+        assert_eq!(
+            hash_params,
+            HashParams::new()
+                .with_rounds(10_000)
+                .with_pbkdf2_algorithm(Pbkdf2Algorithm::Sha512)
+        );
+
+        let m = new().try_get_matches_from([
+            "pwgen2",
+            "hash",
+            "--argon2",
+            "--memory",
+            "65536",
+            "--parallelism",
+            "4",
+        ])?;
+        let Action::Hash { hash_params, .. } = handler(&m)? else {
+            panic!("expected a hash action");
+        };
+        assert_eq!(
+            hash_params,
+            HashParams::new().with_memory(65536).with_parallelism(4)
+        );
+
+        assert!(new()
+            .try_get_matches_from(["pwgen2", "hash", "--bcrypt", "--memory", "65536"])
+            .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_handler_hash_algorithm() -> Result<()> {
+        for algorithm in HashAlgorithm::ALL {
+            let flag = format!("--{}", algorithm.name());
+            let m = new().try_get_matches_from(["pwgen2", flag.as_str()])?;
+
+            let Action::Run { hash_algorithm, .. } = handler(&m)? else {
+                panic!("expected Action::Run");
+            };
+            assert_eq!(hash_algorithm, Some(algorithm));
         }
 
         Ok(())
     }
+
+    #[test]
+    fn test_handler_scan() -> Result<()> {
+        let m =
+            new().try_get_matches_from(["pwgen2", "scan", "hashes.txt", "-b", "--cost", "12"])?;
+
+        let Action::Scan {
+            file,
+            hash_algorithm,
+            hash_params,
+            json,
+        } = handler(&m)?
+        else {
+            panic!("expected a scan action");
+        };
+        assert_eq!(file, PathBuf::from("hashes.txt"));
+        assert_eq!(hash_algorithm, Some(HashAlgorithm::Bcrypt));
+        assert_eq!(hash_params, HashParams::new().with_cost(12));
+        assert!(!json);
+
+        Ok(())
+    }
 }
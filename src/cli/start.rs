@@ -1,11 +1,27 @@
-use crate::cli::{actions::Action, commands, dispatch::handler};
+use crate::cli::{
+    actions::{verify, Action},
+    commands,
+    dispatch::handler,
+};
 use anyhow::Result;
 
 /// Start the CLI
 pub fn start() -> Result<Action> {
     let matches = commands::new().get_matches();
 
-    let action = handler(&matches)?;
+    #[cfg(feature = "openssl")]
+    if matches.get_flag("fips") {
+        crate::pwgen::openssl::enable_fips()?;
+    }
+
+    let action = match handler(&matches) {
+        // `verify` exits with 1 only for a password that doesn't match
+        Err(e) if matches.subcommand_name() == Some("verify") => {
+            eprintln!("Error: {:?}", e);
+            std::process::exit(verify::ERROR_EXIT_CODE);
+        }
+        action => action?,
+    };
 
     Ok(action)
 }
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Result};
+use md5::{Digest, Md5};
+use rand::Rng;
+
+use super::CRYPT_ITOA64 as ITOA64;
+
+const MAGIC: &str = "$apr1$";
+
+/// Magic of the original MD5 crypt of FreeBSD and glibc
+const MD5_MAGIC: &str = "$1$";
+
+/// Salt length of `htpasswd -m`, longer salts are truncated
+pub const SALT_LEN: usize = 8;
+
+const ROUNDS: usize = 1000;
+
+/// Returns a random salt of `SALT_LEN` characters from the crypt alphabet
+pub fn gensalt() -> String {
+    let mut rng = rand::rng();
+    (0..SALT_LEN)
+        .map(|_| ITOA64[rng.random_range(0..ITOA64.len())] as char)
+        .collect()
+}
+
+/// Hashes a password with Apache's MD5 crypt variant, `$apr1$salt$hash`
+pub fn hash(password: &[u8], salt: &str) -> String {
+    crypt(password, MAGIC, salt)
+}
+
+/// Hashes a password with the original MD5 crypt, `$1$salt$hash`
+pub fn md5_crypt(password: &[u8], salt: &str) -> String {
+    crypt(password, MD5_MAGIC, salt)
+}
+
+/// Reads the salt of a `$apr1$` hash
+pub fn salt(hashed: &str) -> Result<&str> {
+    salt_of(hashed, MAGIC).ok_or_else(|| anyhow!("Invalid APR1 hash"))
+}
+
+/// Reads the salt of a `$1$` hash
+pub fn md5_crypt_salt(hashed: &str) -> Result<&str> {
+    salt_of(hashed, MD5_MAGIC).ok_or_else(|| anyhow!("Invalid MD5 crypt hash"))
+}
+
+/// The two variants only differ by the magic, which is also hashed
+fn crypt(password: &[u8], magic: &str, salt: &str) -> String {
+    let salt = salt.split('$').next().unwrap_or_default();
+    let salt = &salt.as_bytes()[..salt.len().min(SALT_LEN)];
+
+    let alternate = Md5::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut ctx = Md5::new()
+        .chain_update(password)
+        .chain_update(magic)
+        .chain_update(salt);
+    let mut remaining = password.len();
+    while remaining > 0 {
+        let len = remaining.min(alternate.len());
+        ctx.update(&alternate[..len]);
+        remaining -= len;
+    }
+
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 == 1 {
+            ctx.update([0]);
+        } else {
+            ctx.update(&password[..1]);
+        }
+        i >>= 1;
+    }
+    let mut digest = ctx.finalize();
+
+    for i in 0..ROUNDS {
+        let mut ctx = Md5::new();
+        if i & 1 == 1 {
+            ctx.update(password);
+        } else {
+            ctx.update(digest);
+        }
+        if i % 3 != 0 {
+            ctx.update(salt);
+        }
+        if i % 7 != 0 {
+            ctx.update(password);
+        }
+        if i & 1 == 1 {
+            ctx.update(digest);
+        } else {
+            ctx.update(password);
+        }
+        digest = ctx.finalize();
+    }
+
+    let mut encoded = String::from(magic);
+    encoded.push_str(&String::from_utf8_lossy(salt));
+    encoded.push('$');
+    for (a, b, c) in [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)] {
+        let value = u32::from(digest[a]) << 16 | u32::from(digest[b]) << 8 | u32::from(digest[c]);
+        encode(&mut encoded, value, 4);
+    }
+    encode(&mut encoded, u32::from(digest[11]), 2);
+
+    encoded
+}
+
+fn salt_of<'a>(hashed: &'a str, magic: &str) -> Option<&'a str> {
+    hashed
+        .strip_prefix(magic)
+        .and_then(|rest| rest.split_once('$'))
+        .map(|(salt, _)| salt)
+}
+
+fn encode(encoded: &mut String, mut value: u32, chars: usize) {
+    for _ in 0..chars {
+        encoded.push(ITOA64[(value & 0x3f) as usize] as char);
+        value >>= 6;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash() {
+        // Computed with `openssl passwd -apr1`
+        assert_eq!(
+            hash(b"password", "abcdefgh"),
+            "$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1"
+        );
+        assert_eq!(hash(b"", "xx"), "$apr1$xx$aGHxR9NsG1buO7l/rFJjm1");
+        assert_eq!(
+            hash(
+                b"a very long password that is longer than sixteen bytes",
+                "r31.KY.."
+            ),
+            "$apr1$r31.KY..$Ww91CEBBBBLK2QKgBUMv/0"
+        );
+    }
+
+    #[test]
+    fn test_md5_crypt() {
+        // Computed with `openssl passwd -1`
+        assert_eq!(
+            md5_crypt(b"password", "saltsalt"),
+            "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/"
+        );
+        assert_eq!(md5_crypt(b"", "xx"), "$1$xx$Qiw/Pi26KfpLUDkeOIdG..");
+        assert_eq!(
+            md5_crypt(
+                b"a very long password that is longer than sixteen bytes",
+                "r31.KY.."
+            ),
+            "$1$r31.KY..$F1G.aiJwZGP/B6f7Wp9301"
+        );
+    }
+
+    #[test]
+    fn test_hash_long_salt() {
+        assert_eq!(
+            hash(b"password", "abcdefghijkl"),
+            hash(b"password", "abcdefgh")
+        );
+        assert_eq!(
+            hash(b"password", "abcdefgh$FBwExRW4dCc8aL.OvjpIE1"),
+            "$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1"
+        );
+    }
+
+    #[test]
+    fn test_salt() {
+        assert_eq!(salt("$apr1$xx$aGHxR9NsG1buO7l/rFJjm1").unwrap(), "xx");
+        assert!(salt("$1$xx$aGHxR9NsG1buO7l/rFJjm1").is_err());
+        assert_eq!(
+            md5_crypt_salt("$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/").unwrap(),
+            "saltsalt"
+        );
+        assert!(md5_crypt_salt("$apr1$xx$aGHxR9NsG1buO7l/rFJjm1").is_err());
+
+        let generated = gensalt();
+        assert_eq!(generated.len(), SALT_LEN);
+        assert!(generated.bytes().all(|c| ITOA64.contains(&c)));
+    }
+}
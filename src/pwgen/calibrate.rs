@@ -0,0 +1,357 @@
+use crate::pwgen::hash::{
+    hash, HashAlgorithm, HashParams, ARGON2_DEFAULT_ITERATIONS, ARGON2_DEFAULT_MEMORY,
+    BCRYPT_COSTS, PBKDF2_ROUNDS, SCRYPT_COSTS, SCRYPT_DEFAULT_R, SHA_CRYPT_ROUNDS,
+};
+use crate::pwgen::yescrypt;
+use anyhow::Result;
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+/// Default target latency of a hash
+pub const DEFAULT_TARGET: Duration = Duration::from_millis(250);
+
+/// Lowest scrypt cost tried, smaller ones are too fast to time reliably
+const SCRYPT_MIN_LOG_N: u32 = 10;
+
+/// Largest Argon2 memory tried in KiB, 1 GiB
+const ARGON2_MAX_MEMORY: u32 = 1024 * 1024;
+
+/// Hashes timed for every measure, their median is kept to smooth out the noise
+const TIMED_RUNS: usize = 5;
+
+/// Recommended setting for one algorithm
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Calibration {
+    pub algorithm: HashAlgorithm,
+    /// Name of the tuned parameter, as its CLI option
+    pub parameter: &'static str,
+    pub value: u32,
+    /// Memory used by the hash in KiB, for the memory-hard algorithms
+    pub memory_kib: Option<u32>,
+    /// Time taken by one hash with the recommended value
+    pub duration: Duration,
+}
+
+/// Times every tunable algorithm and recommends the costs closest to `target`
+/// without exceeding it, or the minimum cost if even that is slower
+///
+/// The memory of Argon2 is raised first, from its default up to 1 GiB, then its
+/// iterations. The memory of scrypt and yescrypt grows with their cost.
+///
+/// Every algorithm is warmed up with a first hash, then each measure is the
+/// median of several hashes so the recommendations are stable from run to run.
+pub fn calibrate(target: Duration) -> Result<Vec<Calibration>> {
+    let with_cost = |algorithm| {
+        let mut timer = timer(algorithm);
+        move |cost| timer(HashParams::new().with_cost(cost))
+    };
+    let with_rounds = |algorithm| {
+        let mut timer = timer(algorithm);
+        move |rounds| timer(HashParams::new().with_rounds(rounds))
+    };
+    let mut argon2 = timer(HashAlgorithm::Argon2);
+    let yescrypt_costs = u32::from(yescrypt::MIN_COST)..=u32::from(yescrypt::MAX_COST);
+
+    Ok(vec![
+        calibrate_exponential(
+            HashAlgorithm::Bcrypt,
+            BCRYPT_COSTS,
+            target,
+            with_cost(HashAlgorithm::Bcrypt),
+        )?,
+        calibrate_linear(
+            HashAlgorithm::Pbkdf2,
+            10_000,
+            PBKDF2_ROUNDS,
+            target,
+            with_rounds(HashAlgorithm::Pbkdf2),
+        )?,
+        calibrate_linear(
+            HashAlgorithm::Sha512,
+            5000,
+            SHA_CRYPT_ROUNDS,
+            target,
+            with_rounds(HashAlgorithm::Sha512),
+        )?,
+        calibrate_argon2(target, |memory, iterations| {
+            argon2(
+                HashParams::new()
+                    .with_memory(memory)
+                    .with_rounds(iterations),
+            )
+        })?,
+        with_memory(
+            calibrate_exponential(
+                HashAlgorithm::Scrypt,
+                SCRYPT_MIN_LOG_N..=*SCRYPT_COSTS.end(),
+                target,
+                with_cost(HashAlgorithm::Scrypt),
+            )?,
+            // 128 * r * N bytes
+            |log_n| (1 << log_n) * SCRYPT_DEFAULT_R / 8,
+        ),
+        with_memory(
+            calibrate_exponential(
+                HashAlgorithm::Yescrypt,
+                yescrypt_costs,
+                target,
+                with_cost(HashAlgorithm::Yescrypt),
+            )?,
+            |cost| yescrypt::memory_kib(cost as u8),
+        ),
+    ])
+}
+
+/// Sets the memory of a calibration from its recommended cost
+fn with_memory(calibration: Calibration, memory_kib: impl Fn(u32) -> u32) -> Calibration {
+    Calibration {
+        memory_kib: Some(memory_kib(calibration.value)),
+        ..calibration
+    }
+}
+
+/// Doubles the memory of Argon2 while a hash with the default iterations stays
+/// under `target`, then scales the iterations with that memory, never below the default
+fn calibrate_argon2(
+    target: Duration,
+    mut measure: impl FnMut(u32, u32) -> Result<Duration>,
+) -> Result<Calibration> {
+    let mut memory = ARGON2_DEFAULT_MEMORY;
+    while memory * 2 <= ARGON2_MAX_MEMORY
+        && measure(memory * 2, ARGON2_DEFAULT_ITERATIONS)? <= target
+    {
+        memory *= 2;
+    }
+
+    let calibration = calibrate_linear(
+        HashAlgorithm::Argon2,
+        ARGON2_DEFAULT_ITERATIONS,
+        ARGON2_DEFAULT_ITERATIONS..=u32::MAX,
+        target,
+        |iterations| measure(memory, iterations),
+    )?;
+
+    Ok(Calibration {
+        memory_kib: Some(memory),
+        ..calibration
+    })
+}
+
+/// Raises a cost that doubles the work at each step until a hash takes longer than `target`
+fn calibrate_exponential(
+    algorithm: HashAlgorithm,
+    costs: RangeInclusive<u32>,
+    target: Duration,
+    mut measure: impl FnMut(u32) -> Result<Duration>,
+) -> Result<Calibration> {
+    let mut value = *costs.start();
+    let mut duration = measure(value)?;
+
+    for cost in costs.skip(1) {
+        let next = measure(cost)?;
+        if next > target {
+            break;
+        }
+        (value, duration) = (cost, next);
+    }
+
+    Ok(Calibration {
+        algorithm,
+        parameter: "cost",
+        value,
+        memory_kib: None,
+        duration,
+    })
+}
+
+/// Scales a number of rounds from the time taken by `baseline` rounds
+///
+/// The extrapolation can overshoot, the rounds are scaled down again until a
+/// hash fits in `target` or the minimum is reached.
+fn calibrate_linear(
+    algorithm: HashAlgorithm,
+    baseline: u32,
+    range: RangeInclusive<u32>,
+    target: Duration,
+    mut measure: impl FnMut(u32) -> Result<Duration>,
+) -> Result<Calibration> {
+    let scale = |rounds: u32, elapsed: Duration| {
+        let elapsed = elapsed.max(Duration::from_micros(1));
+        let scaled = f64::from(rounds) * target.as_secs_f64() / elapsed.as_secs_f64();
+        (scaled as u32).clamp(*range.start(), *range.end())
+    };
+
+    let mut value = scale(baseline, measure(baseline)?);
+    let mut duration = measure(value)?;
+
+    while duration > target && value > *range.start() {
+        value = scale(value, duration).min(value - 1);
+        duration = measure(value)?;
+    }
+
+    Ok(Calibration {
+        algorithm,
+        parameter: "rounds",
+        value,
+        memory_kib: None,
+        duration,
+    })
+}
+
+/// Returns a function timing hashes of `algorithm` with the given parameters
+///
+/// The first hash only warms up the caches and the allocator, it isn't timed.
+/// Each call then returns the median duration of [`TIMED_RUNS`] hashes.
+fn timer(algorithm: HashAlgorithm) -> impl FnMut(HashParams) -> Result<Duration> {
+    let mut warm = false;
+    move |params| {
+        if !warm {
+            hash("calibrate", algorithm, &params)?;
+            warm = true;
+        }
+        let durations = (0..TIMED_RUNS)
+            .map(|_| {
+                let start = Instant::now();
+                hash("calibrate", algorithm, &params)?;
+                Ok(start.elapsed())
+            })
+            .collect::<Result<_>>()?;
+        Ok(median(durations))
+    }
+}
+
+/// Middle value of the durations, the upper one for an even count
+fn median(mut durations: Vec<Duration>) -> Duration {
+    durations.sort_unstable();
+    durations[durations.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulated hash taking `unit` per step of cost
+    fn exponential(unit: Duration) -> impl FnMut(u32) -> Result<Duration> {
+        move |cost| Ok(unit * 2u32.pow(cost))
+    }
+
+    #[test]
+    fn test_median() {
+        let ms = Duration::from_millis;
+        assert_eq!(median(vec![ms(9), ms(1), ms(3), ms(200), ms(4)]), ms(4));
+        assert_eq!(median(vec![ms(2), ms(1)]), ms(2));
+    }
+
+    #[test]
+    fn test_timer() {
+        let mut timer = timer(HashAlgorithm::Sha512);
+        let duration = timer(HashParams::new().with_rounds(1000)).unwrap();
+        assert!(duration > Duration::ZERO);
+        assert!(timer(HashParams::new().with_rounds(1)).is_err());
+    }
+
+    #[test]
+    fn test_calibrate_exponential() {
+        let calibration = calibrate_exponential(
+            HashAlgorithm::Bcrypt,
+            4..=31,
+            Duration::from_millis(250),
+            exponential(Duration::from_micros(100)),
+        )
+        .unwrap();
+
+        // 2^11 * 100µs = 204.8ms, 2^12 * 100µs = 409.6ms
+        assert_eq!(calibration.value, 11);
+        assert_eq!(calibration.duration, Duration::from_micros(204_800));
+    }
+
+    #[test]
+    fn test_calibrate_exponential_bounds() {
+        let slow = calibrate_exponential(
+            HashAlgorithm::Bcrypt,
+            4..=31,
+            Duration::from_millis(1),
+            exponential(Duration::from_millis(1)),
+        )
+        .unwrap();
+        assert_eq!(slow.value, 4);
+
+        let fast = calibrate_exponential(
+            HashAlgorithm::Yescrypt,
+            1..=11,
+            Duration::from_secs(10),
+            exponential(Duration::from_micros(1)),
+        )
+        .unwrap();
+        assert_eq!(fast.value, 11);
+    }
+
+    #[test]
+    fn test_calibrate_linear() {
+        let calibration = calibrate_linear(
+            HashAlgorithm::Pbkdf2,
+            10_000,
+            1000..=u32::MAX,
+            Duration::from_millis(250),
+            |rounds| Ok(Duration::from_micros(u64::from(rounds))),
+        )
+        .unwrap();
+
+        assert_eq!(calibration.value, 250_000);
+        assert_eq!(calibration.duration, Duration::from_millis(250));
+
+        let calibration = calibrate_linear(
+            HashAlgorithm::Sha512,
+            5000,
+            1000..=999_999_999,
+            Duration::from_micros(1),
+            |rounds| Ok(Duration::from_micros(u64::from(rounds))),
+        )
+        .unwrap();
+
+        assert_eq!(calibration.value, 1000);
+    }
+
+    #[test]
+    fn test_calibrate_linear_overshoot() {
+        // The baseline is cheaper per round than larger counts
+        let calibration = calibrate_linear(
+            HashAlgorithm::Pbkdf2,
+            10_000,
+            1000..=u32::MAX,
+            Duration::from_millis(250),
+            |rounds| {
+                let micros = u64::from(rounds) * if rounds > 10_000 { 2 } else { 1 };
+                Ok(Duration::from_micros(micros))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(calibration.value, 125_000);
+        assert!(calibration.duration <= Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_calibrate_argon2() {
+        // 1µs per MiB and iteration
+        let calibration = calibrate_argon2(Duration::from_millis(100), |memory, iterations| {
+            Ok(Duration::from_micros(u64::from(memory / 1024 * iterations)))
+        })
+        .unwrap();
+
+        // 2 * 38 MiB = 76µs .. 2 * 608 MiB = 1.2ms fit, the 1 GiB cap stops the doubling
+        assert_eq!(calibration.memory_kib, Some(ARGON2_DEFAULT_MEMORY * 32));
+        assert_eq!(calibration.value, 164);
+        assert_eq!(calibration.parameter, "rounds");
+
+        let calibration = calibrate_argon2(Duration::from_millis(10), |memory, iterations| {
+            Ok(Duration::from_micros(
+                u64::from(memory / 1024 * iterations) * 100,
+            ))
+        })
+        .unwrap();
+
+        // 2 * 38 MiB = 7.6ms fits, 2 * 76 MiB = 15.2ms does not
+        assert_eq!(calibration.memory_kib, Some(ARGON2_DEFAULT_MEMORY * 2));
+    }
+}
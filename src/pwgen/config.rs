@@ -1,11 +1,53 @@
+use crate::pwgen::{entropy::password_entropy, pattern::Pattern};
+
 /// Error type for password configuration validation
 #[derive(Debug)]
 pub enum PasswordConfigError {
     ZeroLength,
     NoCharacterSetsEnabled,
-    LengthTooShortForSets { length: u8, sets_count: u8 },
-    NotEnoughAvailableCharacters { length: u8, available: u8 },
+    LengthTooShortForSets {
+        length: u8,
+        sets_count: u8,
+    },
+    NotEnoughAvailableCharacters {
+        length: u8,
+        available: u8,
+    },
     PinLengthTooShort,
+    ZeroWords,
+    WordListTooSmall {
+        available: usize,
+    },
+    EmptyPattern,
+    PatternTooLong,
+    UnclosedCharacterClass {
+        position: usize,
+    },
+    EmptyCharacterClass {
+        position: usize,
+    },
+    InvalidCharacterRange {
+        position: usize,
+        start: char,
+        end: char,
+    },
+    InvalidRepetition {
+        position: usize,
+    },
+    DanglingEscape {
+        position: usize,
+    },
+    InvalidRegex(String),
+    RegexUnsupported(String),
+    RegexNoMatch {
+        max_length: u8,
+    },
+    RegexTooComplex,
+    NoLettersEnabled,
+    PronounceableTooShort {
+        length: u8,
+        min_length: u8,
+    },
 }
 
 impl std::fmt::Display for PasswordConfigError {
@@ -30,6 +72,83 @@ impl std::fmt::Display for PasswordConfigError {
                 )
             }
             Self::PinLengthTooShort => write!(f, "PIN length must be at least 4 characters."),
+            Self::ZeroWords => write!(f, "Passphrase must contain at least one word."),
+            Self::WordListTooSmall { available } => {
+                write!(
+                    f,
+                    "Word list must contain at least 2 distinct words ({} available).",
+                    available
+                )
+            }
+            Self::EmptyPattern => write!(f, "Pattern must contain at least one character."),
+            Self::PatternTooLong => {
+                write!(f, "Pattern must produce at most {} characters.", u8::MAX)
+            }
+            Self::UnclosedCharacterClass { position } => {
+                write!(
+                    f,
+                    "Unclosed character class starting at position {}.",
+                    position
+                )
+            }
+            Self::EmptyCharacterClass { position } => {
+                write!(f, "Empty character class at position {}.", position)
+            }
+            Self::InvalidCharacterRange {
+                position,
+                start,
+                end,
+            } => {
+                write!(
+                    f,
+                    "Invalid range '{}-{}' in character class at position {}.",
+                    start, end, position
+                )
+            }
+            Self::InvalidRepetition { position } => {
+                write!(
+                    f,
+                    "Invalid repetition at position {}, expected a placeholder followed by {{n}} with n > 0.",
+                    position
+                )
+            }
+            Self::DanglingEscape { position } => {
+                write!(
+                    f,
+                    "Escape character at position {} has nothing to escape.",
+                    position
+                )
+            }
+            Self::InvalidRegex(e) => write!(f, "Invalid regular expression: {}", e),
+            Self::RegexUnsupported(feature) => {
+                write!(f, "Unsupported regular expression feature: {}.", feature)
+            }
+            Self::RegexNoMatch { max_length } => {
+                write!(
+                    f,
+                    "Regular expression matches no non-empty string of at most {} characters.",
+                    max_length
+                )
+            }
+            Self::RegexTooComplex => {
+                write!(
+                    f,
+                    "Regular expression is too complex to generate passwords from."
+                )
+            }
+            Self::NoLettersEnabled => {
+                write!(
+                    f,
+                    "Pronounceable passwords need lowercase or uppercase letters."
+                )
+            }
+            Self::PronounceableTooShort { length, min_length } => {
+                write!(
+                    f,
+                    "Pronounceable password length ({}) must be at least {} to fit the required digit and symbol.",
+                    length, min_length
+                )
+            }
         }
     }
 }
@@ -59,6 +178,9 @@ pub struct PasswordConfig {
 
     /// Avoid ambiguous characters (0O1Il5S)
     pub avoid_ambiguous: bool,
+
+    /// Template the password must match, overrides length and the include flags
+    pub pattern: Option<Pattern>,
 }
 
 impl Default for PasswordConfig {
@@ -76,6 +198,7 @@ impl Default for PasswordConfig {
             include_digits: true,
             include_symbols: true,
             avoid_ambiguous: true,
+            pattern: None,
         }
     }
 }
@@ -108,6 +231,7 @@ impl PasswordConfig {
             include_digits: true,
             include_symbols: false,
             avoid_ambiguous: false,
+            pattern: None,
         })
     }
 
@@ -121,6 +245,7 @@ impl PasswordConfig {
             include_digits: true,
             include_symbols: false,
             avoid_ambiguous: true,
+            pattern: None,
         })
     }
 
@@ -132,6 +257,22 @@ impl PasswordConfig {
         })
     }
 
+    /// Creates a password configuration producing passwords matching the pattern
+    pub fn pattern(pattern: Pattern) -> Self {
+        Self {
+            length: pattern.len(),
+            pattern: Some(pattern),
+            ..Self::default()
+        }
+    }
+
+    /// Builder method to set the pattern the password must match
+    pub fn with_pattern(mut self, pattern: Pattern) -> Self {
+        self.length = pattern.len();
+        self.pattern = Some(pattern);
+        self
+    }
+
     /// Builder method to set whether to include lowercase letters
     pub const fn with_lowercase(mut self, include: bool) -> Self {
         self.include_lowercase = include;
@@ -162,12 +303,22 @@ impl PasswordConfig {
         self
     }
 
+    /// Bits of entropy of a password generated with this configuration
+    pub fn entropy_bits(&self) -> f64 {
+        password_entropy(self)
+    }
+
     /// Validates the configuration
     pub const fn validate(&self) -> Result<(), PasswordConfigError> {
         if self.length == 0 {
             return Err(PasswordConfigError::ZeroLength);
         }
 
+        // Patterns are validated when parsed
+        if self.pattern.is_some() {
+            return Ok(());
+        }
+
         let sets_count = self.include_lowercase as u8
             + self.include_uppercase as u8
             + self.include_digits as u8
@@ -0,0 +1,79 @@
+use crate::pwgen::hash::Pbkdf2Algorithm;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::{distr::Alphanumeric, Rng};
+
+const PREFIX: &str = "pbkdf2_sha256$";
+
+/// Default iterations of the `PBKDF2PasswordHasher` of Django 5.2
+pub const DEFAULT_ITERATIONS: u32 = 1_000_000;
+
+/// Salt length of Django, 128 bits of alphanumeric characters
+pub const SALT_LEN: usize = 22;
+
+/// Returns a random alphanumeric salt of `SALT_LEN` characters
+pub fn gensalt() -> String {
+    rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(SALT_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Returns the `pbkdf2_sha256$<iterations>$<salt>$<hash>` hash of Django's default hasher
+pub fn hash(password: &[u8], salt: &str, iterations: u32) -> Result<String> {
+    let key = Pbkdf2Algorithm::Sha256.derive(password, salt.as_bytes(), iterations, 32)?;
+    Ok(format!(
+        "{}{}${}${}",
+        PREFIX,
+        iterations,
+        salt,
+        STANDARD.encode(key)
+    ))
+}
+
+/// Returns the iterations and the salt of a hash
+pub fn parse(hashed: &str) -> Result<(u32, &str)> {
+    let invalid = || anyhow!("Invalid Django pbkdf2_sha256 hash");
+
+    let rest = hashed.strip_prefix(PREFIX).ok_or_else(invalid)?;
+    let fields: Vec<&str> = rest.split('$').collect();
+    let [iterations, salt, _] = fields[..] else {
+        return Err(invalid());
+    };
+
+    let iterations = iterations.parse().map_err(|_| invalid())?;
+    if iterations == 0 || salt.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok((iterations, salt))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Computed with Python's `hashlib.pbkdf2_hmac`
+    const HASH: &str = "pbkdf2_sha256$1000$seasalt$YIWkt6M1JFXrHg5s0jZjBSc7C2Cz6QvchSJ0h8Y+i7c=";
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(b"password", "seasalt", 1000).unwrap(), HASH);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(HASH).unwrap(), (1000, "seasalt"));
+        assert!(parse("pbkdf2_sha256$1000$seasalt").is_err());
+        assert!(parse("pbkdf2_sha256$x$seasalt$hash").is_err());
+        assert!(parse("pbkdf2_sha1$1000$seasalt$hash").is_err());
+    }
+
+    #[test]
+    fn test_gensalt() {
+        let salt = gensalt();
+        assert_eq!(salt.len(), SALT_LEN);
+        assert!(salt.bytes().all(|b| b.is_ascii_alphanumeric()));
+    }
+}
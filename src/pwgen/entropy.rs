@@ -0,0 +1,291 @@
+use crate::pwgen::{config::PasswordConfig, AMBIGUOUS_CHARS, DEFAULT_CHARSETS};
+use std::collections::BTreeMap;
+
+/// Probability that `generate_password` picks a symbol while filling the password
+const SYMBOL_PROBABILITY: f64 = 0.3;
+
+/// Computes the bits of entropy of the passwords produced by `generate_password`
+///
+/// This is the Shannon entropy of the output distribution, not the naive
+/// `length * log2(charset)`, it accounts for:
+/// - one guaranteed character of every included set,
+/// - the cap of one symbol per 10 characters,
+/// - the swap that prevents passwords from starting with a symbol.
+///
+/// For patterns it is the sum of the entropy of every placeholder.
+pub fn password_entropy(config: &PasswordConfig) -> f64 {
+    if let Some(pattern) = &config.pattern {
+        return pattern.entropy_bits(config);
+    }
+
+    let filtered_len = |set: &str| {
+        set.chars()
+            .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
+            .count()
+    };
+
+    // Sizes of the included non-symbol sets, each one gets a guaranteed character
+    let sets: Vec<usize> = [
+        (config.include_lowercase, DEFAULT_CHARSETS.lowercase),
+        (config.include_uppercase, DEFAULT_CHARSETS.uppercase),
+        (config.include_digits, DEFAULT_CHARSETS.digits),
+    ]
+    .into_iter()
+    .filter(|(include, _)| *include)
+    .map(|(_, set)| filtered_len(set))
+    .filter(|len| *len > 0)
+    .collect();
+
+    let symbols = if config.include_symbols {
+        config
+            .charset
+            .as_deref()
+            .unwrap_or(DEFAULT_CHARSETS.symbols)
+            .chars()
+            .count()
+    } else {
+        0
+    };
+
+    let length = usize::from(config.length);
+    let ln_factorials = ln_factorials(length);
+
+    symbol_count_distribution(config, sets.len(), symbols)
+        .into_iter()
+        .map(|(count, p)| {
+            let symbols_bits = if count > 0 {
+                count as f64 * (symbols as f64).log2()
+            } else {
+                0.0
+            };
+            let bits = symbol_positions_entropy(length, count, &ln_factorials)
+                + symbols_bits
+                + non_symbols_entropy(length - count, &sets, &ln_factorials);
+            p * (bits - p.log2())
+        })
+        .sum()
+}
+
+/// Distribution of the number of symbols in the password, replays the fill loop
+fn symbol_count_distribution(
+    config: &PasswordConfig,
+    guaranteed: usize,
+    symbols: usize,
+) -> BTreeMap<usize, f64> {
+    let length = usize::from(config.length);
+    let has_symbols = symbols > 0;
+    let max_symbols = if config.include_symbols {
+        length.div_ceil(10)
+    } else {
+        0
+    };
+
+    let mut distribution = BTreeMap::from([(usize::from(has_symbols), 1.0)]);
+
+    for len in (guaranteed + usize::from(has_symbols))..length {
+        let remaining = length - len;
+        let mut next = BTreeMap::new();
+
+        for (count, p) in distribution {
+            let available = max_symbols.saturating_sub(count);
+            if available == 0 || !has_symbols {
+                *next.entry(count).or_insert(0.0) += p;
+            } else if remaining <= available {
+                *next.entry(count + 1).or_insert(0.0) += p;
+            } else {
+                *next.entry(count + 1).or_insert(0.0) += p * SYMBOL_PROBABILITY;
+                *next.entry(count).or_insert(0.0) += p * (1.0 - SYMBOL_PROBABILITY);
+            }
+        }
+
+        distribution = next;
+    }
+
+    distribution
+}
+
+/// Entropy of the symbol positions after the shuffle and the "never start with a symbol" swap
+///
+/// A set of positions `T` (without position 0) whose first `r` positions are
+/// `1..=r` is reached directly or from `r` sets starting at 0, so its
+/// probability is `(1 + r) / C(length, count)`.
+fn symbol_positions_entropy(length: usize, count: usize, ln_factorials: &[f64]) -> f64 {
+    if count == 0 || count >= length {
+        return 0.0;
+    }
+
+    let ln_total = ln_binomial(length, count, ln_factorials);
+
+    (0..=count)
+        .filter_map(|run| {
+            // Sets of `count` positions in 1..length whose initial run is exactly `run`
+            let ln_sets = if run == count {
+                0.0
+            } else if length >= run + 2 && length - run - 2 >= count - run {
+                ln_binomial(length - run - 2, count - run, ln_factorials)
+            } else {
+                return None;
+            };
+            let ln_p = ((1 + run) as f64).ln() - ln_total;
+            Some(-(ln_sets + ln_p).exp() * ln_p / std::f64::consts::LN_2)
+        })
+        .sum()
+}
+
+/// Entropy of the non-symbol characters, in order
+///
+/// The probability of a string only depends on how many characters of each
+/// set it holds (`a_i`): `prod(a_i) / (M!/(M-g)! * prod(s_i) * N^(M-g))`.
+fn non_symbols_entropy(length: usize, sets: &[usize], ln_factorials: &[f64]) -> f64 {
+    let guaranteed = sets.len();
+    if guaranteed == 0 || length < guaranteed {
+        return 0.0;
+    }
+
+    let total: usize = sets.iter().sum();
+    let ln_guaranteed = ln_factorials[length] - ln_factorials[length - guaranteed]
+        + sets.iter().map(|s| (*s as f64).ln()).sum::<f64>();
+    let ln_base = ((length - guaranteed) as f64).mul_add((total as f64).ln(), ln_guaranteed);
+
+    let mut entropy = 0.0;
+    for_each_composition(length, guaranteed, &mut |counts| {
+        let ln_p = counts.iter().map(|a| (*a as f64).ln()).sum::<f64>() - ln_base;
+        let ln_strings = ln_factorials[length]
+            - counts.iter().map(|a| ln_factorials[*a]).sum::<f64>()
+            + counts
+                .iter()
+                .zip(sets)
+                .map(|(a, s)| *a as f64 * (*s as f64).ln())
+                .sum::<f64>();
+        entropy -= (ln_strings + ln_p).exp() * ln_p;
+    });
+
+    entropy / std::f64::consts::LN_2
+}
+
+/// Calls `f` with every way to split `total` in `parts` numbers of at least 1
+fn for_each_composition(total: usize, parts: usize, f: &mut impl FnMut(&[usize])) {
+    fn recurse(
+        remaining: usize,
+        parts: usize,
+        counts: &mut Vec<usize>,
+        f: &mut impl FnMut(&[usize]),
+    ) {
+        if parts == 1 {
+            counts.push(remaining);
+            f(counts);
+            counts.pop();
+            return;
+        }
+        for count in 1..=remaining - (parts - 1) {
+            counts.push(count);
+            recurse(remaining - count, parts - 1, counts, f);
+            counts.pop();
+        }
+    }
+
+    recurse(total, parts, &mut Vec::with_capacity(parts), f);
+}
+
+fn ln_factorials(n: usize) -> Vec<f64> {
+    let mut table = vec![0.0; n + 1];
+    for i in 1..=n {
+        table[i] = table[i - 1] + (i as f64).ln();
+    }
+    table
+}
+
+fn ln_binomial(n: usize, k: usize, ln_factorials: &[f64]) -> f64 {
+    ln_factorials[n] - ln_factorials[k] - ln_factorials[n - k]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pwgen::{generate_password_with_rng, Pattern};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::HashMap;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn test_pin_entropy() {
+        let config = PasswordConfig::pin(6).unwrap();
+        assert_close(password_entropy(&config), 6.0 * 10f64.log2());
+    }
+
+    #[test]
+    fn test_one_of_each_set_entropy() {
+        // A permutation of one lowercase, one uppercase and one digit
+        let config = PasswordConfig::new(3)
+            .unwrap()
+            .with_symbols(false)
+            .with_avoid_ambiguous(false);
+        let expected = (6.0 * 26.0 * 26.0 * 10.0f64).log2();
+
+        assert_close(password_entropy(&config), expected);
+    }
+
+    #[test]
+    fn test_symbol_positions_entropy() {
+        // One symbol in 4 positions, never first: position 1 has probability 2/4,
+        // positions 2 and 3 have 1/4, so 0.5 * 1 + 2 * 0.25 * 2 bits
+        let table = ln_factorials(4);
+
+        assert_close(symbol_positions_entropy(4, 1, &table), 1.5);
+    }
+
+    #[test]
+    fn test_default_entropy_is_below_naive_estimate() {
+        let config = PasswordConfig::default();
+        let entropy = password_entropy(&config);
+
+        // 18 characters out of 23 + 23 + 8 + 10 available characters
+        let naive = 18.0 * 64f64.log2();
+        assert!(entropy < naive, "{} >= {}", entropy, naive);
+        assert!(entropy > 80.0, "{}", entropy);
+    }
+
+    #[test]
+    fn test_entropy_matches_empirical_distribution() {
+        // Small enough to enumerate: 2 digits + 1 symbol out of 2
+        let config = PasswordConfig::custom(4, "!@".to_string())
+            .unwrap()
+            .with_lowercase(false)
+            .with_uppercase(false)
+            .with_digits(true)
+            .with_avoid_ambiguous(false);
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut counts: HashMap<String, f64> = HashMap::new();
+        let samples = 200_000;
+        for _ in 0..samples {
+            *counts
+                .entry(generate_password_with_rng(&config, &mut rng))
+                .or_default() += 1.0;
+        }
+
+        let empirical: f64 = counts
+            .values()
+            .map(|c| {
+                let p = c / f64::from(samples);
+                -p * p.log2()
+            })
+            .sum();
+
+        assert!(
+            (password_entropy(&config) - empirical).abs() < 0.05,
+            "{} != {}",
+            password_entropy(&config),
+            empirical
+        );
+    }
+
+    #[test]
+    fn test_pattern_entropy() {
+        let config = PasswordConfig::pattern(Pattern::parse("9999").unwrap());
+        assert_close(password_entropy(&config), 4.0 * 7f64.log2());
+    }
+}
@@ -1,130 +1,180 @@
-use crate::pwgen::{config::PasswordConfig, AMBIGUOUS_CHARS, DEFAULT_CHARSETS};
-use rand::{rng, seq::SliceRandom, Rng};
+use crate::pwgen::{
+    config::{PasswordConfig, PasswordConfigError},
+    pattern::Pools,
+    AMBIGUOUS_CHARS, DEFAULT_CHARSETS,
+};
+use rand::{rng, seq::SliceRandom, CryptoRng, Rng};
 use std::collections::HashSet;
 
-/// Generates a password based on the given configuration
-pub fn generate_password(config: &PasswordConfig) -> String {
-    let mut rng = rng();
-    let mut charset = String::new();
-    let mut password = Vec::with_capacity(config.length.into());
-
-    let filtered_lowercase: String = DEFAULT_CHARSETS
-        .lowercase
-        .chars()
-        .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
-        .collect();
-
-    let filtered_uppercase: String = DEFAULT_CHARSETS
-        .uppercase
-        .chars()
-        .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
-        .collect();
-
-    let filtered_digits: String = DEFAULT_CHARSETS
-        .digits
-        .chars()
-        .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
-        .collect();
-
-    let filtered_symbols: String = if config.include_symbols {
-        config.charset.as_ref().map_or_else(
-            || DEFAULT_CHARSETS.symbols.to_string(),
-            |charset| charset.chars().collect(),
-        )
-    } else {
-        String::new()
-    };
-
-    charset.push_str(&filtered_symbols);
-
-    if config.include_lowercase && !filtered_lowercase.is_empty() {
-        password.push(
-            filtered_lowercase
-                .chars()
-                .nth(rng.random_range(0..filtered_lowercase.len()))
-                .unwrap(),
-        );
-        charset.push_str(&filtered_lowercase);
-    }
+/// Password generator built once from a validated configuration
+///
+/// The character pools are computed when it is built, every password then only
+/// draws from them. As an [`Iterator`] it yields passwords endlessly.
+#[derive(Debug, Clone)]
+pub struct PasswordGenerator {
+    config: PasswordConfig,
+    lowercase: Vec<char>,
+    uppercase: Vec<char>,
+    digits: Vec<char>,
+    symbols: Vec<char>,
+    /// Characters of the enabled sets that are symbols, drawn while symbol slots remain
+    symbol_pool: Vec<char>,
+    /// Characters of the enabled sets that are not symbols
+    other_pool: Vec<char>,
+    symbol_set: HashSet<char>,
+    /// At most one symbol per 10 characters, rounded up
+    max_symbols: usize,
+    pattern_pools: Option<Pools>,
+}
 
-    if config.include_uppercase && !filtered_uppercase.is_empty() {
-        password.push(
-            filtered_uppercase
-                .chars()
-                .nth(rng.random_range(0..filtered_uppercase.len()))
-                .unwrap(),
-        );
-        charset.push_str(&filtered_uppercase);
+impl PasswordGenerator {
+    /// Validates the configuration and computes its character pools
+    pub fn new(config: PasswordConfig) -> Result<Self, PasswordConfigError> {
+        config.validate()?;
+        Ok(Self::build(config))
     }
 
-    if config.include_digits && !filtered_digits.is_empty() {
-        password.push(
-            filtered_digits
+    fn build(config: PasswordConfig) -> Self {
+        let filter = |set: &str, include: bool| -> Vec<char> {
+            if !include {
+                return Vec::new();
+            }
+            set.chars()
+                .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
+                .collect()
+        };
+
+        let lowercase = filter(DEFAULT_CHARSETS.lowercase, config.include_lowercase);
+        let uppercase = filter(DEFAULT_CHARSETS.uppercase, config.include_uppercase);
+        let digits = filter(DEFAULT_CHARSETS.digits, config.include_digits);
+        let symbols: Vec<char> = if config.include_symbols {
+            config
+                .charset
+                .as_deref()
+                .unwrap_or(DEFAULT_CHARSETS.symbols)
                 .chars()
-                .nth(rng.random_range(0..filtered_digits.len()))
-                .unwrap(),
-        );
-        charset.push_str(&filtered_digits);
-    }
+                .collect()
+        } else {
+            Vec::new()
+        };
 
-    let charset_chars: Vec<char> = charset.chars().collect();
-    let symbols: HashSet<char> = filtered_symbols.chars().collect();
-
-    let mut symbol_count = 0;
-    if config.include_symbols && !filtered_symbols.is_empty() {
-        let symbol = filtered_symbols
-            .chars()
-            .nth(rng.random_range(0..filtered_symbols.len()))
-            .unwrap();
-        password.push(symbol);
-        symbol_count += 1;
-    }
+        let symbol_set: HashSet<char> = symbols.iter().copied().collect();
+        let (symbol_pool, other_pool) = [&symbols, &lowercase, &uppercase, &digits]
+            .into_iter()
+            .flatten()
+            .partition(|c| symbol_set.contains(c));
 
-    // Calculate maximum allowed symbols (1 per 10 characters, rounded up)
-    let max_symbols = if config.include_symbols {
-        (config.length as f32 / 10.0).ceil() as u8
-    } else {
-        0
-    };
-
-    // Prepare symbol tracking
-    let (symbol_charset, non_symbol_charset): (Vec<_>, Vec<_>) =
-        charset_chars.iter().partition(|c| symbols.contains(c));
-
-    // Fill remaining characters with symbol balance
-    while password.len() < config.length.into() {
-        let remaining = config.length.saturating_sub(password.len() as u8);
-        let available_symbol_slots = max_symbols.saturating_sub(symbol_count);
-
-        // Prefer non-symbols if we have more slots than remaining characters
-        let use_symbol = available_symbol_slots > 0
-            && (remaining <= available_symbol_slots || rng.random_bool(0.3));
-
-        let c = if use_symbol && !filtered_symbols.is_empty() {
-            *symbol_charset
-                .get(rng.random_range(0..symbol_charset.len()))
-                .unwrap_or(&'~') // Fallback if empty
+        let max_symbols = if config.include_symbols {
+            usize::from(config.length).div_ceil(10)
         } else {
-            *non_symbol_charset
-                .get(rng.random_range(0..non_symbol_charset.len()))
-                .unwrap_or(&'a') // Fallback if empty
+            0
         };
 
-        if symbols.contains(&c) {
-            symbol_count += 1;
+        Self {
+            pattern_pools: config.pattern.as_ref().map(|_| Pools::new(&config)),
+            config,
+            lowercase,
+            uppercase,
+            digits,
+            symbols,
+            symbol_pool,
+            other_pool,
+            symbol_set,
+            max_symbols,
         }
-        password.push(c);
     }
 
-    password.shuffle(&mut rng);
+    /// The configuration the generator was built from
+    pub const fn config(&self) -> &PasswordConfig {
+        &self.config
+    }
+
+    /// Generates a password with the thread RNG
+    pub fn generate(&self) -> String {
+        self.generate_with_rng(&mut rng())
+    }
+
+    /// Generates a password using the provided RNG
+    pub fn generate_with_rng<R: Rng + CryptoRng + ?Sized>(&self, rng: &mut R) -> String {
+        if let (Some(pattern), Some(pools)) = (&self.config.pattern, &self.pattern_pools) {
+            return pattern.generate_with_pools(pools, rng);
+        }
 
-    if symbols.contains(&password[0]) {
-        if let Some(non_symbol_index) = password.iter().position(|c| !symbols.contains(c)) {
-            password.swap(0, non_symbol_index);
+        let length = usize::from(self.config.length);
+        let mut password = Vec::with_capacity(length);
+
+        // One character of every enabled set
+        for set in [
+            &self.lowercase,
+            &self.uppercase,
+            &self.digits,
+            &self.symbols,
+        ] {
+            if !set.is_empty() {
+                password.push(set[rng.random_range(0..set.len())]);
+            }
+        }
+        let mut symbol_count = usize::from(!self.symbols.is_empty());
+
+        // Fill remaining characters with symbol balance
+        while password.len() < length {
+            let remaining = length - password.len();
+            let available_symbol_slots = self.max_symbols.saturating_sub(symbol_count);
+
+            // Prefer non-symbols if we have more slots than remaining characters
+            let use_symbol = available_symbol_slots > 0
+                && (remaining <= available_symbol_slots || rng.random_bool(0.3));
+
+            // Symbols only configurations have nothing else to draw from
+            let pool = if (use_symbol || self.other_pool.is_empty()) && !self.symbols.is_empty() {
+                &self.symbol_pool
+            } else {
+                &self.other_pool
+            };
+            let c = pool[rng.random_range(0..pool.len())];
+
+            if self.symbol_set.contains(&c) {
+                symbol_count += 1;
+            }
+            password.push(c);
         }
+
+        password.shuffle(rng);
+
+        if password
+            .first()
+            .is_some_and(|c| self.symbol_set.contains(c))
+        {
+            if let Some(index) = password.iter().position(|c| !self.symbol_set.contains(c)) {
+                password.swap(0, index);
+            }
+        }
+
+        password.into_iter().collect()
+    }
+}
+
+impl Iterator for PasswordGenerator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        Some(self.generate())
     }
+}
 
-    password.into_iter().collect()
+/// Generates a password based on the given configuration
+pub fn generate_password(config: &PasswordConfig) -> String {
+    generate_password_with_rng(config, &mut rng())
+}
+
+/// Generates a password based on the given configuration using the provided RNG
+///
+/// The pools are computed on every call, build a [`PasswordGenerator`] to generate many passwords.
+pub fn generate_password_with_rng<R: Rng + CryptoRng + ?Sized>(
+    config: &PasswordConfig,
+    rng: &mut R,
+) -> String {
+    PasswordGenerator::build(config.clone()).generate_with_rng(rng)
 }
 
 #[cfg(test)]
@@ -222,6 +272,7 @@ mod tests {
             include_digits: true,
             include_symbols: true,
             avoid_ambiguous: false,
+            pattern: None,
         };
 
         for _ in 0..1000 {
@@ -245,6 +296,7 @@ mod tests {
             include_digits: true,
             include_symbols: true,
             avoid_ambiguous: true,
+            pattern: None,
         };
 
         for _ in 0..1000 {
@@ -267,6 +319,7 @@ mod tests {
             include_digits: true,
             include_symbols: true,
             avoid_ambiguous: false,
+            pattern: None,
         };
 
         for _ in 0..1000 {
@@ -289,4 +342,103 @@ mod tests {
             assert!(password.chars().any(|c| c == '~'));
         }
     }
+
+    #[test]
+    fn test_generate_password_with_pattern() {
+        let pattern = "Aaaa-9999-!!".parse().unwrap();
+        let config = PasswordConfig::pattern(pattern);
+
+        assert_eq!(config.length, 12);
+        assert!(config.validate().is_ok());
+
+        let password = generate_password(&config);
+        assert_eq!(password.len(), 12);
+        assert_eq!(password.chars().nth(4), Some('-'));
+    }
+
+    #[test]
+    fn test_generate_password_with_rng() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let config = PasswordConfig::new(32).unwrap();
+        let a = generate_password_with_rng(&config, &mut StdRng::seed_from_u64(42));
+        let b = generate_password_with_rng(&config, &mut StdRng::seed_from_u64(42));
+        let c = generate_password_with_rng(&config, &mut StdRng::seed_from_u64(43));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+        assert_eq!(a.len(), 32);
+    }
+
+    #[test]
+    fn test_password_generator() {
+        use rand::{rngs::StdRng, SeedableRng};
+
+        let config = PasswordConfig::new(24).unwrap();
+        let generator = PasswordGenerator::new(config.clone()).unwrap();
+
+        assert_eq!(generator.generate().len(), 24);
+        assert_eq!(
+            generator.generate_with_rng(&mut StdRng::seed_from_u64(42)),
+            generate_password_with_rng(&config, &mut StdRng::seed_from_u64(42))
+        );
+
+        let pattern = PasswordConfig::pattern("Aaaa-9999".parse().unwrap());
+        let password = PasswordGenerator::new(pattern).unwrap().generate();
+        assert_eq!(password.chars().nth(4), Some('-'));
+    }
+
+    #[test]
+    fn test_password_generator_invalid_config() {
+        let config = PasswordConfig::new(16)
+            .unwrap()
+            .with_lowercase(false)
+            .with_uppercase(false)
+            .with_digits(false)
+            .with_symbols(false);
+
+        assert!(matches!(
+            PasswordGenerator::new(config),
+            Err(PasswordConfigError::NoCharacterSetsEnabled)
+        ));
+    }
+
+    #[test]
+    fn test_password_generator_symbols_only() {
+        let config = PasswordConfig::new(16)
+            .unwrap()
+            .with_lowercase(false)
+            .with_uppercase(false)
+            .with_digits(false)
+            .with_symbols(true);
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for password in generator.take(100) {
+            assert_eq!(password.len(), 16);
+            assert!(password
+                .chars()
+                .all(|c| DEFAULT_CHARSETS.symbols.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_password_generator_iterator() {
+        let generator = PasswordGenerator::new(PasswordConfig::new(18).unwrap()).unwrap();
+        let passwords: HashSet<String> = generator.take(1000).collect();
+
+        assert_eq!(passwords.len(), 1000);
+        assert!(passwords.iter().all(|password| password.len() == 18));
+    }
+
+    #[test]
+    fn test_password_multibyte_charset() {
+        let config = PasswordConfig::custom(20, "€£¥".to_string()).unwrap();
+        let generator = PasswordGenerator::new(config).unwrap();
+
+        for password in generator.take(1000) {
+            assert_eq!(password.chars().count(), 20);
+            assert!(password.chars().any(|c| "€£¥".contains(c)));
+            assert!(!"€£¥".contains(password.chars().next().unwrap()));
+        }
+    }
 }
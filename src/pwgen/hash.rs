@@ -1,46 +1,1463 @@
-use anyhow::{Context, Result};
-use bcrypt::{hash, verify, DEFAULT_COST};
+#[cfg(not(feature = "openssl"))]
+use crate::pwgen::apr1;
+#[cfg(feature = "openssl")]
+use crate::pwgen::openssl::{self, Pbkdf2};
+use crate::pwgen::{
+    django, keycloak, ldap, mosquitto, mysql, pepper::Pepper, phpass, postgres, werkzeug, yescrypt,
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use bcrypt::DEFAULT_COST;
+#[cfg(not(feature = "openssl"))]
+use pbkdf2::Pbkdf2;
 use pbkdf2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Pbkdf2,
+    password_hash::{
+        self, rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Algorithm as Pbkdf2Ident, Params as Pbkdf2Params,
 };
-use sha_crypt::{sha512_check, sha512_simple, Sha512Params};
+use scrypt::{Params as ScryptParams, Scrypt};
+#[cfg(not(feature = "openssl"))]
+use sha_crypt::{
+    sha256_check, sha256_simple, sha512_check, sha512_simple, Sha256Params, Sha512Params,
+};
+use std::{ops::RangeInclusive, str::FromStr};
+
+/// Valid bcrypt costs
+pub const BCRYPT_COSTS: RangeInclusive<u32> = 4..=31;
+
+/// Default PBKDF2 rounds, the OWASP recommendation for PBKDF2-HMAC-SHA256
+pub const PBKDF2_DEFAULT_ROUNDS: u32 = Pbkdf2Params::RECOMMENDED_ROUNDS as u32;
+
+/// Valid PBKDF2 rounds
+pub const PBKDF2_ROUNDS: RangeInclusive<u32> = 1000..=u32::MAX;
+
+/// Valid scrypt costs, as log2 of N
+pub const SCRYPT_COSTS: RangeInclusive<u32> = 1..=30;
+
+/// Valid PostgreSQL SCRAM-SHA-256 iterations
+pub const SCRAM_ROUNDS: RangeInclusive<u32> = postgres::DEFAULT_ITERATIONS..=i32::MAX as u32;
+
+/// Valid Argon2 degrees of parallelism
+pub const ARGON2_PARALLELISM: RangeInclusive<u32> = Params::MIN_P_COST..=Params::MAX_P_COST;
+
+/// Valid SHA-256 and SHA-512 crypt rounds
+pub const SHA_CRYPT_ROUNDS: RangeInclusive<u32> =
+    sha_crypt::ROUNDS_MIN as u32..=sha_crypt::ROUNDS_MAX as u32;
+
+/// Supported hash algorithms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HashAlgorithm {
+    Bcrypt,
+    Pbkdf2,
+    Sha512,
+    Sha256,
+    Argon2,
+    Scrypt,
+    Yescrypt,
+    Apr1,
+    /// MD5 crypt, the `$1$` format of `openssl passwd -1`
+    Md5Crypt,
+    /// PostgreSQL `SCRAM-SHA-256` verifier
+    ScramSha256,
+    /// MySQL `mysql_native_password`
+    MysqlNative,
+    /// MySQL `caching_sha2_password`
+    MysqlSha2,
+    /// LDAP `{SSHA}`
+    Ssha,
+    /// LDAP `{SSHA512}`
+    Ssha512,
+    /// LDAP `{CRYPT}` with SHA-512 crypt
+    LdapCrypt,
+    /// Django `pbkdf2_sha256`
+    Django,
+    /// Werkzeug and Flask `pbkdf2:sha256`
+    Werkzeug,
+    /// WordPress and phpBB portable phpass hashes
+    Phpass,
+    /// Keycloak `pbkdf2-sha512` credential
+    Keycloak,
+    /// Mosquitto `$7$` PBKDF2-HMAC-SHA512
+    Mosquitto,
+}
+
+impl HashAlgorithm {
+    pub const ALL: [Self; 20] = [
+        Self::Bcrypt,
+        Self::Pbkdf2,
+        Self::Sha512,
+        Self::Sha256,
+        Self::Argon2,
+        Self::Scrypt,
+        Self::Yescrypt,
+        Self::Apr1,
+        Self::Md5Crypt,
+        Self::ScramSha256,
+        Self::MysqlNative,
+        Self::MysqlSha2,
+        Self::Ssha,
+        Self::Ssha512,
+        Self::LdapCrypt,
+        Self::Django,
+        Self::Werkzeug,
+        Self::Phpass,
+        Self::Keycloak,
+        Self::Mosquitto,
+    ];
+
+    /// Name of the algorithm, also its CLI flag
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Bcrypt => "bcrypt",
+            Self::Pbkdf2 => "pbkdf2",
+            Self::Sha512 => "sha512",
+            Self::Sha256 => "sha256",
+            Self::Argon2 => "argon2",
+            Self::Scrypt => "scrypt",
+            Self::Yescrypt => "yescrypt",
+            Self::Apr1 => "apr1",
+            Self::Md5Crypt => "md5-crypt",
+            Self::ScramSha256 => "scram-sha256",
+            Self::MysqlNative => "mysql-native",
+            Self::MysqlSha2 => "mysql-sha2",
+            Self::Ssha => "ssha",
+            Self::Ssha512 => "ssha512",
+            Self::LdapCrypt => "ldap-crypt",
+            Self::Django => "django",
+            Self::Werkzeug => "werkzeug",
+            Self::Phpass => "phpass",
+            Self::Keycloak => "keycloak",
+            Self::Mosquitto => "mosquitto",
+        }
+    }
+
+    /// Detects the algorithm of a hash from its prefix
+    pub fn detect(hashed: &str) -> Option<Self> {
+        const PREFIXES: [(&str, HashAlgorithm); 18] = [
+            ("$2a$", HashAlgorithm::Bcrypt),
+            ("$2b$", HashAlgorithm::Bcrypt),
+            ("$2x$", HashAlgorithm::Bcrypt),
+            ("$2y$", HashAlgorithm::Bcrypt),
+            ("$pbkdf2", HashAlgorithm::Pbkdf2),
+            ("$6$", HashAlgorithm::Sha512),
+            ("$5$", HashAlgorithm::Sha256),
+            ("$argon2", HashAlgorithm::Argon2),
+            ("$scrypt$", HashAlgorithm::Scrypt),
+            ("$y$", HashAlgorithm::Yescrypt),
+            ("$apr1$", HashAlgorithm::Apr1),
+            ("$1$", HashAlgorithm::Md5Crypt),
+            ("SCRAM-SHA-256$", HashAlgorithm::ScramSha256),
+            ("$A$", HashAlgorithm::MysqlSha2),
+            ("pbkdf2_sha256$", HashAlgorithm::Django),
+            ("pbkdf2:", HashAlgorithm::Werkzeug),
+            ("$P$", HashAlgorithm::Phpass),
+            ("$H$", HashAlgorithm::Phpass),
+        ];
+        const LDAP_SCHEMES: [(&str, HashAlgorithm); 3] = [
+            (ldap::SSHA, HashAlgorithm::Ssha),
+            (ldap::SSHA512, HashAlgorithm::Ssha512),
+            (ldap::CRYPT, HashAlgorithm::LdapCrypt),
+        ];
+
+        if mysql::is_native_hash(hashed) {
+            return Some(Self::MysqlNative);
+        }
+        if mosquitto::is_hash(hashed) {
+            return Some(Self::Mosquitto);
+        }
+        if keycloak::is_credential(hashed) {
+            return Some(Self::Keycloak);
+        }
+
+        PREFIXES
+            .iter()
+            .find(|(prefix, _)| hashed.starts_with(prefix))
+            .or_else(|| {
+                LDAP_SCHEMES
+                    .iter()
+                    .find(|(scheme, _)| ldap::has_scheme(hashed, scheme))
+            })
+            .map(|(_, algorithm)| *algorithm)
+    }
+}
+
+impl std::fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+/// Pseudo-random function used by PBKDF2
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pbkdf2Algorithm {
+    #[default]
+    Sha256,
+    Sha512,
+}
+
+impl Pbkdf2Algorithm {
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Sha512 => "sha512",
+        }
+    }
+
+    /// Length of the HMAC output, the key length used by most formats
+    pub const fn output_len(self) -> usize {
+        match self {
+            Self::Sha256 => 32,
+            Self::Sha512 => 64,
+        }
+    }
+
+    /// Derives a key of `len` bytes with PBKDF2 using this pseudo-random function
+    ///
+    /// With the `openssl` feature OpenSSL derives it, which can refuse weak parameters in FIPS mode.
+    pub fn derive(
+        self,
+        password: &[u8],
+        salt: &[u8],
+        rounds: u32,
+        len: usize,
+    ) -> anyhow::Result<Vec<u8>> {
+        #[cfg(feature = "openssl")]
+        let key = openssl::pbkdf2(self, password, salt, rounds, len)?;
+        #[cfg(not(feature = "openssl"))]
+        let key = {
+            let mut key = vec![0u8; len];
+            match self {
+                Self::Sha256 => {
+                    pbkdf2::pbkdf2_hmac::<sha2::Sha256>(password, salt, rounds, &mut key)
+                }
+                Self::Sha512 => {
+                    pbkdf2::pbkdf2_hmac::<sha2::Sha512>(password, salt, rounds, &mut key)
+                }
+            }
+            key
+        };
+        Ok(key)
+    }
+}
+
+impl FromStr for Pbkdf2Algorithm {
+    type Err = HashParamsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha256" => Ok(Self::Sha256),
+            "sha512" => Ok(Self::Sha512),
+            _ => Err(HashParamsError::UnknownPbkdf2Algorithm(s.to_string())),
+        }
+    }
+}
+
+/// Error type for hash parameter validation
+#[derive(Debug)]
+pub enum HashParamsError {
+    CostOutOfRange {
+        algorithm: &'static str,
+        cost: u32,
+        range: RangeInclusive<u32>,
+    },
+    RoundsOutOfRange {
+        algorithm: &'static str,
+        rounds: u32,
+        range: RangeInclusive<u32>,
+    },
+    RoundsNotMultiple {
+        algorithm: &'static str,
+        rounds: u32,
+        multiple: u32,
+    },
+    MemoryOutOfRange {
+        memory: u32,
+        range: RangeInclusive<u32>,
+    },
+    ParallelismOutOfRange {
+        parallelism: u32,
+        range: RangeInclusive<u32>,
+    },
+    UnknownPbkdf2Algorithm(String),
+}
+
+impl std::fmt::Display for HashParamsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CostOutOfRange {
+                algorithm,
+                cost,
+                range,
+            } => write!(
+                f,
+                "Invalid {} cost {}, it must be between {} and {}.",
+                algorithm,
+                cost,
+                range.start(),
+                range.end()
+            ),
+            Self::RoundsOutOfRange {
+                algorithm,
+                rounds,
+                range,
+            } => write!(
+                f,
+                "Invalid {} rounds {}, they must be between {} and {}.",
+                algorithm,
+                rounds,
+                range.start(),
+                range.end()
+            ),
+            Self::RoundsNotMultiple {
+                algorithm,
+                rounds,
+                multiple,
+            } => write!(
+                f,
+                "Invalid {} rounds {}, they must be a multiple of {}.",
+                algorithm, rounds, multiple
+            ),
+            Self::MemoryOutOfRange { memory, range } => write!(
+                f,
+                "Invalid Argon2 memory {} KiB, it must be between {} and {} KiB.",
+                memory,
+                range.start(),
+                range.end()
+            ),
+            Self::ParallelismOutOfRange { parallelism, range } => write!(
+                f,
+                "Invalid Argon2 parallelism {}, it must be between {} and {}.",
+                parallelism,
+                range.start(),
+                range.end()
+            ),
+            Self::UnknownPbkdf2Algorithm(name) => {
+                write!(
+                    f,
+                    "Unknown PBKDF2 algorithm '{}', use sha256 or sha512.",
+                    name
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for HashParamsError {}
+
+/// Error type for hashing and verifying passwords
+#[derive(Debug)]
+pub enum HashError {
+    InvalidParams(HashParamsError),
+    UnknownFormat,
+    InvalidHash {
+        algorithm: HashAlgorithm,
+        reason: String,
+    },
+    Hashing {
+        algorithm: HashAlgorithm,
+        reason: String,
+    },
+}
+
+impl HashError {
+    fn invalid_hash(algorithm: HashAlgorithm, reason: impl std::fmt::Display) -> Self {
+        Self::InvalidHash {
+            algorithm,
+            reason: reason.to_string(),
+        }
+    }
+
+    fn hashing(algorithm: HashAlgorithm, reason: impl std::fmt::Display) -> Self {
+        Self::Hashing {
+            algorithm,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for HashError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidParams(e) => write!(f, "{}", e),
+            Self::UnknownFormat => write!(f, "Unknown hash format"),
+            Self::InvalidHash { algorithm, reason } => {
+                write!(f, "Invalid {} hash: {}", algorithm, reason)
+            }
+            Self::Hashing { algorithm, reason } => {
+                write!(f, "Failed to hash password using {}: {}", algorithm, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for HashError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::InvalidParams(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<HashParamsError> for HashError {
+    fn from(e: HashParamsError) -> Self {
+        Self::InvalidParams(e)
+    }
+}
+
+/// Cost parameters for hashing, unset values use the default of each algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashParams {
+    /// bcrypt cost, scrypt log2 of N or yescrypt cost
+    pub cost: Option<u32>,
+    /// PBKDF2 and SHA crypt rounds or Argon2 iterations
+    pub rounds: Option<u32>,
+    /// Argon2 memory in KiB
+    pub memory: Option<u32>,
+    /// Argon2 degree of parallelism
+    pub parallelism: Option<u32>,
+    /// PBKDF2 pseudo-random function
+    pub pbkdf2_algorithm: Pbkdf2Algorithm,
+}
+
+impl HashParams {
+    pub const fn new() -> Self {
+        Self {
+            cost: None,
+            rounds: None,
+            memory: None,
+            parallelism: None,
+            pbkdf2_algorithm: Pbkdf2Algorithm::Sha256,
+        }
+    }
+
+    pub const fn with_cost(mut self, cost: u32) -> Self {
+        self.cost = Some(cost);
+        self
+    }
+
+    pub const fn with_rounds(mut self, rounds: u32) -> Self {
+        self.rounds = Some(rounds);
+        self
+    }
+
+    pub const fn with_memory(mut self, memory: u32) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    pub const fn with_parallelism(mut self, parallelism: u32) -> Self {
+        self.parallelism = Some(parallelism);
+        self
+    }
+
+    pub const fn with_pbkdf2_algorithm(mut self, algorithm: Pbkdf2Algorithm) -> Self {
+        self.pbkdf2_algorithm = algorithm;
+        self
+    }
+
+    /// Checks that the parameters are in range for the algorithm
+    pub fn validate(&self, algorithm: HashAlgorithm) -> Result<(), HashParamsError> {
+        match algorithm {
+            HashAlgorithm::Bcrypt => self.bcrypt_cost().map(drop),
+            HashAlgorithm::Pbkdf2 => self.pbkdf2_rounds().map(drop),
+            HashAlgorithm::Sha512 | HashAlgorithm::Sha256 | HashAlgorithm::LdapCrypt => {
+                self.sha_crypt_rounds().map(drop)
+            }
+            HashAlgorithm::Argon2 => {
+                self.argon2_iterations()?;
+                self.argon2_memory().map(drop)
+            }
+            HashAlgorithm::Scrypt => self.scrypt_log_n().map(drop),
+            HashAlgorithm::Yescrypt => self.yescrypt_cost().map(drop),
+            HashAlgorithm::ScramSha256 => self.scram_iterations().map(drop),
+            HashAlgorithm::MysqlSha2 => self.mysql_sha2_rounds().map(drop),
+            HashAlgorithm::Django => self.django_rounds().map(drop),
+            HashAlgorithm::Werkzeug => self.werkzeug_rounds().map(drop),
+            HashAlgorithm::Phpass => self.phpass_cost().map(drop),
+            HashAlgorithm::Keycloak => self.keycloak_rounds().map(drop),
+            HashAlgorithm::Mosquitto => self.mosquitto_rounds().map(drop),
+            HashAlgorithm::Apr1
+            | HashAlgorithm::Md5Crypt
+            | HashAlgorithm::MysqlNative
+            | HashAlgorithm::Ssha
+            | HashAlgorithm::Ssha512 => Ok(()),
+        }
+    }
+
+    pub fn bcrypt_cost(&self) -> Result<u32, HashParamsError> {
+        self.checked_cost("bcrypt", DEFAULT_COST, BCRYPT_COSTS)
+    }
+
+    pub fn scrypt_log_n(&self) -> Result<u8, HashParamsError> {
+        self.checked_cost("scrypt", u32::from(SCRYPT_DEFAULT_LOG_N), SCRYPT_COSTS)
+            .map(|cost| cost as u8)
+    }
+
+    pub fn yescrypt_cost(&self) -> Result<u8, HashParamsError> {
+        let range = u32::from(yescrypt::MIN_COST)..=u32::from(yescrypt::MAX_COST);
+        self.checked_cost("yescrypt", u32::from(YESCRYPT_DEFAULT_COST), range)
+            .map(|cost| cost as u8)
+    }
+
+    /// Cost of phpass as log2 of the iterations
+    pub fn phpass_cost(&self) -> Result<u8, HashParamsError> {
+        let range = u32::from(phpass::MIN_COST)..=u32::from(phpass::MAX_COST);
+        self.checked_cost("phpass", u32::from(phpass::DEFAULT_COST), range)
+            .map(|cost| cost as u8)
+    }
+
+    pub fn pbkdf2_rounds(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds("PBKDF2", PBKDF2_DEFAULT_ROUNDS, PBKDF2_ROUNDS)
+    }
+
+    pub fn sha_crypt_rounds(&self) -> Result<usize, HashParamsError> {
+        self.checked_rounds(
+            "SHA crypt",
+            sha_crypt::ROUNDS_DEFAULT as u32,
+            SHA_CRYPT_ROUNDS,
+        )
+        .map(|rounds| rounds as usize)
+    }
+
+    pub fn argon2_iterations(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds("Argon2", ARGON2_DEFAULT_ITERATIONS, 1..=u32::MAX)
+    }
+
+    /// Argon2 degree of parallelism
+    pub fn argon2_parallelism(&self) -> Result<u32, HashParamsError> {
+        match self.parallelism {
+            Some(parallelism) if !ARGON2_PARALLELISM.contains(&parallelism) => {
+                Err(HashParamsError::ParallelismOutOfRange {
+                    parallelism,
+                    range: ARGON2_PARALLELISM,
+                })
+            }
+            parallelism => Ok(parallelism.unwrap_or(ARGON2_DEFAULT_PARALLELISM)),
+        }
+    }
+
+    /// Argon2 memory in KiB, at least 8 KiB per lane
+    pub fn argon2_memory(&self) -> Result<u32, HashParamsError> {
+        let range = self.argon2_parallelism()? * Params::MIN_M_COST..=Params::MAX_M_COST;
+        match self.memory {
+            Some(memory) if !range.contains(&memory) => {
+                Err(HashParamsError::MemoryOutOfRange { memory, range })
+            }
+            memory => Ok(memory.unwrap_or(ARGON2_DEFAULT_MEMORY)),
+        }
+    }
+
+    pub fn scram_iterations(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds("SCRAM-SHA-256", postgres::DEFAULT_ITERATIONS, SCRAM_ROUNDS)
+    }
+
+    pub fn django_rounds(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds("Django PBKDF2", django::DEFAULT_ITERATIONS, PBKDF2_ROUNDS)
+    }
+
+    pub fn werkzeug_rounds(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds(
+            "Werkzeug PBKDF2",
+            werkzeug::DEFAULT_ITERATIONS,
+            PBKDF2_ROUNDS,
+        )
+    }
+
+    pub fn keycloak_rounds(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds(
+            "Keycloak PBKDF2",
+            keycloak::DEFAULT_ITERATIONS,
+            PBKDF2_ROUNDS,
+        )
+    }
+
+    pub fn mosquitto_rounds(&self) -> Result<u32, HashParamsError> {
+        self.checked_rounds(
+            "Mosquitto PBKDF2",
+            mosquitto::DEFAULT_ITERATIONS,
+            PBKDF2_ROUNDS,
+        )
+    }
+
+    /// Rounds of `caching_sha2_password`, MySQL stores them in thousands
+    pub fn mysql_sha2_rounds(&self) -> Result<u32, HashParamsError> {
+        let rounds = self.checked_rounds(
+            "caching_sha2_password",
+            mysql::SHA2_DEFAULT_ROUNDS,
+            mysql::SHA2_ROUNDS,
+        )?;
+
+        if rounds % 1000 != 0 {
+            return Err(HashParamsError::RoundsNotMultiple {
+                algorithm: "caching_sha2_password",
+                rounds,
+                multiple: 1000,
+            });
+        }
+        Ok(rounds)
+    }
+
+    fn checked_cost(
+        &self,
+        algorithm: &'static str,
+        default: u32,
+        range: RangeInclusive<u32>,
+    ) -> Result<u32, HashParamsError> {
+        match self.cost {
+            Some(cost) if !range.contains(&cost) => Err(HashParamsError::CostOutOfRange {
+                algorithm,
+                cost,
+                range,
+            }),
+            cost => Ok(cost.unwrap_or(default)),
+        }
+    }
+
+    fn checked_rounds(
+        &self,
+        algorithm: &'static str,
+        default: u32,
+        range: RangeInclusive<u32>,
+    ) -> Result<u32, HashParamsError> {
+        match self.rounds {
+            Some(rounds) if !range.contains(&rounds) => Err(HashParamsError::RoundsOutOfRange {
+                algorithm,
+                rounds,
+                range,
+            }),
+            rounds => Ok(rounds.unwrap_or(default)),
+        }
+    }
+}
 
 /// Hash a password using bcrypt
-pub fn hash_bcrypt(password: &str) -> Result<String> {
-    hash(password, DEFAULT_COST).context("Failed to hash password using bcrypt")
+pub fn hash_bcrypt(password: &str, cost: u32) -> Result<String, HashError> {
+    bcrypt::hash(password, cost).map_err(|e| HashError::hashing(HashAlgorithm::Bcrypt, e))
 }
 
 /// Verify a password against a bcrypt hash
-pub fn verify_bcrypt(password: &str, hashed: &str) -> Result<bool> {
-    verify(password, hashed).context("Failed to verify bcrypt password")
+pub fn verify_bcrypt(password: &str, hashed: &str) -> Result<bool, HashError> {
+    bcrypt::verify(password, hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Bcrypt, e))
 }
 
-/// Hash a password using PBKDF2
-pub fn hash_pbkdf2(password: &str) -> Result<String> {
+/// Hash a password using PBKDF2, in PHC string format
+pub fn hash_pbkdf2(
+    password: &str,
+    rounds: u32,
+    algorithm: Pbkdf2Algorithm,
+) -> Result<String, HashError> {
     let salt = SaltString::generate(&mut OsRng);
-    let password_hash = Pbkdf2
-        .hash_password(password.as_bytes(), &salt)
-        .expect("Failed to hash password using PBKDF2");
-    Ok(password_hash.to_string())
+    let ident = match algorithm {
+        Pbkdf2Algorithm::Sha256 => Pbkdf2Ident::Pbkdf2Sha256,
+        Pbkdf2Algorithm::Sha512 => Pbkdf2Ident::Pbkdf2Sha512,
+    };
+    let params = Pbkdf2Params {
+        rounds,
+        ..Default::default()
+    };
+
+    Pbkdf2
+        .hash_password_customized(
+            password.as_bytes(),
+            Some(ident.ident()),
+            None,
+            params,
+            &salt,
+        )
+        .map(|hash| hash.to_string())
+        .map_err(|e| HashError::hashing(HashAlgorithm::Pbkdf2, e))
 }
 
 /// Verify a password against a PBKDF2 hash
-pub fn verify_pbkdf2(password: &str, hashed: &str) -> Result<bool> {
-    let parsed_hash = PasswordHash::new(hashed).expect("Failed to parse PBKDF2 hash");
-    Ok(Pbkdf2
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .is_ok())
+pub fn verify_pbkdf2(password: &str, hashed: &str) -> Result<bool, HashError> {
+    verify_phc(&Pbkdf2, HashAlgorithm::Pbkdf2, password, hashed)
+}
+
+/// Default Argon2 memory cost in KiB (19 MiB, the OWASP recommendation)
+pub const ARGON2_DEFAULT_MEMORY: u32 = 19 * 1024;
+
+/// Default Argon2 number of iterations
+pub const ARGON2_DEFAULT_ITERATIONS: u32 = 2;
+
+/// Default Argon2 degree of parallelism
+pub const ARGON2_DEFAULT_PARALLELISM: u32 = 1;
+
+/// Hash a password using Argon2id, memory is in KiB
+pub fn hash_argon2(
+    password: &str,
+    memory: u32,
+    iterations: u32,
+    parallelism: u32,
+) -> Result<String, HashError> {
+    let params = Params::new(memory, iterations, parallelism, None)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Argon2, e))?;
+    let salt = SaltString::generate(&mut OsRng);
+
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| HashError::hashing(HashAlgorithm::Argon2, e))
+}
+
+/// Verify a password against an Argon2 hash, the parameters are read from the hash
+pub fn verify_argon2(password: &str, hashed: &str) -> Result<bool, HashError> {
+    verify_phc(&Argon2::default(), HashAlgorithm::Argon2, password, hashed)
+}
+
+/// Default scrypt cost as log2 of N
+pub const SCRYPT_DEFAULT_LOG_N: u8 = ScryptParams::RECOMMENDED_LOG_N;
+
+/// Default scrypt block size
+pub const SCRYPT_DEFAULT_R: u32 = ScryptParams::RECOMMENDED_R;
+
+/// Default scrypt parallelization
+pub const SCRYPT_DEFAULT_P: u32 = ScryptParams::RECOMMENDED_P;
+
+/// Hash a password using scrypt, in PHC string format
+pub fn hash_scrypt(password: &str, log_n: u8, r: u32, p: u32) -> Result<String, HashError> {
+    let params = ScryptParams::new(log_n, r, p, ScryptParams::RECOMMENDED_LEN)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Scrypt, e))?;
+    let salt = SaltString::generate(&mut OsRng);
+
+    Scrypt
+        .hash_password_customized(password.as_bytes(), None, None, params, &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| HashError::hashing(HashAlgorithm::Scrypt, e))
+}
+
+/// Verify a password against a scrypt PHC hash
+pub fn verify_scrypt(password: &str, hashed: &str) -> Result<bool, HashError> {
+    verify_phc(&Scrypt, HashAlgorithm::Scrypt, password, hashed)
+}
+
+/// Verify a password against a PHC string hash, a mismatch is not an error
+fn verify_phc(
+    verifier: &impl PasswordVerifier,
+    algorithm: HashAlgorithm,
+    password: &str,
+    hashed: &str,
+) -> Result<bool, HashError> {
+    let parsed_hash =
+        PasswordHash::new(hashed).map_err(|e| HashError::invalid_hash(algorithm, e))?;
+
+    match verifier.verify_password(password.as_bytes(), &parsed_hash) {
+        Ok(()) => Ok(true),
+        Err(password_hash::Error::Password) => Ok(false),
+        Err(e) => Err(HashError::invalid_hash(algorithm, e)),
+    }
+}
+
+/// Default yescrypt cost, the one used by Debian and Fedora for `/etc/shadow`
+pub const YESCRYPT_DEFAULT_COST: u8 = yescrypt::DEFAULT_COST;
+
+/// Hash a password using yescrypt, in the `$y$` crypt format
+pub fn hash_yescrypt(password: &str, cost: u8) -> Result<String, HashError> {
+    yescrypt::gensalt(cost)
+        .and_then(|setting| yescrypt::hash(password.as_bytes(), &setting))
+        .map_err(|e| HashError::hashing(HashAlgorithm::Yescrypt, e))
+}
+
+/// Verify a password against a yescrypt hash, the parameters are read from the hash
+pub fn verify_yescrypt(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let computed = yescrypt::hash(password.as_bytes(), hashed)
+        .map_err(|e| HashError::invalid_hash(HashAlgorithm::Yescrypt, e))?;
+
+    Ok(constant_time_eq(computed, hashed))
+}
+
+/// Hash a password using Apache's APR1-MD5, the `$apr1$` format of `htpasswd -m`
+pub fn hash_apr1(password: &str) -> Result<String, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt(HashAlgorithm::Apr1, password, 0)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        Ok(apr1::hash(password.as_bytes(), &apr1::gensalt()))
+    }
+}
+
+/// Verify a password against an APR1-MD5 hash
+pub fn verify_apr1(password: &str, hashed: &str) -> Result<bool, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt_verify(HashAlgorithm::Apr1, password, hashed)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        let salt =
+            apr1::salt(hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Apr1, e))?;
+        Ok(constant_time_eq(
+            apr1::hash(password.as_bytes(), salt),
+            hashed,
+        ))
+    }
+}
+
+/// Hash a password using MD5 crypt, the `$1$` format of `openssl passwd -1`
+pub fn hash_md5_crypt(password: &str) -> Result<String, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt(HashAlgorithm::Md5Crypt, password, 0)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        Ok(apr1::md5_crypt(password.as_bytes(), &apr1::gensalt()))
+    }
+}
+
+/// Verify a password against an MD5 crypt hash
+pub fn verify_md5_crypt(password: &str, hashed: &str) -> Result<bool, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt_verify(HashAlgorithm::Md5Crypt, password, hashed)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        let salt = apr1::md5_crypt_salt(hashed)
+            .map_err(|e| HashError::invalid_hash(HashAlgorithm::Md5Crypt, e))?;
+        Ok(constant_time_eq(
+            apr1::md5_crypt(password.as_bytes(), salt),
+            hashed,
+        ))
+    }
+}
+
+/// Hash a password using SHA-256 crypt, the `$5$` format
+pub fn hash_sha256(password: &str, rounds: usize) -> Result<String, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt(HashAlgorithm::Sha256, password, rounds)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        let params = Sha256Params::new(rounds)
+            .map_err(|e| HashError::hashing(HashAlgorithm::Sha256, format!("{:?}", e)))?;
+
+        sha256_simple(password, &params)
+            .map_err(|e| HashError::hashing(HashAlgorithm::Sha256, format!("{:?}", e)))
+    }
+}
+
+/// Verify a password against a SHA-256 crypt hash
+pub fn verify_sha256(password: &str, hashed: &str) -> Result<bool, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt_verify(HashAlgorithm::Sha256, password, hashed)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        sha_crypt_result(HashAlgorithm::Sha256, sha256_check(password, hashed))
+    }
+}
+
+/// Hash a password using SHA-512 crypt, the `$6$` format
+pub fn hash_sha512(password: &str, rounds: usize) -> Result<String, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt(HashAlgorithm::Sha512, password, rounds)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        let params = Sha512Params::new(rounds)
+            .map_err(|e| HashError::hashing(HashAlgorithm::Sha512, format!("{:?}", e)))?;
+
+        sha512_simple(password, &params)
+            .map_err(|e| HashError::hashing(HashAlgorithm::Sha512, format!("{:?}", e)))
+    }
+}
+
+/// Verify a password against a SHA-512 crypt hash
+pub fn verify_sha512(password: &str, hashed: &str) -> Result<bool, HashError> {
+    #[cfg(feature = "openssl")]
+    {
+        openssl_crypt_verify(HashAlgorithm::Sha512, password, hashed)
+    }
+    #[cfg(not(feature = "openssl"))]
+    {
+        sha_crypt_result(HashAlgorithm::Sha512, sha512_check(password, hashed))
+    }
+}
+
+/// Hash a password with the crypt(3) implementation over OpenSSL digests, like `openssl passwd`
+#[cfg(feature = "openssl")]
+fn openssl_crypt(
+    algorithm: HashAlgorithm,
+    password: &str,
+    rounds: usize,
+) -> Result<String, HashError> {
+    openssl::gensalt(algorithm, rounds)
+        .and_then(|setting| openssl::crypt(password.as_bytes(), &setting))
+        .map_err(|e| HashError::hashing(algorithm, e))
+}
+
+/// Verify a password against a crypt(3) hash recomputed with OpenSSL, from its own setting
+#[cfg(feature = "openssl")]
+fn openssl_crypt_verify(
+    algorithm: HashAlgorithm,
+    password: &str,
+    hashed: &str,
+) -> Result<bool, HashError> {
+    if HashAlgorithm::detect(hashed) != Some(algorithm) {
+        return Err(HashError::invalid_hash(algorithm, "unexpected prefix"));
+    }
+    let computed = openssl::crypt(password.as_bytes(), hashed)
+        .map_err(|e| HashError::invalid_hash(algorithm, e))?;
+
+    // Only the hash after the setting may differ, anything else is a malformed hash
+    let setting_len = computed.rfind('$').unwrap_or_default();
+    if computed.len() != hashed.len()
+        || computed.as_bytes()[..setting_len] != hashed.as_bytes()[..setting_len]
+    {
+        return Err(HashError::invalid_hash(algorithm, "malformed hash"));
+    }
+    Ok(constant_time_eq(computed, hashed))
+}
+
+/// Turns a SHA crypt check into a match, a mismatch is not an error
+#[cfg(not(feature = "openssl"))]
+fn sha_crypt_result<E: std::fmt::Debug>(
+    algorithm: HashAlgorithm,
+    result: Result<(), E>,
+) -> Result<bool, HashError> {
+    // sha-crypt does not export its `CheckError`, so the variant is told apart by name
+    match result.map_err(|e| format!("{:?}", e)) {
+        Ok(()) => Ok(true),
+        Err(e) if e == "HashMismatch" => Ok(false),
+        Err(e) => Err(HashError::invalid_hash(algorithm, e)),
+    }
+}
+
+/// Hash a password into a PostgreSQL `SCRAM-SHA-256` verifier
+pub fn hash_scram_sha256(password: &str, iterations: u32) -> Result<String, HashError> {
+    Ok(postgres::hash(
+        password.as_bytes(),
+        &postgres::gensalt(),
+        iterations,
+    ))
+}
+
+/// Verify a password against a PostgreSQL `SCRAM-SHA-256` verifier
+pub fn verify_scram_sha256(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let (iterations, salt) = postgres::parse(hashed)
+        .map_err(|e| HashError::invalid_hash(HashAlgorithm::ScramSha256, e))?;
+    Ok(constant_time_eq(
+        postgres::hash(password.as_bytes(), &salt, iterations),
+        hashed,
+    ))
+}
+
+/// Hash a password using MySQL `mysql_native_password`, unsalted and only for legacy servers
+pub fn hash_mysql_native(password: &str) -> Result<String, HashError> {
+    Ok(mysql::native_hash(password.as_bytes()))
+}
+
+/// Verify a password against a MySQL `mysql_native_password` hash
+pub fn verify_mysql_native(password: &str, hashed: &str) -> Result<bool, HashError> {
+    Ok(constant_time_eq(
+        mysql::native_hash(password.as_bytes()),
+        hashed.to_ascii_uppercase(),
+    ))
+}
+
+/// Hash a password using MySQL `caching_sha2_password`
+pub fn hash_mysql_sha2(password: &str, rounds: u32) -> Result<String, HashError> {
+    Ok(mysql::sha2_hash(
+        password.as_bytes(),
+        &mysql::sha2_gensalt(),
+        rounds,
+    ))
+}
+
+/// Verify a password against a MySQL `caching_sha2_password` hash
+pub fn verify_mysql_sha2(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let (rounds, salt) = mysql::sha2_parse(hashed)
+        .map_err(|e| HashError::invalid_hash(HashAlgorithm::MysqlSha2, e))?;
+    Ok(constant_time_eq(
+        mysql::sha2_hash(password.as_bytes(), salt, rounds),
+        hashed,
+    ))
+}
+
+/// Hash a password using the LDAP `{SSHA}` scheme
+pub fn hash_ssha(password: &str) -> Result<String, HashError> {
+    Ok(ldap::ssha(password.as_bytes(), &ldap::gensalt()))
+}
+
+/// Verify a password against an LDAP `{SSHA}` hash
+pub fn verify_ssha(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let salt = ldap::salt(hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Ssha, e))?;
+    Ok(constant_time_eq(
+        ldap::ssha(password.as_bytes(), &salt),
+        normalize_scheme(hashed, ldap::SSHA),
+    ))
+}
+
+/// Hash a password using the LDAP `{SSHA512}` scheme
+pub fn hash_ssha512(password: &str) -> Result<String, HashError> {
+    Ok(ldap::ssha512(password.as_bytes(), &ldap::gensalt()))
+}
+
+/// Verify a password against an LDAP `{SSHA512}` hash
+pub fn verify_ssha512(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let salt =
+        ldap::salt(hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Ssha512, e))?;
+    Ok(constant_time_eq(
+        ldap::ssha512(password.as_bytes(), &salt),
+        normalize_scheme(hashed, ldap::SSHA512),
+    ))
+}
+
+/// Hash a password using the LDAP `{CRYPT}` scheme with SHA-512 crypt
+pub fn hash_ldap_crypt(password: &str, rounds: usize) -> Result<String, HashError> {
+    Ok(format!("{}{}", ldap::CRYPT, hash_sha512(password, rounds)?))
+}
+
+/// Verify a password against an LDAP `{CRYPT}` hash, any supported crypt(3) hash is accepted
+pub fn verify_ldap_crypt(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let (_, crypt) = ldap_crypt_inner(hashed)?;
+    verify(password, crypt)
+}
+
+/// Strips a single `{CRYPT}` scheme, returns the algorithm and the crypt(3) hash it wraps
+fn ldap_crypt_inner(hashed: &str) -> Result<(HashAlgorithm, &str), HashError> {
+    let invalid = |reason| HashError::invalid_hash(HashAlgorithm::LdapCrypt, reason);
+    let crypt = ldap::strip_crypt(hashed).ok_or_else(|| invalid("missing scheme"))?;
+    match HashAlgorithm::detect(crypt).ok_or(HashError::UnknownFormat)? {
+        HashAlgorithm::Ssha | HashAlgorithm::Ssha512 | HashAlgorithm::LdapCrypt => {
+            Err(invalid("nested LDAP scheme"))
+        }
+        inner => Ok((inner, crypt)),
+    }
+}
+
+/// Hash a password using Django's default `pbkdf2_sha256` hasher
+pub fn hash_django(password: &str, iterations: u32) -> Result<String, HashError> {
+    django::hash(password.as_bytes(), &django::gensalt(), iterations)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Django, e))
+}
+
+/// Verify a password against a Django `pbkdf2_sha256` hash
+pub fn verify_django(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let (iterations, salt) =
+        django::parse(hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Django, e))?;
+    let computed = django::hash(password.as_bytes(), salt, iterations)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Django, e))?;
+    Ok(constant_time_eq(computed, hashed))
+}
+
+/// Hash a password like Werkzeug's `generate_password_hash` with `pbkdf2:sha256`
+pub fn hash_werkzeug(password: &str, iterations: u32) -> Result<String, HashError> {
+    werkzeug::hash(password.as_bytes(), &werkzeug::gensalt(), iterations)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Werkzeug, e))
 }
 
-pub fn hash_sha512(password: &str) -> Result<String> {
-    let params = Sha512Params::new(sha_crypt::ROUNDS_DEFAULT).expect("RandomError!");
+/// Verify a password against a Werkzeug `pbkdf2:sha256` or `pbkdf2:sha512` hash
+pub fn verify_werkzeug(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let parsed =
+        werkzeug::parse(hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Werkzeug, e))?;
+    let key = werkzeug::key(
+        password.as_bytes(),
+        parsed.digest,
+        parsed.salt,
+        parsed.iterations,
+    )
+    .map_err(|e| HashError::hashing(HashAlgorithm::Werkzeug, e))?;
+    Ok(constant_time_eq(key, parsed.key))
+}
 
-    Ok(sha512_simple(password, &params).expect("hashing error"))
+/// Hash a password using the portable phpass hashes of WordPress, MD5 based
+pub fn hash_phpass(password: &str, cost: u8) -> Result<String, HashError> {
+    phpass::gensalt(cost)
+        .and_then(|setting| phpass::hash(password.as_bytes(), &setting))
+        .map_err(|e| HashError::hashing(HashAlgorithm::Phpass, e))
 }
 
-pub fn verify_sha512(password: &str, hashed: &str) -> Result<bool> {
-    Ok(sha512_check(password, hashed).is_ok())
+/// Verify a password against a phpass `$P$` or `$H$` hash
+pub fn verify_phpass(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let computed = phpass::hash(password.as_bytes(), hashed)
+        .map_err(|e| HashError::invalid_hash(HashAlgorithm::Phpass, e))?;
+    Ok(constant_time_eq(computed, hashed))
+}
+
+/// Hash a password into a Keycloak `pbkdf2-sha512` credential
+pub fn hash_keycloak(password: &str, iterations: u32) -> Result<String, HashError> {
+    keycloak::hash(password.as_bytes(), &keycloak::gensalt(), iterations)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Keycloak, e))
+}
+
+/// Verify a password against a Keycloak `pbkdf2-sha256` or `pbkdf2-sha512` credential
+pub fn verify_keycloak(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let credential =
+        keycloak::parse(hashed).map_err(|e| HashError::invalid_hash(HashAlgorithm::Keycloak, e))?;
+    let value = credential
+        .digest
+        .derive(
+            password.as_bytes(),
+            &credential.salt,
+            credential.iterations,
+            credential.value.len(),
+        )
+        .map_err(|e| HashError::hashing(HashAlgorithm::Keycloak, e))?;
+    Ok(constant_time_eq(value, &credential.value))
+}
+
+/// Hash a password using the `$7$` PBKDF2-HMAC-SHA512 format of `mosquitto_passwd`
+pub fn hash_mosquitto(password: &str, iterations: u32) -> Result<String, HashError> {
+    mosquitto::hash(password.as_bytes(), &mosquitto::gensalt(), iterations)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Mosquitto, e))
+}
+
+/// Verify a password against a Mosquitto `$7$` hash
+pub fn verify_mosquitto(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let (iterations, salt) = mosquitto::parse(hashed)
+        .map_err(|e| HashError::invalid_hash(HashAlgorithm::Mosquitto, e))?;
+    let computed = mosquitto::hash(password.as_bytes(), &salt, iterations)
+        .map_err(|e| HashError::hashing(HashAlgorithm::Mosquitto, e))?;
+    Ok(constant_time_eq(computed, hashed))
+}
+
+/// Uppercases the LDAP scheme of a hash, servers accept it in any case
+fn normalize_scheme(hashed: &str, scheme: &str) -> String {
+    format!("{}{}", scheme, &hashed[scheme.len()..])
+}
+
+/// Hash a password with any supported algorithm, unset parameters use its defaults
+pub fn hash(
+    password: &str,
+    algorithm: HashAlgorithm,
+    params: &HashParams,
+) -> Result<String, HashError> {
+    params.validate(algorithm)?;
+    #[cfg(feature = "openssl")]
+    openssl::check_fips(algorithm).map_err(|e| HashError::hashing(algorithm, e))?;
+
+    match algorithm {
+        HashAlgorithm::Bcrypt => hash_bcrypt(password, params.bcrypt_cost()?),
+        HashAlgorithm::Pbkdf2 => {
+            hash_pbkdf2(password, params.pbkdf2_rounds()?, params.pbkdf2_algorithm)
+        }
+        HashAlgorithm::Sha512 => hash_sha512(password, params.sha_crypt_rounds()?),
+        HashAlgorithm::Sha256 => hash_sha256(password, params.sha_crypt_rounds()?),
+        HashAlgorithm::Argon2 => hash_argon2(
+            password,
+            params.argon2_memory()?,
+            params.argon2_iterations()?,
+            params.argon2_parallelism()?,
+        ),
+        HashAlgorithm::Scrypt => hash_scrypt(
+            password,
+            params.scrypt_log_n()?,
+            SCRYPT_DEFAULT_R,
+            SCRYPT_DEFAULT_P,
+        ),
+        HashAlgorithm::Yescrypt => hash_yescrypt(password, params.yescrypt_cost()?),
+        HashAlgorithm::Apr1 => hash_apr1(password),
+        HashAlgorithm::Md5Crypt => hash_md5_crypt(password),
+        HashAlgorithm::ScramSha256 => hash_scram_sha256(password, params.scram_iterations()?),
+        HashAlgorithm::MysqlNative => hash_mysql_native(password),
+        HashAlgorithm::MysqlSha2 => hash_mysql_sha2(password, params.mysql_sha2_rounds()?),
+        HashAlgorithm::Ssha => hash_ssha(password),
+        HashAlgorithm::Ssha512 => hash_ssha512(password),
+        HashAlgorithm::LdapCrypt => hash_ldap_crypt(password, params.sha_crypt_rounds()?),
+        HashAlgorithm::Django => hash_django(password, params.django_rounds()?),
+        HashAlgorithm::Werkzeug => hash_werkzeug(password, params.werkzeug_rounds()?),
+        HashAlgorithm::Phpass => hash_phpass(password, params.phpass_cost()?),
+        HashAlgorithm::Keycloak => hash_keycloak(password, params.keycloak_rounds()?),
+        HashAlgorithm::Mosquitto => hash_mosquitto(password, params.mosquitto_rounds()?),
+    }
+}
+
+/// Verify a password against a hash of any supported algorithm, detected from its prefix
+pub fn verify(password: &str, hashed: &str) -> Result<bool, HashError> {
+    let algorithm = HashAlgorithm::detect(hashed).ok_or(HashError::UnknownFormat)?;
+    #[cfg(feature = "openssl")]
+    openssl::check_fips(algorithm).map_err(|e| HashError::hashing(algorithm, e))?;
+
+    match algorithm {
+        HashAlgorithm::Bcrypt => verify_bcrypt(password, hashed),
+        HashAlgorithm::Pbkdf2 => verify_pbkdf2(password, hashed),
+        HashAlgorithm::Sha512 => verify_sha512(password, hashed),
+        HashAlgorithm::Sha256 => verify_sha256(password, hashed),
+        HashAlgorithm::Argon2 => verify_argon2(password, hashed),
+        HashAlgorithm::Scrypt => verify_scrypt(password, hashed),
+        HashAlgorithm::Yescrypt => verify_yescrypt(password, hashed),
+        HashAlgorithm::Apr1 => verify_apr1(password, hashed),
+        HashAlgorithm::Md5Crypt => verify_md5_crypt(password, hashed),
+        HashAlgorithm::ScramSha256 => verify_scram_sha256(password, hashed),
+        HashAlgorithm::MysqlNative => verify_mysql_native(password, hashed),
+        HashAlgorithm::MysqlSha2 => verify_mysql_sha2(password, hashed),
+        HashAlgorithm::Ssha => verify_ssha(password, hashed),
+        HashAlgorithm::Ssha512 => verify_ssha512(password, hashed),
+        HashAlgorithm::LdapCrypt => verify_ldap_crypt(password, hashed),
+        HashAlgorithm::Django => verify_django(password, hashed),
+        HashAlgorithm::Werkzeug => verify_werkzeug(password, hashed),
+        HashAlgorithm::Phpass => verify_phpass(password, hashed),
+        HashAlgorithm::Keycloak => verify_keycloak(password, hashed),
+        HashAlgorithm::Mosquitto => verify_mosquitto(password, hashed),
+    }
+}
+
+/// Hash the HMAC-SHA256 of a password with a server-side pepper instead of the password
+pub fn hash_peppered(
+    password: &str,
+    algorithm: HashAlgorithm,
+    params: &HashParams,
+    pepper: &Pepper,
+) -> Result<String, HashError> {
+    hash(&pepper.apply(password), algorithm, params)
+}
+
+/// Verify a password against a hash of `hash_peppered`, with the same pepper
+pub fn verify_peppered(password: &str, hashed: &str, pepper: &Pepper) -> Result<bool, HashError> {
+    verify(&pepper.apply(password), hashed)
+}
+
+/// Default PBKDF2 rounds for HMAC-SHA512, the OWASP recommendation
+pub const PBKDF2_SHA512_DEFAULT_ROUNDS: u32 = 210_000;
+
+/// Minimum algorithms and costs of stored hashes, weaker ones need to be rehashed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RehashPolicy {
+    algorithms: Vec<HashAlgorithm>,
+    bcrypt_cost: u32,
+    pbkdf2_sha256_rounds: u32,
+    pbkdf2_sha512_rounds: u32,
+    sha_crypt_rounds: u32,
+    argon2_memory: u32,
+    argon2_iterations: u32,
+    scrypt_log_n: u32,
+    yescrypt_cost: u32,
+    scram_iterations: u32,
+    mysql_sha2_rounds: u32,
+    phpass_cost: u32,
+}
+
+impl Default for RehashPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RehashPolicy {
+    /// Accepts every algorithm with a cost, with the default costs as minimums
+    ///
+    /// APR1-MD5, MD5 crypt, phpass, `mysql_native_password`, `{SSHA}` and `{SSHA512}` are
+    /// fast MD5 or SHA hashes, they always need a rehash.
+    pub fn new() -> Self {
+        const FAST: [HashAlgorithm; 6] = [
+            HashAlgorithm::Apr1,
+            HashAlgorithm::Md5Crypt,
+            HashAlgorithm::Phpass,
+            HashAlgorithm::MysqlNative,
+            HashAlgorithm::Ssha,
+            HashAlgorithm::Ssha512,
+        ];
+
+        Self {
+            algorithms: HashAlgorithm::ALL
+                .into_iter()
+                .filter(|algorithm| !FAST.contains(algorithm))
+                .collect(),
+            bcrypt_cost: DEFAULT_COST,
+            pbkdf2_sha256_rounds: PBKDF2_DEFAULT_ROUNDS,
+            pbkdf2_sha512_rounds: PBKDF2_SHA512_DEFAULT_ROUNDS,
+            sha_crypt_rounds: sha_crypt::ROUNDS_DEFAULT as u32,
+            argon2_memory: ARGON2_DEFAULT_MEMORY,
+            argon2_iterations: ARGON2_DEFAULT_ITERATIONS,
+            scrypt_log_n: u32::from(SCRYPT_DEFAULT_LOG_N),
+            yescrypt_cost: u32::from(YESCRYPT_DEFAULT_COST),
+            scram_iterations: postgres::DEFAULT_ITERATIONS,
+            mysql_sha2_rounds: mysql::SHA2_DEFAULT_ROUNDS,
+            phpass_cost: u32::from(phpass::DEFAULT_COST),
+        }
+    }
+
+    /// Minimum PBKDF2 rounds of a pseudo-random function
+    const fn pbkdf2_rounds(&self, digest: Pbkdf2Algorithm) -> u32 {
+        match digest {
+            Pbkdf2Algorithm::Sha256 => self.pbkdf2_sha256_rounds,
+            Pbkdf2Algorithm::Sha512 => self.pbkdf2_sha512_rounds,
+        }
+    }
+
+    /// Only accepts hashes of these algorithms
+    pub fn with_algorithms(mut self, algorithms: &[HashAlgorithm]) -> Self {
+        self.algorithms = algorithms.to_vec();
+        self
+    }
+
+    /// Sets the minimum cost or rounds of an algorithm from the parameters that are set
+    pub fn with_params(
+        mut self,
+        algorithm: HashAlgorithm,
+        params: &HashParams,
+    ) -> Result<Self, HashParamsError> {
+        params.validate(algorithm)?;
+
+        match algorithm {
+            HashAlgorithm::Bcrypt => self.bcrypt_cost = params.cost.unwrap_or(self.bcrypt_cost),
+            HashAlgorithm::Pbkdf2 => match params.pbkdf2_algorithm {
+                Pbkdf2Algorithm::Sha256 => {
+                    self.pbkdf2_sha256_rounds = params.rounds.unwrap_or(self.pbkdf2_sha256_rounds)
+                }
+                Pbkdf2Algorithm::Sha512 => {
+                    self.pbkdf2_sha512_rounds = params.rounds.unwrap_or(self.pbkdf2_sha512_rounds)
+                }
+            },
+            HashAlgorithm::Sha512 | HashAlgorithm::Sha256 | HashAlgorithm::LdapCrypt => {
+                self.sha_crypt_rounds = params.rounds.unwrap_or(self.sha_crypt_rounds)
+            }
+            HashAlgorithm::Argon2 => {
+                self.argon2_iterations = params.rounds.unwrap_or(self.argon2_iterations);
+                self.argon2_memory = params.memory.unwrap_or(self.argon2_memory);
+            }
+            HashAlgorithm::Scrypt => self.scrypt_log_n = params.cost.unwrap_or(self.scrypt_log_n),
+            HashAlgorithm::Yescrypt => {
+                self.yescrypt_cost = params.cost.unwrap_or(self.yescrypt_cost)
+            }
+            HashAlgorithm::ScramSha256 => {
+                self.scram_iterations = params.rounds.unwrap_or(self.scram_iterations)
+            }
+            HashAlgorithm::MysqlSha2 => {
+                self.mysql_sha2_rounds = params.rounds.unwrap_or(self.mysql_sha2_rounds)
+            }
+            HashAlgorithm::Django | HashAlgorithm::Werkzeug => {
+                self.pbkdf2_sha256_rounds = params.rounds.unwrap_or(self.pbkdf2_sha256_rounds)
+            }
+            HashAlgorithm::Keycloak | HashAlgorithm::Mosquitto => {
+                self.pbkdf2_sha512_rounds = params.rounds.unwrap_or(self.pbkdf2_sha512_rounds)
+            }
+            HashAlgorithm::Phpass => self.phpass_cost = params.cost.unwrap_or(self.phpass_cost),
+            HashAlgorithm::Apr1
+            | HashAlgorithm::Md5Crypt
+            | HashAlgorithm::MysqlNative
+            | HashAlgorithm::Ssha
+            | HashAlgorithm::Ssha512 => {}
+        }
+
+        Ok(self)
+    }
+}
+
+/// Whether a stored hash uses an algorithm or a cost below the policy
+pub fn needs_rehash(hashed: &str, policy: &RehashPolicy) -> Result<bool, HashError> {
+    let algorithm = HashAlgorithm::detect(hashed).ok_or(HashError::UnknownFormat)?;
+    if !policy.algorithms.contains(&algorithm) {
+        return Ok(true);
+    }
+
+    below_policy(algorithm, hashed, policy)
+}
+
+/// Whether the cost of a hash is below the policy minimum for its algorithm
+fn below_policy(
+    algorithm: HashAlgorithm,
+    hashed: &str,
+    policy: &RehashPolicy,
+) -> Result<bool, HashError> {
+    let invalid = |reason: &dyn std::fmt::Display| HashError::invalid_hash(algorithm, reason);
+    let phc = || PasswordHash::new(hashed).map_err(|e| invalid(&e));
+
+    Ok(match algorithm {
+        HashAlgorithm::Bcrypt => {
+            let cost = hashed
+                .split('$')
+                .nth(2)
+                .and_then(|cost| cost.parse::<u32>().ok())
+                .ok_or_else(|| invalid(&"missing cost"))?;
+            cost < policy.bcrypt_cost
+        }
+        HashAlgorithm::Pbkdf2 => {
+            let phc = phc()?;
+            let rounds = Pbkdf2Params::try_from(&phc)
+                .map_err(|e| invalid(&e))?
+                .rounds;
+            match phc.algorithm {
+                ident if ident == Pbkdf2Ident::Pbkdf2Sha256.ident() => {
+                    rounds < policy.pbkdf2_sha256_rounds
+                }
+                ident if ident == Pbkdf2Ident::Pbkdf2Sha512.ident() => {
+                    rounds < policy.pbkdf2_sha512_rounds
+                }
+                // PBKDF2-HMAC-SHA1 is always upgraded
+                _ => true,
+            }
+        }
+        HashAlgorithm::Sha512 | HashAlgorithm::Sha256 => {
+            sha_crypt_rounds(hashed).map_err(|e| invalid(&e))? < policy.sha_crypt_rounds
+        }
+        HashAlgorithm::Argon2 => {
+            let phc = phc()?;
+            let params = Params::try_from(&phc).map_err(|e| invalid(&e))?;
+            // Argon2i and Argon2d are upgraded to Argon2id
+            phc.algorithm != Algorithm::Argon2id.ident()
+                || params.m_cost() < policy.argon2_memory
+                || params.t_cost() < policy.argon2_iterations
+        }
+        HashAlgorithm::Scrypt => {
+            let params = ScryptParams::try_from(&phc()?).map_err(|e| invalid(&e))?;
+            u32::from(params.log_n()) < policy.scrypt_log_n
+        }
+        HashAlgorithm::Yescrypt => {
+            u32::from(yescrypt::cost(hashed).map_err(|e| invalid(&e))?) < policy.yescrypt_cost
+        }
+        HashAlgorithm::ScramSha256 => {
+            let (iterations, _) = postgres::parse(hashed).map_err(|e| invalid(&e))?;
+            iterations < policy.scram_iterations
+        }
+        HashAlgorithm::MysqlSha2 => {
+            let (rounds, _) = mysql::sha2_parse(hashed).map_err(|e| invalid(&e))?;
+            rounds < policy.mysql_sha2_rounds
+        }
+        HashAlgorithm::Django => {
+            let (iterations, _) = django::parse(hashed).map_err(|e| invalid(&e))?;
+            iterations < policy.pbkdf2_sha256_rounds
+        }
+        HashAlgorithm::Werkzeug => {
+            let parsed = werkzeug::parse(hashed).map_err(|e| invalid(&e))?;
+            parsed.iterations < policy.pbkdf2_rounds(parsed.digest)
+        }
+        HashAlgorithm::Phpass => {
+            u32::from(phpass::cost(hashed).map_err(|e| invalid(&e))?) < policy.phpass_cost
+        }
+        HashAlgorithm::Keycloak => {
+            let credential = keycloak::parse(hashed).map_err(|e| invalid(&e))?;
+            credential.iterations < policy.pbkdf2_rounds(credential.digest)
+        }
+        HashAlgorithm::Mosquitto => {
+            let (iterations, _) = mosquitto::parse(hashed).map_err(|e| invalid(&e))?;
+            iterations < policy.pbkdf2_sha512_rounds
+        }
+        // The cost of the wrapped crypt(3) hash is checked, whatever its algorithm
+        HashAlgorithm::LdapCrypt => {
+            let (inner, crypt) = ldap_crypt_inner(hashed)?;
+            below_policy(inner, crypt, policy)?
+        }
+        // Fast hashes have a fixed cost, only the accepted algorithms matter
+        HashAlgorithm::Apr1
+        | HashAlgorithm::Md5Crypt
+        | HashAlgorithm::MysqlNative
+        | HashAlgorithm::Ssha
+        | HashAlgorithm::Ssha512 => false,
+    })
+}
+
+/// Rounds of a SHA crypt hash, the default when the `rounds=` field is missing
+fn sha_crypt_rounds(hashed: &str) -> Result<u32, std::num::ParseIntError> {
+    let fields = hashed.split('$').nth(2).unwrap_or_default();
+
+    fields
+        .strip_prefix("rounds=")
+        .map_or(Ok(sha_crypt::ROUNDS_DEFAULT as u32), str::parse)
+}
+
+/// Compares two hashes or keys in constant time
+fn constant_time_eq(a: impl AsRef<[u8]>, b: impl AsRef<[u8]>) -> bool {
+    let (a, b) = (a.as_ref(), b.as_ref());
+
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
 }
 
 #[cfg(test)]
@@ -50,21 +1467,607 @@ mod tests {
     #[test]
     fn test_hash_bcrypt() {
         let password = "password";
-        let hashed = hash_bcrypt(password).unwrap();
+        let hashed = hash_bcrypt(password, 4).unwrap();
         assert!(verify_bcrypt(password, &hashed).unwrap());
     }
 
     #[test]
     fn test_hash_pbkdf2() {
         let password = "password";
-        let hashed = hash_pbkdf2(password).unwrap();
+        let hashed = hash_pbkdf2(password, 1000, Pbkdf2Algorithm::Sha256).unwrap();
         assert!(verify_pbkdf2(password, &hashed).unwrap());
     }
 
+    #[test]
+    fn test_hash_argon2() {
+        let password = "password";
+        let hashed = hash_argon2(password, 1024, 1, 1).unwrap();
+
+        assert!(hashed.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"));
+        assert!(verify_argon2(password, &hashed).unwrap());
+        assert!(!verify_argon2("wrong", &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_hash_argon2_defaults() {
+        let hashed = hash_argon2(
+            "password",
+            ARGON2_DEFAULT_MEMORY,
+            ARGON2_DEFAULT_ITERATIONS,
+            ARGON2_DEFAULT_PARALLELISM,
+        )
+        .unwrap();
+
+        assert!(hashed.starts_with("$argon2id$v=19$m=19456,t=2,p=1$"));
+    }
+
+    #[test]
+    fn test_hash_argon2_invalid_params() {
+        assert!(hash_argon2("password", 1, 1, 1).is_err());
+        assert!(verify_argon2("password", "not a hash").is_err());
+    }
+
+    #[test]
+    fn test_hash_scrypt() {
+        let password = "password";
+        let hashed = hash_scrypt(password, 10, 8, 1).unwrap();
+
+        assert!(hashed.starts_with("$scrypt$ln=10,r=8,p=1$"));
+        assert!(verify_scrypt(password, &hashed).unwrap());
+        assert!(!verify_scrypt("wrong", &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_hash_scrypt_invalid_params() {
+        assert!(hash_scrypt("password", 10, 0, 1).is_err());
+        assert!(verify_scrypt("password", "not a hash").is_err());
+    }
+
+    #[test]
+    fn test_hash_yescrypt() {
+        let password = "password";
+        let hashed = hash_yescrypt(password, 1).unwrap();
+
+        assert!(hashed.starts_with("$y$j75$"));
+        assert!(verify_yescrypt(password, &hashed).unwrap());
+        assert!(!verify_yescrypt("wrong", &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_verify_yescrypt() {
+        let hashed = "$y$j9T$k2XAnEHBqQ1Ct2aMXFKNa/$OVYXzjlkiQpWT/F1CUE0JrvV4phLY8FB.ofDttnrSQ7";
+
+        assert!(verify_yescrypt("password", hashed).unwrap());
+        assert!(!verify_yescrypt("Password", hashed).unwrap());
+        assert!(verify_yescrypt("password", "$y$j9T").is_err());
+    }
+
     #[test]
     fn test_hash_sha512() {
         let password = "password";
-        let hashed = hash_sha512(password).unwrap();
+        let hashed = hash_sha512(password, sha_crypt::ROUNDS_DEFAULT).unwrap();
         assert!(verify_sha512(password, &hashed).unwrap());
     }
+
+    #[test]
+    fn test_hash_apr1() {
+        let password = "password";
+        let hashed = hash_apr1(password).unwrap();
+
+        assert!(hashed.starts_with("$apr1$"));
+        assert!(verify_apr1(password, &hashed).unwrap());
+        assert!(!verify_apr1("wrong", &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_verify_md5_crypt() {
+        // Computed with `openssl passwd -1`
+        let hashed = "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/";
+
+        assert!(verify_md5_crypt("password", hashed).unwrap());
+        assert!(!verify_md5_crypt("Password", hashed).unwrap());
+        assert!(verify_md5_crypt("password", "$apr1$saltsalt$").is_err());
+        assert!(hash_md5_crypt("password").unwrap().starts_with("$1$"));
+    }
+
+    #[test]
+    fn test_verify_apr1() {
+        let hashed = "$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1";
+
+        assert!(verify_apr1("password", hashed).unwrap());
+        assert!(!verify_apr1("Password", hashed).unwrap());
+        assert!(verify_apr1("password", "$1$abcdefgh$").is_err());
+    }
+
+    #[test]
+    fn test_hash_sha256() {
+        let password = "password";
+        let hashed = hash_sha256(password, sha_crypt::ROUNDS_DEFAULT).unwrap();
+
+        assert!(hashed.starts_with("$5$"));
+        assert!(verify_sha256(password, &hashed).unwrap());
+        assert!(!verify_sha256("wrong", &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_verify_sha256() {
+        // Computed with `openssl passwd -5`
+        let hashed = "$5$saltstring$OH4IDuTlsuTYPdED1gsuiRMyTAwNlRWyA6Xr3I4/dQ5";
+
+        assert!(verify_sha256("password", hashed).unwrap());
+        assert!(!verify_sha256("Password", hashed).unwrap());
+    }
+
+    #[test]
+    fn test_hash_bcrypt_cost() {
+        let hashed = hash_bcrypt("password", 5).unwrap();
+
+        assert!(hashed.starts_with("$2b$05$"));
+        assert!(hash_bcrypt("password", 3).is_err());
+    }
+
+    #[test]
+    fn test_hash_pbkdf2_sha512() {
+        let password = "password";
+        let hashed = hash_pbkdf2(password, 2000, Pbkdf2Algorithm::Sha512).unwrap();
+
+        assert!(hashed.starts_with("$pbkdf2-sha512$i=2000,l=32$"));
+        assert!(verify_pbkdf2(password, &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_hash_sha_crypt_rounds() {
+        let hashed = hash_sha512("password", 10_000).unwrap();
+        assert!(hashed.starts_with("$6$rounds=10000$"));
+
+        let hashed = hash_sha256("password", 10_000).unwrap();
+        assert!(hashed.starts_with("$5$rounds=10000$"));
+        assert!(hash_sha256("password", 999).is_err());
+    }
+
+    #[test]
+    fn test_hash_params_defaults() {
+        let params = HashParams::new();
+
+        assert_eq!(params.bcrypt_cost().unwrap(), DEFAULT_COST);
+        assert_eq!(params.scrypt_log_n().unwrap(), SCRYPT_DEFAULT_LOG_N);
+        assert_eq!(params.yescrypt_cost().unwrap(), YESCRYPT_DEFAULT_COST);
+        assert_eq!(params.pbkdf2_rounds().unwrap(), 600_000);
+        assert_eq!(params.sha_crypt_rounds().unwrap(), 5000);
+        assert_eq!(
+            params.argon2_iterations().unwrap(),
+            ARGON2_DEFAULT_ITERATIONS
+        );
+        assert_eq!(params.pbkdf2_algorithm, Pbkdf2Algorithm::Sha256);
+    }
+
+    #[test]
+    fn test_hash_params_ranges() {
+        let params = HashParams::new().with_cost(12).with_rounds(10_000);
+
+        assert_eq!(params.bcrypt_cost().unwrap(), 12);
+        assert!(params.yescrypt_cost().is_err());
+        assert_eq!(params.sha_crypt_rounds().unwrap(), 10_000);
+
+        let params = HashParams::new().with_cost(32).with_rounds(999);
+        assert_eq!(
+            params.bcrypt_cost().unwrap_err().to_string(),
+            "Invalid bcrypt cost 32, it must be between 4 and 31."
+        );
+        assert_eq!(
+            params.pbkdf2_rounds().unwrap_err().to_string(),
+            "Invalid PBKDF2 rounds 999, they must be between 1000 and 4294967295."
+        );
+        assert!(params.sha_crypt_rounds().is_err());
+        assert_eq!(params.argon2_iterations().unwrap(), 999);
+
+        let params = HashParams::new().with_memory(64 * 1024).with_parallelism(4);
+        assert_eq!(params.argon2_memory().unwrap(), 64 * 1024);
+        assert_eq!(params.argon2_parallelism().unwrap(), 4);
+        assert!(params.validate(HashAlgorithm::Argon2).is_ok());
+
+        let params = HashParams::new().with_memory(16).with_parallelism(4);
+        assert_eq!(
+            params.argon2_memory().unwrap_err().to_string(),
+            "Invalid Argon2 memory 16 KiB, it must be between 32 and 4294967295 KiB."
+        );
+        assert!(params.validate(HashAlgorithm::Argon2).is_err());
+        assert!(params.validate(HashAlgorithm::Bcrypt).is_ok());
+        assert!(HashParams::new()
+            .with_parallelism(0)
+            .validate(HashAlgorithm::Argon2)
+            .is_err());
+    }
+
+    #[test]
+    fn test_hash_argon2_params() {
+        let params = HashParams::new()
+            .with_memory(8 * 1024)
+            .with_rounds(1)
+            .with_parallelism(2);
+        let hashed = hash("password", HashAlgorithm::Argon2, &params).unwrap();
+
+        assert!(hashed.starts_with("$argon2id$v=19$m=8192,t=1,p=2$"));
+        assert!(verify("password", &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_pbkdf2_algorithm_from_str() {
+        assert_eq!(
+            "sha512".parse::<Pbkdf2Algorithm>().unwrap(),
+            Pbkdf2Algorithm::Sha512
+        );
+        assert!("md5".parse::<Pbkdf2Algorithm>().is_err());
+    }
+
+    #[test]
+    fn test_detect_algorithm() {
+        let detect = HashAlgorithm::detect;
+
+        assert_eq!(detect("$2y$10$abc"), Some(HashAlgorithm::Bcrypt));
+        assert_eq!(detect("$pbkdf2-sha512$i=1000"), Some(HashAlgorithm::Pbkdf2));
+        assert_eq!(detect("$6$rounds=5000$abc"), Some(HashAlgorithm::Sha512));
+        assert_eq!(detect("$5$abc$def"), Some(HashAlgorithm::Sha256));
+        assert_eq!(detect("$argon2id$v=19"), Some(HashAlgorithm::Argon2));
+        assert_eq!(detect("$scrypt$ln=17"), Some(HashAlgorithm::Scrypt));
+        assert_eq!(detect("$y$j9T$abc"), Some(HashAlgorithm::Yescrypt));
+        assert_eq!(detect("$apr1$abc$def"), Some(HashAlgorithm::Apr1));
+        assert_eq!(detect("$1$abc$def"), Some(HashAlgorithm::Md5Crypt));
+        assert_eq!(
+            detect("SCRAM-SHA-256$4096:c2FsdA==$a2V5:a2V5"),
+            Some(HashAlgorithm::ScramSha256)
+        );
+        assert_eq!(
+            detect("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"),
+            Some(HashAlgorithm::MysqlNative)
+        );
+        assert_eq!(detect("$A$005$abc"), Some(HashAlgorithm::MysqlSha2));
+        assert_eq!(detect("{SSHA}abc"), Some(HashAlgorithm::Ssha));
+        assert_eq!(detect("{ssha}abc"), Some(HashAlgorithm::Ssha));
+        assert_eq!(detect("{SSHA512}abc"), Some(HashAlgorithm::Ssha512));
+        assert_eq!(detect("{CRYPT}$6$abc"), Some(HashAlgorithm::LdapCrypt));
+        assert_eq!(
+            detect("pbkdf2_sha256$1000$salt$key"),
+            Some(HashAlgorithm::Django)
+        );
+        assert_eq!(
+            detect("pbkdf2:sha256$salt$key"),
+            Some(HashAlgorithm::Werkzeug)
+        );
+        assert_eq!(detect("$P$Babc"), Some(HashAlgorithm::Phpass));
+        assert_eq!(detect("$H$7abc"), Some(HashAlgorithm::Phpass));
+        assert_eq!(
+            detect(r#"{"credentialData":"{}","secretData":"{}","type":"password"}"#),
+            Some(HashAlgorithm::Keycloak)
+        );
+        assert_eq!(detect("$7$101$salt$hash"), Some(HashAlgorithm::Mosquitto));
+        assert_eq!(detect("$7$CU..../....salt$hash"), None);
+        assert_eq!(detect("{SHA}abc"), None);
+        assert_eq!(detect("*2470"), None);
+        assert_eq!(detect("$3$abc$def"), None);
+        assert_eq!(detect("password"), None);
+    }
+
+    #[test]
+    fn test_hash_verify() {
+        let params = HashParams::new().with_cost(4).with_rounds(1000);
+
+        for algorithm in HashAlgorithm::ALL {
+            let params = match algorithm {
+                HashAlgorithm::Scrypt => params.with_cost(10),
+                HashAlgorithm::Yescrypt => params.with_cost(1),
+                HashAlgorithm::Argon2 => params.with_rounds(1),
+                HashAlgorithm::ScramSha256 | HashAlgorithm::MysqlSha2 => params.with_rounds(5000),
+                HashAlgorithm::Phpass => params.with_cost(7),
+                _ => params,
+            };
+            let hashed = hash("password", algorithm, &params).unwrap();
+
+            assert_eq!(HashAlgorithm::detect(&hashed), Some(algorithm));
+            assert!(verify("password", &hashed).unwrap(), "{}", hashed);
+            assert!(!verify("wrong", &hashed).unwrap(), "{}", hashed);
+        }
+
+        assert!(verify("password", "$3$abc$def").is_err());
+    }
+
+    #[test]
+    fn test_verify_database_hashes() {
+        // Computed with Python's `hashlib`, libxcrypt and MySQL's `PASSWORD()`
+        let vectors = [
+            "SCRAM-SHA-256$4096:c2FsdHNhbHRzYWx0c2FsdA==$\
+             CozjiHjNmiMjBgH9gZ7qn0QWud6nrVP6E72IBh477bQ=:\
+             VKers2x8MllK1Rh7LZLqtj6KOTzoFWJpIaokMX3blS0=",
+            "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19",
+            "*2470c0c06dee42fd1618bb99005adca2ec9d1e19",
+            "{SSHA}yrht1iYXEIkejLVu42JWkadd80RzYWx0c2FsdA==",
+            "{ssha}yrht1iYXEIkejLVu42JWkadd80RzYWx0c2FsdA==",
+            "{CRYPT}$5$rounds=5000$saltstringsaltst$Ekah6lEFydzYloW2P/P45IGa7Yv0vGRQi.McSitgqd9",
+        ];
+
+        for hashed in vectors {
+            assert!(verify("password", hashed).unwrap(), "{}", hashed);
+            assert!(!verify("Password", hashed).unwrap(), "{}", hashed);
+        }
+
+        assert!(hash_ldap_crypt("password", 5000)
+            .unwrap()
+            .starts_with("{CRYPT}$6$"));
+        assert!(matches!(
+            verify("password", "{CRYPT}$3$abc$def"),
+            Err(HashError::UnknownFormat)
+        ));
+        assert!(verify("password", "{SSHA}!!").is_err());
+        let nested = format!(
+            "{}{}",
+            "{CRYPT}".repeat(100_000),
+            hash_sha512("password", 5000).unwrap()
+        );
+        assert!(matches!(
+            verify("password", &nested),
+            Err(HashError::InvalidHash {
+                algorithm: HashAlgorithm::LdapCrypt,
+                ..
+            })
+        ));
+        assert!(verify("password", "{CRYPT}{SSHA}!!").is_err());
+        assert!(verify("password", "$A$005$short").is_err());
+        assert!(verify("password", "SCRAM-SHA-256$4096:c2FsdA==").is_err());
+    }
+
+    #[test]
+    fn test_hash_peppered() {
+        let pepper = Pepper::new("pepper").unwrap();
+        let hashed = hash_peppered(
+            "password",
+            HashAlgorithm::Bcrypt,
+            &HashParams::new().with_cost(4),
+            &pepper,
+        )
+        .unwrap();
+
+        assert!(verify_peppered("password", &hashed, &pepper).unwrap());
+        assert!(!verify_peppered("Password", &hashed, &pepper).unwrap());
+        assert!(!verify_peppered("password", &hashed, &Pepper::new("other").unwrap()).unwrap());
+        assert!(!verify("password", &hashed).unwrap());
+        assert!(verify(&pepper.apply("password"), &hashed).unwrap());
+    }
+
+    #[test]
+    fn test_verify_framework_hashes() {
+        // Computed with Python's `hashlib` and the test vector of phpass
+        let vectors: [(&str, &str); 5] = [
+            (
+                "password",
+                "pbkdf2_sha256$1000$seasalt$YIWkt6M1JFXrHg5s0jZjBSc7C2Cz6QvchSJ0h8Y+i7c=",
+            ),
+            (
+                "password",
+                "pbkdf2:sha256:1000$saltsaltsaltsalt$\
+                 f275fb870144cc807c68f6a325360af3078741ce4d833d2915500abd2bb88d00",
+            ),
+            ("test12345", "$P$9IQRaTwmfeRo7ud9Fh4E2PdI0S3r.L0"),
+            (
+                "password",
+                r#"{"credentialData":"{\"additionalParameters\":{},\"algorithm\":\"pbkdf2-sha512\",\"hashIterations\":1000}","secretData":"{\"additionalParameters\":{},\"salt\":\"c2FsdHNhbHRzYWx0c2FsdA==\",\"value\":\"715rqIr5dXOVPpBhqqsugl037zT5bWJTWYmZtIcK8hBnisKpwfY7kokvwjDrNHqHhF50Pb7MD6HvkJwiDQw4ww==\"}","type":"password"}"#,
+            ),
+            (
+                "password",
+                "$7$1000$c2FsdHNhbHRzYWx0$qEWuBHP82uzPrybCUpp9FMGVDoszagDwOgBhAY44y2gPun4\
+                 RFBM9zjAnhQjJRk+l/dEEhatLCI65Yj/OYq4dcQ==",
+            ),
+        ];
+
+        for (password, hashed) in vectors {
+            assert!(verify(password, hashed).unwrap(), "{}", hashed);
+            assert!(!verify("wrong", hashed).unwrap(), "{}", hashed);
+        }
+
+        assert!(verify("password", "pbkdf2:md5:1000$salt$key").is_err());
+        assert!(verify("password", "$P$4saltsaltsaltsaltsaltsaltsal").is_err());
+        assert!(verify("password", r#"{"credentialData":"x"}"#).is_err());
+    }
+
+    #[test]
+    fn test_hash_database_params() {
+        let params = HashParams::new().with_rounds(5500);
+        assert!(matches!(
+            params.validate(HashAlgorithm::MysqlSha2),
+            Err(HashParamsError::RoundsNotMultiple { .. })
+        ));
+        assert!(params.validate(HashAlgorithm::ScramSha256).is_ok());
+        assert!(params.validate(HashAlgorithm::Ssha).is_ok());
+
+        let params = HashParams::new().with_rounds(1000);
+        assert!(params.validate(HashAlgorithm::MysqlSha2).is_err());
+        assert!(params.validate(HashAlgorithm::ScramSha256).is_err());
+        assert!(params.validate(HashAlgorithm::LdapCrypt).is_ok());
+
+        let hashed = hash(
+            "password",
+            HashAlgorithm::MysqlSha2,
+            &HashParams::new().with_rounds(10000),
+        )
+        .unwrap();
+        assert!(hashed.starts_with("$A$00A$"));
+    }
+
+    #[test]
+    fn test_hash_invalid_params() {
+        let params = HashParams::new().with_cost(32);
+
+        assert!(hash("password", HashAlgorithm::Bcrypt, &params).is_err());
+        assert!(params.validate(HashAlgorithm::Bcrypt).is_err());
+        assert!(params.validate(HashAlgorithm::Sha512).is_ok());
+    }
+
+    #[test]
+    fn test_verify_malformed_hash() {
+        for hashed in [
+            "$pbkdf2-sha256$i=1000$!$!",
+            "$argon2id$v=19$m=19456,t=2,p=1$!$!",
+            "$scrypt$ln=17,r=8,p=1$!$!",
+            "$6$rounds=5000",
+            "$5$",
+            "$2b$12$short",
+            "$y$j9T",
+            "$apr1$",
+        ] {
+            assert!(
+                matches!(
+                    verify("password", hashed),
+                    Err(HashError::InvalidHash { .. })
+                ),
+                "{}",
+                hashed
+            );
+        }
+
+        assert!(matches!(
+            verify("password", "$3$abc$def"),
+            Err(HashError::UnknownFormat)
+        ));
+    }
+
+    #[test]
+    fn test_hash_error_display() {
+        let error = HashError::from(HashParamsError::CostOutOfRange {
+            algorithm: "bcrypt",
+            cost: 32,
+            range: BCRYPT_COSTS,
+        });
+        assert_eq!(
+            error.to_string(),
+            "Invalid bcrypt cost 32, it must be between 4 and 31."
+        );
+        assert!(std::error::Error::source(&error).is_some());
+
+        let error = HashError::InvalidHash {
+            algorithm: HashAlgorithm::Pbkdf2,
+            reason: "salt invalid".to_string(),
+        };
+        assert_eq!(error.to_string(), "Invalid pbkdf2 hash: salt invalid");
+    }
+
+    #[test]
+    fn test_needs_rehash() {
+        let policy = RehashPolicy::new();
+
+        assert!(needs_rehash(&hash_bcrypt("password", 4).unwrap(), &policy).unwrap());
+        assert!(!needs_rehash(
+            "$2b$12$R9h/cIPz0gi.URNNX3kh2OPST9/PgBkqquzi.Ss7KIUgO2t0jWMUW",
+            &policy
+        )
+        .unwrap());
+
+        let pbkdf2 = hash_pbkdf2("password", 1000, Pbkdf2Algorithm::Sha512).unwrap();
+        assert!(needs_rehash(&pbkdf2, &policy).unwrap());
+
+        let sha512 = hash_sha512("password", 5000).unwrap();
+        assert!(!needs_rehash(&sha512, &policy).unwrap());
+        assert!(needs_rehash("$6$rounds=1000$salt$hash", &policy).unwrap());
+
+        let argon2 = hash_argon2("password", ARGON2_DEFAULT_MEMORY, 2, 1).unwrap();
+        assert!(!needs_rehash(&argon2, &policy).unwrap());
+        let argon2i = Argon2::new(Algorithm::Argon2i, Version::V0x13, Params::default())
+            .hash_password(b"password", &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        assert!(needs_rehash(&argon2i, &policy).unwrap());
+
+        assert!(needs_rehash(&hash_scrypt("password", 10, 8, 1).unwrap(), &policy).unwrap());
+        assert!(needs_rehash(&hash_yescrypt("password", 1).unwrap(), &policy).unwrap());
+        assert!(!needs_rehash(&hash_yescrypt("password", 5).unwrap(), &policy).unwrap());
+        assert!(needs_rehash(&hash_apr1("password").unwrap(), &policy).unwrap());
+        assert!(needs_rehash(&hash_md5_crypt("password").unwrap(), &policy).unwrap());
+
+        assert!(!needs_rehash(&hash_scram_sha256("password", 4096).unwrap(), &policy).unwrap());
+        assert!(needs_rehash("SCRAM-SHA-256$1000:c2FsdA==$a2V5:a2V5", &policy).unwrap());
+        assert!(!needs_rehash(&hash_mysql_sha2("password", 5000).unwrap(), &policy).unwrap());
+        assert!(!needs_rehash(&hash_ldap_crypt("password", 5000).unwrap(), &policy).unwrap());
+        assert!(needs_rehash("{CRYPT}$6$rounds=1000$salt$hash", &policy).unwrap());
+        assert!(!needs_rehash(&hash_django("password", 1_000_000).unwrap(), &policy).unwrap());
+        assert!(needs_rehash("pbkdf2_sha256$260000$salt$key", &policy).unwrap());
+        assert!(needs_rehash("pbkdf2:sha512:1000$salt$key", &policy).unwrap());
+        assert!(!needs_rehash(&hash_mosquitto("password", 210_000).unwrap(), &policy).unwrap());
+        assert!(needs_rehash("$7$101$c2FsdA==$a2V5", &policy).unwrap());
+        for hashed in [
+            hash_phpass("password", 7).unwrap(),
+            hash_mysql_native("password").unwrap(),
+            hash_ssha("password").unwrap(),
+            hash_ssha512("password").unwrap(),
+        ] {
+            assert!(needs_rehash(&hashed, &policy).unwrap(), "{}", hashed);
+        }
+    }
+
+    #[test]
+    fn test_needs_rehash_policy() {
+        let policy = RehashPolicy::new()
+            .with_params(
+                HashAlgorithm::Pbkdf2,
+                &HashParams::new()
+                    .with_rounds(1000)
+                    .with_pbkdf2_algorithm(Pbkdf2Algorithm::Sha512),
+            )
+            .unwrap()
+            .with_params(HashAlgorithm::Sha512, &HashParams::new().with_rounds(10000))
+            .unwrap();
+
+        let pbkdf2 = hash_pbkdf2("password", 1000, Pbkdf2Algorithm::Sha512).unwrap();
+        assert!(!needs_rehash(&pbkdf2, &policy).unwrap());
+        assert!(needs_rehash(&hash_sha512("password", 5000).unwrap(), &policy).unwrap());
+
+        let policy = RehashPolicy::new()
+            .with_algorithms(&[HashAlgorithm::Phpass])
+            .with_params(HashAlgorithm::Phpass, &HashParams::new().with_cost(8))
+            .unwrap();
+        assert!(needs_rehash(&hash_phpass("password", 7).unwrap(), &policy).unwrap());
+        assert!(!needs_rehash(&hash_phpass("password", 8).unwrap(), &policy).unwrap());
+
+        let policy = RehashPolicy::new().with_algorithms(&[HashAlgorithm::Apr1]);
+        assert!(!needs_rehash(&hash_apr1("password").unwrap(), &policy).unwrap());
+        assert!(needs_rehash(&hash_yescrypt("password", 5).unwrap(), &policy).unwrap());
+
+        let policy = RehashPolicy::new()
+            .with_params(
+                HashAlgorithm::Argon2,
+                &HashParams::new().with_memory(32 * 1024),
+            )
+            .unwrap();
+        let argon2 = hash_argon2("password", ARGON2_DEFAULT_MEMORY, 2, 1).unwrap();
+        assert!(needs_rehash(&argon2, &policy).unwrap());
+        let argon2 = hash_argon2("password", 32 * 1024, 2, 1).unwrap();
+        assert!(!needs_rehash(&argon2, &policy).unwrap());
+
+        assert!(RehashPolicy::new()
+            .with_params(HashAlgorithm::Bcrypt, &HashParams::new().with_cost(32))
+            .is_err());
+    }
+
+    #[test]
+    fn test_needs_rehash_invalid() {
+        let policy = RehashPolicy::new();
+
+        assert!(matches!(
+            needs_rehash("$3$abc$def", &policy),
+            Err(HashError::UnknownFormat)
+        ));
+        for hashed in [
+            "$2b$xx$hash",
+            "$pbkdf2-sha256$!",
+            "$6$rounds=x$salt$hash",
+            "$y$j9T",
+            "{CRYPT}{CRYPT}$6$salt$hash",
+        ] {
+            assert!(
+                matches!(
+                    needs_rehash(hashed, &policy),
+                    Err(HashError::InvalidHash { .. })
+                ),
+                "{}",
+                hashed
+            );
+        }
+    }
 }
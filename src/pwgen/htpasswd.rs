@@ -0,0 +1,284 @@
+use crate::pwgen::hash::{self, HashAlgorithm, HashParams};
+use anyhow::{bail, Context, Result};
+use std::{
+    ffi::OsString,
+    fmt,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+    process,
+};
+
+/// Hash algorithms Apache accepts in htpasswd files
+pub const ALGORITHMS: [HashAlgorithm; 4] = [
+    HashAlgorithm::Bcrypt,
+    HashAlgorithm::Apr1,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Sha512,
+];
+
+/// Default algorithm, the one of `htpasswd -B`
+pub const DEFAULT_ALGORITHM: HashAlgorithm = HashAlgorithm::Bcrypt;
+
+/// An htpasswd file, `user:hash` lines as read by Apache and nginx
+///
+/// Comments and any other lines are kept as is when the file is updated.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Htpasswd {
+    lines: Vec<String>,
+}
+
+impl Htpasswd {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn parse(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(str::to_string).collect(),
+        }
+    }
+
+    /// Reads an htpasswd file, a missing file is empty
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(content) => Ok(Self::parse(&content)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Replaces the file atomically, a crash or a full disk leaves the old file untouched
+    ///
+    /// The content is written to a temporary file next to it, with the
+    /// permissions of the old file, then renamed over it.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Not a file path"))?;
+        let mut temp_name = OsString::from(".");
+        temp_name.push(name);
+        temp_name.push(format!(".{}.tmp", process::id()));
+        let temp = path.with_file_name(temp_name);
+
+        let saved = self
+            .write_new(&temp, path)
+            .and_then(|()| fs::rename(&temp, path));
+        if saved.is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        saved
+    }
+
+    /// Writes the content to `temp` with the permissions of `original`, if it exists
+    fn write_new(&self, temp: &Path, original: &Path) -> io::Result<()> {
+        let mut file = File::create(temp)?;
+        match fs::metadata(original) {
+            Ok(metadata) => file.set_permissions(metadata.permissions())?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        file.write_all(self.to_string().as_bytes())?;
+        file.sync_all()
+    }
+
+    /// Returns the hash of a user
+    pub fn get(&self, user: &str) -> Option<&str> {
+        self.lines.iter().find_map(|line| {
+            let (name, hash) = line.split_once(':')?;
+            (name == user).then_some(hash)
+        })
+    }
+
+    /// Replaces the hash of a user, new users are appended
+    pub fn set(&mut self, user: &str, hash: &str) {
+        let entry = format!("{}:{}", user, hash);
+
+        match self.position(user) {
+            Some(index) => self.lines[index] = entry,
+            None => self.lines.push(entry),
+        }
+    }
+
+    /// Removes a user, returns whether it was found
+    pub fn remove(&mut self, user: &str) -> bool {
+        let Some(index) = self.position(user) else {
+            return false;
+        };
+        self.lines.remove(index);
+        true
+    }
+
+    /// Users of the file, in file order
+    pub fn users(&self) -> impl Iterator<Item = &str> {
+        self.lines
+            .iter()
+            .filter_map(|line| line.split_once(':').map(|(name, _)| name))
+    }
+
+    fn position(&self, user: &str) -> Option<usize> {
+        self.lines
+            .iter()
+            .position(|line| line.split_once(':').is_some_and(|(name, _)| name == user))
+    }
+}
+
+impl fmt::Display for Htpasswd {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that a username can be stored in an htpasswd file
+pub fn validate_username(user: &str) -> Result<()> {
+    if user.is_empty() {
+        bail!("Username must not be empty");
+    }
+    if user.contains(':') || user.chars().any(char::is_control) {
+        bail!(
+            "Invalid username '{}', it must not contain ':' or control characters",
+            user.escape_debug()
+        );
+    }
+    if user.starts_with('#') {
+        bail!("Invalid username '{}', it must not start with '#'", user);
+    }
+    Ok(())
+}
+
+/// Hashes a password for an htpasswd file, bcrypt hashes use the `$2y$` prefix of `htpasswd -B`
+pub fn hash(password: &str, algorithm: HashAlgorithm, params: &HashParams) -> Result<String> {
+    if !ALGORITHMS.contains(&algorithm) {
+        bail!(
+            "htpasswd files don't support {} hashes, use bcrypt, apr1, sha256 or sha512",
+            algorithm
+        );
+    }
+
+    if algorithm == HashAlgorithm::Bcrypt {
+        params.validate(algorithm)?;
+        let hashed = bcrypt::hash_with_result(password, params.bcrypt_cost()?)
+            .context("Failed to hash password using bcrypt")?;
+        return Ok(hashed.format_for_version(bcrypt::Version::TwoY));
+    }
+
+    Ok(hash::hash(password, algorithm, params)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "# managed by pwgen2
+alice:$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1
+bob:$2y$05$abcdefghijklmnopqrstuu
+";
+
+    #[test]
+    fn test_parse() {
+        let htpasswd = Htpasswd::parse(CONTENT);
+
+        assert_eq!(htpasswd.users().collect::<Vec<_>>(), ["alice", "bob"]);
+        assert_eq!(
+            htpasswd.get("alice"),
+            Some("$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1")
+        );
+        assert_eq!(htpasswd.get("carol"), None);
+        assert_eq!(htpasswd.to_string(), CONTENT);
+    }
+
+    #[test]
+    fn test_set_remove() {
+        let mut htpasswd = Htpasswd::parse(CONTENT);
+
+        htpasswd.set("alice", "$5$salt$hash");
+        htpasswd.set("carol", "$6$salt$hash");
+        assert!(htpasswd.remove("bob"));
+        assert!(!htpasswd.remove("dave"));
+
+        assert_eq!(
+            htpasswd.to_string(),
+            "# managed by pwgen2\nalice:$5$salt$hash\ncarol:$6$salt$hash\n"
+        );
+    }
+
+    #[test]
+    fn test_load_missing() {
+        let htpasswd = Htpasswd::load("/nonexistent/.htpasswd").unwrap();
+        assert_eq!(htpasswd, Htpasswd::new());
+        assert_eq!(htpasswd.to_string(), "");
+    }
+
+    #[test]
+    fn test_save() {
+        let dir = std::env::temp_dir().join(format!("pwgen2-htpasswd-save-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".htpasswd");
+        fs::write(&path, CONTENT).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+
+        let mut htpasswd = Htpasswd::load(&path).unwrap();
+        htpasswd.set("bob", "$5$salt$hash");
+        htpasswd.save(&path).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# managed by pwgen2\nalice:$apr1$saltsalt$ijgaUyb3YLWQ1.dnGQF2v1\nbob:$5$salt$hash\n"
+        );
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+        // Only the htpasswd file is left, the temporary file was renamed
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_save_new_file() {
+        let path = std::env::temp_dir().join(format!("pwgen2-htpasswd-new-{}", process::id()));
+
+        let mut htpasswd = Htpasswd::new();
+        htpasswd.set("alice", "$5$salt$hash");
+        htpasswd.save(&path).unwrap();
+        assert_eq!(Htpasswd::load(&path).unwrap(), htpasswd);
+        fs::remove_file(&path).unwrap();
+
+        assert!(htpasswd.save("/nonexistent/.htpasswd").is_err());
+    }
+
+    #[test]
+    fn test_validate_username() {
+        assert!(validate_username("alice").is_ok());
+        assert!(validate_username("alice@example.com").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("al:ice").is_err());
+        assert!(validate_username("al\nice").is_err());
+        assert!(validate_username("#alice").is_err());
+    }
+
+    #[test]
+    fn test_hash() {
+        let params = HashParams::new().with_cost(4);
+        let hashed = super::hash("password", HashAlgorithm::Bcrypt, &params).unwrap();
+        assert!(hashed.starts_with("$2y$04$"));
+        assert!(hash::verify("password", &hashed).unwrap());
+
+        let hashed = super::hash("password", HashAlgorithm::Apr1, &HashParams::new()).unwrap();
+        assert!(hash::verify("password", &hashed).unwrap());
+
+        assert!(super::hash("password", HashAlgorithm::Argon2, &HashParams::new()).is_err());
+    }
+}
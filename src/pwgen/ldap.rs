@@ -0,0 +1,110 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::random;
+use sha1::Sha1;
+use sha2::{Digest, Sha512};
+
+/// Scheme of salted SHA-1 hashes
+pub const SSHA: &str = "{SSHA}";
+
+/// Scheme of salted SHA-512 hashes
+pub const SSHA512: &str = "{SSHA512}";
+
+/// Scheme of crypt(3) hashes
+pub const CRYPT: &str = "{CRYPT}";
+
+/// Salt length, `slappasswd` uses 4 bytes
+pub const SALT_LEN: usize = 8;
+
+/// Returns a random salt of `SALT_LEN` bytes
+pub fn gensalt() -> [u8; SALT_LEN] {
+    random()
+}
+
+/// Returns the `{SSHA}` hash, base64 of the SHA-1 of the password and salt followed by the salt
+pub fn ssha(password: &[u8], salt: &[u8]) -> String {
+    salted::<Sha1>(SSHA, password, salt)
+}
+
+/// Returns the `{SSHA512}` hash, base64 of the SHA-512 of the password and salt followed by the salt
+pub fn ssha512(password: &[u8], salt: &[u8]) -> String {
+    salted::<Sha512>(SSHA512, password, salt)
+}
+
+/// Returns the salt of a `{SSHA}` or `{SSHA512}` hash, the scheme is case-insensitive
+pub fn salt(hashed: &str) -> Result<Vec<u8>> {
+    let (scheme, digest_len) = if has_scheme(hashed, SSHA512) {
+        (SSHA512, 64)
+    } else if has_scheme(hashed, SSHA) {
+        (SSHA, 20)
+    } else {
+        return Err(anyhow!("Unknown LDAP password scheme"));
+    };
+
+    let decoded = STANDARD
+        .decode(&hashed[scheme.len()..])
+        .map_err(|_| anyhow!("Invalid {} hash", scheme))?;
+    if decoded.len() <= digest_len {
+        return Err(anyhow!("Invalid {} hash, the salt is missing", scheme));
+    }
+
+    Ok(decoded[digest_len..].to_vec())
+}
+
+/// Strips the `{CRYPT}` scheme, returns the crypt(3) hash
+pub fn strip_crypt(hashed: &str) -> Option<&str> {
+    has_scheme(hashed, CRYPT).then(|| &hashed[CRYPT.len()..])
+}
+
+/// Whether a hash starts with the scheme, ignoring case like LDAP servers do
+pub fn has_scheme(hashed: &str, scheme: &str) -> bool {
+    hashed
+        .get(..scheme.len())
+        .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+}
+
+fn salted<D: Digest>(scheme: &str, password: &[u8], salt: &[u8]) -> String {
+    let mut value = D::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .finalize()
+        .to_vec();
+    value.extend_from_slice(salt);
+
+    format!("{}{}", scheme, STANDARD.encode(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Computed with Python's `hashlib`
+    const SSHA_HASH: &str = "{SSHA}yrht1iYXEIkejLVu42JWkadd80RzYWx0c2FsdA==";
+    const SSHA512_HASH: &str = "{SSHA512}9ZxHVj4YomwqqFiYKcIjExMLx2ZblYfXRGc4KMqbgvHq2+HOgwiTIi+eO/Uam/8D0beDAkGpvx14+UFlfBskLnNhbHRzYWx0";
+
+    #[test]
+    fn test_ssha() {
+        assert_eq!(ssha(b"password", b"saltsalt"), SSHA_HASH);
+        assert_eq!(ssha512(b"password", b"saltsalt"), SSHA512_HASH);
+    }
+
+    #[test]
+    fn test_salt() {
+        assert_eq!(salt(SSHA_HASH).unwrap(), b"saltsalt");
+        assert_eq!(salt(SSHA512_HASH).unwrap(), b"saltsalt");
+        assert_eq!(
+            salt(&SSHA_HASH.replace("{SSHA}", "{ssha}")).unwrap(),
+            b"saltsalt"
+        );
+        assert!(salt("{SSHA}!!").is_err());
+        assert!(salt("{SSHA}yrht1iYXEIkejLVu42JWkadd80Q=").is_err());
+        assert!(salt("{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=").is_err());
+    }
+
+    #[test]
+    fn test_strip_crypt() {
+        assert_eq!(strip_crypt("{CRYPT}$6$salt$hash"), Some("$6$salt$hash"));
+        assert_eq!(strip_crypt("{crypt}$6$salt$hash"), Some("$6$salt$hash"));
+        assert_eq!(strip_crypt("$6$salt$hash"), None);
+    }
+}
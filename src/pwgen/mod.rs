@@ -1,9 +1,35 @@
+pub mod apr1;
+pub mod calibrate;
 pub mod config;
+pub mod django;
+pub mod entropy;
 pub mod generator;
 pub mod hash;
+pub mod htpasswd;
+pub mod keycloak;
+pub mod ldap;
+pub mod mosquitto;
+pub mod mysql;
+#[cfg(feature = "openssl")]
+pub mod openssl;
+pub mod passphrase;
+pub mod pattern;
+pub mod pepper;
+pub mod phpass;
+pub mod postgres;
+pub mod pronounceable;
+pub mod regex;
+pub mod shadow;
+pub mod strength;
+pub mod werkzeug;
+pub mod yescrypt;
 
 pub use config::PasswordConfig;
-pub use generator::generate_password;
+pub use generator::{generate_password, generate_password_with_rng, PasswordGenerator};
+pub use passphrase::{generate_passphrase, PassphraseConfig};
+pub use pattern::Pattern;
+pub use pronounceable::{generate_pronounceable, PronounceableGenerator};
+pub use regex::{generate_from_regex, RegexPattern};
 
 /// Character sets for password generation
 pub struct CharacterSets {
@@ -25,5 +51,9 @@ pub const DEFAULT_CHARSETS: CharacterSets = CharacterSets {
     symbols: "!@#$%&.-_*",
 };
 
+/// Base64 alphabet of crypt(3) hashes
+pub(crate) const CRYPT_ITOA64: &[u8; 64] =
+    b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
 /// Ambiguous characters to be avoided if `avoid_ambiguous` is enabled
 pub const AMBIGUOUS_CHARS: &str = "0O1Il5S";
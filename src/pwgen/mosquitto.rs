@@ -0,0 +1,87 @@
+use crate::pwgen::hash::Pbkdf2Algorithm;
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::random;
+
+const PREFIX: &str = "$7$";
+
+/// Default iterations, `mosquitto_passwd` uses 101 but OWASP recommends 210,000
+pub const DEFAULT_ITERATIONS: u32 = 210_000;
+
+/// Salt length of `mosquitto_passwd`
+pub const SALT_LEN: usize = 12;
+
+/// Returns a random salt of `SALT_LEN` bytes
+pub fn gensalt() -> [u8; SALT_LEN] {
+    random()
+}
+
+/// Returns the `$7$<iterations>$<salt>$<hash>` PBKDF2-HMAC-SHA512 hash of `mosquitto_passwd`
+pub fn hash(password: &[u8], salt: &[u8], iterations: u32) -> Result<String> {
+    let key = Pbkdf2Algorithm::Sha512.derive(password, salt, iterations, 64)?;
+    Ok(format!(
+        "{}{}${}${}",
+        PREFIX,
+        iterations,
+        STANDARD.encode(salt),
+        STANDARD.encode(key)
+    ))
+}
+
+/// Returns the iterations and the salt of a hash
+pub fn parse(hashed: &str) -> Result<(u32, Vec<u8>)> {
+    let invalid = || anyhow!("Invalid Mosquitto PBKDF2 hash");
+
+    let rest = hashed.strip_prefix(PREFIX).ok_or_else(invalid)?;
+    let fields: Vec<&str> = rest.split('$').collect();
+    let [iterations, salt, _] = fields[..] else {
+        return Err(invalid());
+    };
+
+    let iterations = iterations.parse().map_err(|_| invalid())?;
+    let salt = STANDARD.decode(salt).map_err(|_| invalid())?;
+    if iterations == 0 || salt.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok((iterations, salt))
+}
+
+/// Whether a string is a Mosquitto hash, libxcrypt's `$7$` scrypt has no iterations field
+pub fn is_hash(hashed: &str) -> bool {
+    hashed
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.split_once('$'))
+        .is_some_and(|(iterations, _)| {
+            !iterations.is_empty() && iterations.bytes().all(|b| b.is_ascii_digit())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Computed with Python's `hashlib.pbkdf2_hmac`
+    const HASH: &str = "$7$1000$c2FsdHNhbHRzYWx0$qEWuBHP82uzPrybCUpp9FMGVDoszagDwOgBhAY44y2gPun4\
+        RFBM9zjAnhQjJRk+l/dEEhatLCI65Yj/OYq4dcQ==";
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(b"password", b"saltsaltsalt", 1000).unwrap(), HASH);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(HASH).unwrap(), (1000, b"saltsaltsalt".to_vec()));
+        assert!(parse("$7$1000$c2FsdA==").is_err());
+        assert!(parse("$7$x$c2FsdA==$a2V5").is_err());
+        assert!(parse("$7$1000$!!$a2V5").is_err());
+    }
+
+    #[test]
+    fn test_is_hash() {
+        assert!(is_hash(HASH));
+        assert!(!is_hash("$7$CU..../....2fe2k$hash"));
+        assert!(!is_hash("$6$rounds=5000$salt$hash"));
+    }
+}
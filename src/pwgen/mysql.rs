@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+use std::ops::RangeInclusive;
+
+use super::CRYPT_ITOA64 as ITOA64;
+
+const SHA2_PREFIX: &str = "$A$";
+
+/// Default `caching_sha2_password` rounds, the `caching_sha2_password_digest_rounds` default
+pub const SHA2_DEFAULT_ROUNDS: u32 = 5000;
+
+/// Valid `caching_sha2_password` rounds, stored in thousands as 3 hex digits
+pub const SHA2_ROUNDS: RangeInclusive<u32> = 5000..=4_095_000;
+
+/// Salt length of `caching_sha2_password`
+pub const SHA2_SALT_LEN: usize = 20;
+
+/// Returns the `mysql_native_password` hash, `*` and the hex SHA1 of the SHA1 of the password
+pub fn native_hash(password: &[u8]) -> String {
+    let digest = Sha1::digest(Sha1::digest(password));
+    let hex: String = digest.iter().map(|b| format!("{:02X}", b)).collect();
+    format!("*{}", hex)
+}
+
+/// Whether a string is a `mysql_native_password` hash
+pub fn is_native_hash(hashed: &str) -> bool {
+    hashed.len() == 41
+        && hashed.starts_with('*')
+        && hashed[1..].bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Returns a random `caching_sha2_password` salt from the crypt alphabet
+pub fn sha2_gensalt() -> String {
+    let mut rng = rand::rng();
+    (0..SHA2_SALT_LEN)
+        .map(|_| ITOA64[rng.random_range(0..ITOA64.len())] as char)
+        .collect()
+}
+
+/// Returns the `caching_sha2_password` hash, `$A$<rounds / 1000>$<salt><hash>`
+///
+/// It is SHA-256 crypt with a 20 bytes salt, `rounds` must be a multiple of 1000.
+pub fn sha2_hash(password: &[u8], salt: &str, rounds: u32) -> String {
+    let digest = sha256_crypt(password, salt.as_bytes(), rounds);
+    format!(
+        "{}{:03X}${}{}",
+        SHA2_PREFIX,
+        rounds / 1000,
+        salt,
+        encode_sha256(&digest)
+    )
+}
+
+/// Returns the rounds and the salt of a `caching_sha2_password` hash
+pub fn sha2_parse(hashed: &str) -> Result<(u32, &str)> {
+    let invalid = || anyhow!("Invalid caching_sha2_password hash");
+
+    let rest = hashed.strip_prefix(SHA2_PREFIX).ok_or_else(invalid)?;
+    let (count, rest) = rest.split_once('$').ok_or_else(invalid)?;
+    if count.len() != 3 || rest.len() != SHA2_SALT_LEN + 43 {
+        return Err(invalid());
+    }
+
+    let rounds = u32::from_str_radix(count, 16).map_err(|_| invalid())? * 1000;
+    if !SHA2_ROUNDS.contains(&rounds) {
+        return Err(invalid());
+    }
+
+    let salt = rest.get(..SHA2_SALT_LEN).ok_or_else(invalid)?;
+    Ok((rounds, salt))
+}
+
+/// SHA-256 crypt from Ulrich Drepper's specification, without its 16 bytes salt limit
+fn sha256_crypt(password: &[u8], salt: &[u8], rounds: u32) -> [u8; 32] {
+    let b = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut a = Sha256::new().chain_update(password).chain_update(salt);
+    let mut remaining = password.len();
+    while remaining > 32 {
+        a.update(b);
+        remaining -= 32;
+    }
+    a.update(&b[..remaining]);
+
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 != 0 {
+            a.update(b);
+        } else {
+            a.update(password);
+        }
+        length >>= 1;
+    }
+    let a = a.finalize();
+
+    let mut dp = Sha256::new();
+    for _ in 0..password.len() {
+        dp.update(password);
+    }
+    let p = repeat(&dp.finalize(), password.len());
+
+    let mut ds = Sha256::new();
+    for _ in 0..16 + usize::from(a[0]) {
+        ds.update(salt);
+    }
+    let s = repeat(&ds.finalize(), salt.len());
+
+    let mut c: [u8; 32] = a.into();
+    for i in 0..rounds {
+        let mut ctx = Sha256::new();
+        if i & 1 != 0 {
+            ctx.update(&p);
+        } else {
+            ctx.update(c);
+        }
+        if i % 3 != 0 {
+            ctx.update(&s);
+        }
+        if i % 7 != 0 {
+            ctx.update(&p);
+        }
+        if i & 1 != 0 {
+            ctx.update(c);
+        } else {
+            ctx.update(&p);
+        }
+        c = ctx.finalize().into();
+    }
+
+    c
+}
+
+/// Repeats a digest up to `len` bytes
+fn repeat(digest: &[u8], len: usize) -> Vec<u8> {
+    digest.iter().copied().cycle().take(len).collect()
+}
+
+/// Encodes a SHA-256 crypt digest with the byte order of crypt(3)
+fn encode_sha256(digest: &[u8; 32]) -> String {
+    const ORDER: [(usize, usize, usize); 10] = [
+        (0, 10, 20),
+        (21, 1, 11),
+        (12, 22, 2),
+        (3, 13, 23),
+        (24, 4, 14),
+        (15, 25, 5),
+        (6, 16, 26),
+        (27, 7, 17),
+        (18, 28, 8),
+        (9, 19, 29),
+    ];
+
+    let mut out = String::with_capacity(43);
+    let mut push = |value: u32, chars: usize| {
+        let mut value = value;
+        for _ in 0..chars {
+            out.push(ITOA64[(value & 0x3f) as usize] as char);
+            value >>= 6;
+        }
+    };
+
+    for (b2, b1, b0) in ORDER {
+        push(
+            u32::from(digest[b2]) << 16 | u32::from(digest[b1]) << 8 | u32::from(digest[b0]),
+            4,
+        );
+    }
+    push(u32::from(digest[31]) << 8 | u32::from(digest[30]), 3);
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_native_hash() {
+        assert_eq!(
+            native_hash(b"password"),
+            "*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19"
+        );
+        assert!(is_native_hash(&native_hash(b"")));
+        assert!(!is_native_hash("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E1"));
+        assert!(!is_native_hash("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E1Z"));
+    }
+
+    #[test]
+    fn test_sha256_crypt() {
+        // Computed with libxcrypt's `crypt(3)`
+        let vectors: [(&[u8], &[u8], u32, &str); 3] = [
+            (
+                b"password",
+                b"saltstringsaltst",
+                5000,
+                "Ekah6lEFydzYloW2P/P45IGa7Yv0vGRQi.McSitgqd9",
+            ),
+            (
+                b"",
+                b"abc",
+                1000,
+                "sP9FmVrTEqPcRDE7OxGDY0efugGF1dtCtqYcUsX9wmD",
+            ),
+            (
+                b"a much longer password that is over thirty two bytes long!",
+                b"0123456789abcdef",
+                5000,
+                "vQJgkPmw.QFNo2YzVw/PbrjYYzsbDGC2zri/Nlizsr.",
+            ),
+        ];
+
+        for (password, salt, rounds, expected) in vectors {
+            assert_eq!(
+                encode_sha256(&sha256_crypt(password, salt, rounds)),
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_sha2_hash() {
+        let salt = "abcdefghijklmnopqrst";
+        let hashed = sha2_hash(b"password", salt, 5000);
+
+        assert!(hashed.starts_with("$A$005$abcdefghijklmnopqrst"));
+        assert_eq!(hashed.len(), 7 + SHA2_SALT_LEN + 43);
+        assert_eq!(sha2_parse(&hashed).unwrap(), (5000, salt));
+        assert!(sha2_hash(b"password", salt, 10000).starts_with("$A$00A$"));
+    }
+
+    #[test]
+    fn test_sha2_parse_invalid() {
+        assert!(sha2_parse("$A$005$short").is_err());
+        assert!(sha2_parse("$A$5$abcdefghijklmnopqrst").is_err());
+        assert!(sha2_parse("$5$rounds=5000$salt$hash").is_err());
+        assert!(sha2_parse(&format!("$A$004${}", "a".repeat(63))).is_err());
+    }
+
+    #[test]
+    fn test_sha2_gensalt() {
+        let salt = sha2_gensalt();
+        assert_eq!(salt.len(), SHA2_SALT_LEN);
+        assert!(salt.bytes().all(|b| ITOA64.contains(&b)));
+    }
+}
@@ -0,0 +1,493 @@
+use crate::pwgen::{
+    apr1,
+    hash::{HashAlgorithm, Pbkdf2Algorithm},
+    CRYPT_ITOA64 as ITOA64,
+};
+use ::openssl::{
+    error::ErrorStack,
+    hash::{Hasher, MessageDigest},
+    pkcs5::pbkdf2_hmac,
+    provider::Provider,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use pbkdf2::{
+    password_hash::{
+        self, Decimal, Ident, Output, ParamsString, PasswordHash, PasswordHasher, Salt,
+    },
+    Algorithm as Pbkdf2Ident, Params as Pbkdf2Params,
+};
+use rand::Rng;
+use std::sync::OnceLock;
+
+/// Algorithms built on the SHA-2 digests and PBKDF2 of the FIPS provider
+pub const FIPS_ALGORITHMS: [HashAlgorithm; 7] = [
+    HashAlgorithm::Pbkdf2,
+    HashAlgorithm::Sha512,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::LdapCrypt,
+    HashAlgorithm::Django,
+    HashAlgorithm::Werkzeug,
+    HashAlgorithm::Keycloak,
+];
+
+/// Salt length of SHA crypt, longer salts are truncated
+const SHA_CRYPT_SALT_LEN: usize = 16;
+
+const SHA_CRYPT_DEFAULT_ROUNDS: u32 = 5000;
+
+const SHA_CRYPT_ROUNDS: std::ops::RangeInclusive<u32> = 1000..=999_999_999;
+
+/// Order of the digest bytes in SHA-256 crypt hashes, by groups of three
+const SHA256_ORDER: [(usize, usize, usize); 10] = [
+    (0, 10, 20),
+    (21, 1, 11),
+    (12, 22, 2),
+    (3, 13, 23),
+    (24, 4, 14),
+    (15, 25, 5),
+    (6, 16, 26),
+    (27, 7, 17),
+    (18, 28, 8),
+    (9, 19, 29),
+];
+
+/// Order of the digest bytes in SHA-512 crypt hashes, by groups of three
+const SHA512_ORDER: [(usize, usize, usize); 21] = [
+    (0, 21, 42),
+    (22, 43, 1),
+    (44, 2, 23),
+    (3, 24, 45),
+    (25, 46, 4),
+    (47, 5, 26),
+    (6, 27, 48),
+    (28, 49, 7),
+    (50, 8, 29),
+    (9, 30, 51),
+    (31, 52, 10),
+    (53, 11, 32),
+    (12, 33, 54),
+    (34, 55, 13),
+    (56, 14, 35),
+    (15, 36, 57),
+    (37, 58, 16),
+    (59, 17, 38),
+    (18, 39, 60),
+    (40, 61, 19),
+    (62, 20, 41),
+];
+
+/// Order of the digest bytes in MD5 crypt hashes, by groups of three
+const MD5_ORDER: [(usize, usize, usize); 5] =
+    [(0, 6, 12), (1, 7, 13), (2, 8, 14), (3, 9, 15), (4, 10, 5)];
+
+static FIPS_PROVIDER: OnceLock<Provider> = OnceLock::new();
+
+/// Loads the FIPS provider, hashes then only use its validated algorithms
+///
+/// OpenSSL finds the module in `OPENSSL_MODULES` and its configuration through
+/// `OPENSSL_CONF`, like the `fipsmodule.cnf` written by `openssl fipsinstall`.
+pub fn enable_fips() -> Result<()> {
+    if FIPS_PROVIDER.get().is_none() {
+        let provider =
+            Provider::load(None, "fips").context("Failed to load the OpenSSL FIPS provider")?;
+        let _ = FIPS_PROVIDER.set(provider);
+    }
+    Ok(())
+}
+
+/// Whether `enable_fips` loaded the FIPS provider
+pub fn fips_enabled() -> bool {
+    FIPS_PROVIDER.get().is_some()
+}
+
+/// Fails for the algorithms outside `FIPS_ALGORITHMS` once FIPS mode is enabled
+pub fn check_fips(algorithm: HashAlgorithm) -> Result<()> {
+    if fips_enabled() && !FIPS_ALGORITHMS.contains(&algorithm) {
+        bail!("{} is not allowed in FIPS mode", algorithm);
+    }
+    Ok(())
+}
+
+/// Returns a random crypt(3) setting of MD5 crypt, APR1-MD5, SHA-256 or SHA-512 crypt
+///
+/// The rounds only apply to SHA crypt and are left out at the default, like
+/// `openssl passwd -5` and `-6` do.
+pub fn gensalt(algorithm: HashAlgorithm, rounds: usize) -> Result<String> {
+    let salt = |len: usize| -> String {
+        let mut rng = rand::rng();
+        (0..len)
+            .map(|_| ITOA64[rng.random_range(0..ITOA64.len())] as char)
+            .collect()
+    };
+    let sha_crypt = |magic: &str| {
+        if rounds == SHA_CRYPT_DEFAULT_ROUNDS as usize {
+            format!("{}{}", magic, salt(SHA_CRYPT_SALT_LEN))
+        } else {
+            format!("{}rounds={}${}", magic, rounds, salt(SHA_CRYPT_SALT_LEN))
+        }
+    };
+
+    match algorithm {
+        HashAlgorithm::Md5Crypt => Ok(format!("$1${}", salt(apr1::SALT_LEN))),
+        HashAlgorithm::Apr1 => Ok(format!("$apr1${}", salt(apr1::SALT_LEN))),
+        HashAlgorithm::Sha256 => Ok(sha_crypt("$5$")),
+        HashAlgorithm::Sha512 => Ok(sha_crypt("$6$")),
+        _ => Err(anyhow!(
+            "{} is not a crypt(3) algorithm of OpenSSL",
+            algorithm
+        )),
+    }
+}
+
+/// Hashes a password like `openssl passwd -1`, `-apr1`, `-5` or `-6`
+///
+/// The setting is a `$1$`, `$apr1$`, `$5$` or `$6$` hash or its prefix up to the salt.
+pub fn crypt(password: &[u8], setting: &str) -> Result<String> {
+    const MAGICS: [&str; 4] = ["$1$", "$apr1$", "$5$", "$6$"];
+
+    let magic = MAGICS
+        .into_iter()
+        .find(|magic| setting.starts_with(magic))
+        .ok_or_else(|| anyhow!("Unsupported crypt(3) setting"))?;
+    let rest = &setting[magic.len()..];
+
+    match magic {
+        "$5$" => sha_crypt(password, MessageDigest::sha256(), magic, rest),
+        "$6$" => sha_crypt(password, MessageDigest::sha512(), magic, rest),
+        _ => md5_crypt(password, magic, rest),
+    }
+}
+
+/// Derives a key of `len` bytes with PBKDF2, as `Pbkdf2Algorithm::derive` does
+///
+/// The FIPS provider refuses salts under 16 bytes and keys under 14 bytes.
+pub fn pbkdf2(
+    digest: Pbkdf2Algorithm,
+    password: &[u8],
+    salt: &[u8],
+    rounds: u32,
+    len: usize,
+) -> Result<Vec<u8>> {
+    let md = match digest {
+        Pbkdf2Algorithm::Sha256 => MessageDigest::sha256(),
+        Pbkdf2Algorithm::Sha512 => MessageDigest::sha512(),
+    };
+
+    let mut key = vec![0u8; len];
+    pbkdf2_hmac(password, salt, rounds as usize, md, &mut key)?;
+    Ok(key)
+}
+
+/// PBKDF2 in PHC string format with OpenSSL, the same hashes as the `pbkdf2` crate
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pbkdf2;
+
+impl PasswordHasher for Pbkdf2 {
+    type Params = Pbkdf2Params;
+
+    fn hash_password_customized<'a>(
+        &self,
+        password: &[u8],
+        alg_id: Option<Ident<'a>>,
+        version: Option<Decimal>,
+        params: Pbkdf2Params,
+        salt: impl Into<Salt<'a>>,
+    ) -> password_hash::Result<PasswordHash<'a>> {
+        let ident = alg_id.unwrap_or_else(|| Pbkdf2Ident::default().ident());
+        let digest = match Pbkdf2Ident::try_from(ident)? {
+            Pbkdf2Ident::Pbkdf2Sha256 => Pbkdf2Algorithm::Sha256,
+            Pbkdf2Ident::Pbkdf2Sha512 => Pbkdf2Algorithm::Sha512,
+            _ => return Err(password_hash::Error::Algorithm),
+        };
+        if version.is_some() {
+            return Err(password_hash::Error::Version);
+        }
+
+        let salt = salt.into();
+        let mut salt_buf = [0u8; Salt::MAX_LENGTH];
+        let salt_bytes = salt.decode_b64(&mut salt_buf)?;
+        let key = pbkdf2(
+            digest,
+            password,
+            salt_bytes,
+            params.rounds,
+            params.output_length,
+        )
+        .map_err(|_| password_hash::Error::Crypto)?;
+
+        Ok(PasswordHash {
+            algorithm: ident,
+            version: None,
+            params: ParamsString::try_from(params)?,
+            salt: Some(salt),
+            hash: Some(Output::new(&key)?),
+        })
+    }
+}
+
+/// MD5 crypt, APR1-MD5 only differs by its magic
+fn md5_crypt(password: &[u8], magic: &str, rest: &str) -> Result<String> {
+    let salt = rest.split('$').next().unwrap_or_default();
+    let salt = &salt.as_bytes()[..salt.len().min(apr1::SALT_LEN)];
+    let md = MessageDigest::md5();
+
+    let alternate = digest(md, &[password, salt, password])?;
+
+    let mut ctx = Hasher::new(md)?;
+    ctx.update(password)?;
+    ctx.update(magic.as_bytes())?;
+    ctx.update(salt)?;
+    update_repeated(&mut ctx, &alternate, password.len())?;
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 == 1 {
+            ctx.update(&[0])?;
+        } else {
+            ctx.update(&password[..1])?;
+        }
+        i >>= 1;
+    }
+    let mut result = ctx.finish()?.to_vec();
+
+    for i in 0..1000 {
+        if i & 1 == 1 {
+            ctx.update(password)?;
+        } else {
+            ctx.update(&result)?;
+        }
+        if i % 3 != 0 {
+            ctx.update(salt)?;
+        }
+        if i % 7 != 0 {
+            ctx.update(password)?;
+        }
+        if i & 1 == 1 {
+            ctx.update(&result)?;
+        } else {
+            ctx.update(password)?;
+        }
+        result = ctx.finish()?.to_vec();
+    }
+
+    Ok(format!(
+        "{}{}${}",
+        magic,
+        String::from_utf8_lossy(salt),
+        encode(&result, &MD5_ORDER, &[11])
+    ))
+}
+
+/// SHA-256 and SHA-512 crypt as specified by Ulrich Drepper
+fn sha_crypt(password: &[u8], md: MessageDigest, magic: &str, rest: &str) -> Result<String> {
+    let (rounds, rest) = match rest.strip_prefix("rounds=") {
+        Some(rest) => {
+            let (rounds, rest) = rest
+                .split_once('$')
+                .ok_or_else(|| anyhow!("Invalid SHA crypt setting"))?;
+            let rounds = rounds
+                .parse()
+                .ok()
+                .filter(|rounds| SHA_CRYPT_ROUNDS.contains(rounds))
+                .ok_or_else(|| anyhow!("Invalid SHA crypt rounds '{}'", rounds))?;
+            (Some(rounds), rest)
+        }
+        None => (None, rest),
+    };
+    let salt = rest.split('$').next().unwrap_or_default();
+    let salt = &salt.as_bytes()[..salt.len().min(SHA_CRYPT_SALT_LEN)];
+
+    let alternate = digest(md, &[password, salt, password])?;
+
+    let mut ctx = Hasher::new(md)?;
+    ctx.update(password)?;
+    ctx.update(salt)?;
+    update_repeated(&mut ctx, &alternate, password.len())?;
+    let mut i = password.len();
+    while i > 0 {
+        if i & 1 == 1 {
+            ctx.update(&alternate)?;
+        } else {
+            ctx.update(password)?;
+        }
+        i >>= 1;
+    }
+    let mut result = ctx.finish()?.to_vec();
+
+    for _ in 0..password.len() {
+        ctx.update(password)?;
+    }
+    let p_bytes = repeated(&ctx.finish()?, password.len());
+    for _ in 0..16 + usize::from(result[0]) {
+        ctx.update(salt)?;
+    }
+    let s_bytes = repeated(&ctx.finish()?, salt.len());
+
+    for i in 0..rounds.unwrap_or(SHA_CRYPT_DEFAULT_ROUNDS) {
+        if i & 1 == 1 {
+            ctx.update(&p_bytes)?;
+        } else {
+            ctx.update(&result)?;
+        }
+        if i % 3 != 0 {
+            ctx.update(&s_bytes)?;
+        }
+        if i % 7 != 0 {
+            ctx.update(&p_bytes)?;
+        }
+        if i & 1 == 1 {
+            ctx.update(&result)?;
+        } else {
+            ctx.update(&p_bytes)?;
+        }
+        result = ctx.finish()?.to_vec();
+    }
+
+    let encoded = if result.len() == 32 {
+        encode(&result, &SHA256_ORDER, &[31, 30])
+    } else {
+        encode(&result, &SHA512_ORDER, &[63])
+    };
+    let rounds = rounds.map_or_else(String::new, |rounds| format!("rounds={}$", rounds));
+
+    Ok(format!(
+        "{}{}{}${}",
+        magic,
+        rounds,
+        String::from_utf8_lossy(salt),
+        encoded
+    ))
+}
+
+fn digest(md: MessageDigest, parts: &[&[u8]]) -> Result<Vec<u8>, ErrorStack> {
+    let mut ctx = Hasher::new(md)?;
+    for part in parts {
+        ctx.update(part)?;
+    }
+    Ok(ctx.finish()?.to_vec())
+}
+
+/// Feeds `len` bytes of a digest repeated as many times as needed
+fn update_repeated(ctx: &mut Hasher, digest: &[u8], len: usize) -> Result<(), ErrorStack> {
+    let mut remaining = len;
+    while remaining > 0 {
+        let len = remaining.min(digest.len());
+        ctx.update(&digest[..len])?;
+        remaining -= len;
+    }
+    Ok(())
+}
+
+fn repeated(digest: &[u8], len: usize) -> Vec<u8> {
+    digest.iter().copied().cycle().take(len).collect()
+}
+
+/// Encodes a digest with the crypt(3) base64 in the byte order of the algorithm
+///
+/// The bytes left after the groups of three are encoded together, the first one highest.
+fn encode(digest: &[u8], order: &[(usize, usize, usize)], tail: &[usize]) -> String {
+    let mut encoded = String::with_capacity(digest.len().div_ceil(3) * 4);
+    let mut push = |mut value: u32, chars: usize| {
+        for _ in 0..chars {
+            encoded.push(ITOA64[(value & 0x3f) as usize] as char);
+            value >>= 6;
+        }
+    };
+
+    for &(a, b, c) in order {
+        push(
+            u32::from(digest[a]) << 16 | u32::from(digest[b]) << 8 | u32::from(digest[c]),
+            4,
+        );
+    }
+    let value = tail
+        .iter()
+        .fold(0, |value, &i| value << 8 | u32::from(digest[i]));
+    push(value, tail.len() + 1);
+
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crypt() {
+        // Computed with `openssl passwd`, and libxcrypt for the rounds
+        let vectors: [(&[u8], &str); 6] = [
+            (b"password", "$1$saltsalt$qjXMvbEw8oaL.CzflDtaK/"),
+            (b"", "$1$xx$Qiw/Pi26KfpLUDkeOIdG.."),
+            (b"password", "$apr1$abcdefgh$FBwExRW4dCc8aL.OvjpIE1"),
+            (
+                b"password",
+                "$5$saltsaltsaltsalt$WsFBeg1qQ90JL3VkUTuM7xVV/5njhLngIVm6ftSnBR2",
+            ),
+            (
+                b"password",
+                "$6$saltsaltsaltsalt$bcXJ8qxwY5sQ4v8MTl.0B1jeZ0z0JlA9jjmbUoCJZ.1wYXiLTU.\
+                 q2ILyrDJLm890lyfuF7sWAeli0yjOyFPkf0",
+            ),
+            (
+                b"a very long password that is longer than sixty-four bytes, the size of SHA-512",
+                "$6$rounds=1000$saltsalt$jXnaCx3rroclLCAwvCYMObC.G.6nZII.9btVkiiR5i9udh1M\
+                 zaFyM3/9JhdQv/ITy1/21hMyd0AODX7zOsEE/1",
+            ),
+        ];
+
+        for (password, hashed) in vectors {
+            assert_eq!(crypt(password, hashed).unwrap(), hashed);
+        }
+    }
+
+    #[test]
+    fn test_crypt_invalid() {
+        assert!(crypt(b"password", "$5$rounds=10$saltsalt").is_err());
+        assert!(crypt(b"password", "$5$rounds=x$saltsalt").is_err());
+        assert!(crypt(b"password", "$2b$12$saltsalt").is_err());
+    }
+
+    #[test]
+    fn test_gensalt() {
+        let setting = gensalt(HashAlgorithm::Sha512, 5000).unwrap();
+        assert!(setting.starts_with("$6$"));
+        assert_eq!(setting.len(), 3 + SHA_CRYPT_SALT_LEN);
+        assert!(gensalt(HashAlgorithm::Sha256, 6000)
+            .unwrap()
+            .starts_with("$5$rounds=6000$"));
+        assert!(gensalt(HashAlgorithm::Md5Crypt, 0)
+            .unwrap()
+            .starts_with("$1$"));
+        assert!(gensalt(HashAlgorithm::Bcrypt, 0).is_err());
+    }
+
+    #[test]
+    fn test_pbkdf2() {
+        let phc = Pbkdf2
+            .hash_password_customized(
+                b"password",
+                Some(Pbkdf2Ident::Pbkdf2Sha256.ident()),
+                None,
+                Pbkdf2Params {
+                    rounds: 1000,
+                    output_length: 32,
+                },
+                Salt::from_b64("c2FsdHNhbHRzYWx0c2FsdA").unwrap(),
+            )
+            .unwrap()
+            .to_string();
+        let expected = pbkdf2::Pbkdf2
+            .hash_password_customized(
+                b"password",
+                Some(Pbkdf2Ident::Pbkdf2Sha256.ident()),
+                None,
+                Pbkdf2Params {
+                    rounds: 1000,
+                    output_length: 32,
+                },
+                Salt::from_b64("c2FsdHNhbHRzYWx0c2FsdA").unwrap(),
+            )
+            .unwrap()
+            .to_string();
+        assert_eq!(phc, expected);
+    }
+}
@@ -0,0 +1,296 @@
+use crate::pwgen::{config::PasswordConfigError, DEFAULT_CHARSETS};
+use rand::{rng, CryptoRng, Rng};
+use std::{
+    fs, io,
+    path::Path,
+    sync::{Arc, OnceLock},
+};
+
+/// Embedded default word list (BIP39 English, 2048 words)
+pub const DEFAULT_WORDLIST: &str = include_str!("wordlists/bip39_english.txt");
+
+/// Words of the embedded list, parsed on first use
+///
+/// Lines are parsed like [`load_word_list`] does, a list in the EFF diceware
+/// format can be embedded as is.
+pub fn default_word_list() -> &'static [&'static str] {
+    static WORDS: OnceLock<Vec<&'static str>> = OnceLock::new();
+
+    WORDS.get_or_init(|| {
+        DEFAULT_WORDLIST
+            .lines()
+            .filter_map(|line| line.split_whitespace().last())
+            .collect()
+    })
+}
+
+/// Configuration for passphrase generation
+#[derive(Debug, Clone)]
+pub struct PassphraseConfig {
+    /// Number of words in the passphrase
+    pub words: u8,
+
+    /// Separator placed between words
+    pub separator: String,
+
+    /// Capitalize the first letter of every word
+    pub capitalize: bool,
+
+    /// Append a random digit to the passphrase
+    pub append_digit: bool,
+
+    /// Append a random symbol to the passphrase
+    pub append_symbol: bool,
+
+    /// Custom word list, the embedded list is used when `None`
+    pub word_list: Option<Arc<[String]>>,
+}
+
+impl Default for PassphraseConfig {
+    /// Creates a default passphrase configuration:
+    /// - 6 words separated by `-`
+    /// - No capitalization and nothing appended
+    /// - Uses the embedded word list
+    fn default() -> Self {
+        Self {
+            words: 6,
+            separator: "-".to_string(),
+            capitalize: false,
+            append_digit: false,
+            append_symbol: false,
+            word_list: None,
+        }
+    }
+}
+
+impl PassphraseConfig {
+    /// Creates a new passphrase configuration with the specified number of words
+    pub fn new(words: u8) -> Result<Self, PasswordConfigError> {
+        if words == 0 {
+            return Err(PasswordConfigError::ZeroWords);
+        }
+
+        Ok(Self {
+            words,
+            ..Self::default()
+        })
+    }
+
+    /// Builder method to set the separator placed between words
+    pub fn with_separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Builder method to set whether to capitalize every word
+    pub const fn with_capitalize(mut self, capitalize: bool) -> Self {
+        self.capitalize = capitalize;
+        self
+    }
+
+    /// Builder method to set whether to append a random digit
+    pub const fn with_digit(mut self, include: bool) -> Self {
+        self.append_digit = include;
+        self
+    }
+
+    /// Builder method to set whether to append a random symbol
+    pub const fn with_symbol(mut self, include: bool) -> Self {
+        self.append_symbol = include;
+        self
+    }
+
+    /// Builder method to use a custom word list
+    pub fn with_word_list(mut self, words: Vec<String>) -> Self {
+        self.word_list = Some(words.into());
+        self
+    }
+
+    /// Number of words available to pick from
+    pub fn word_list_len(&self) -> usize {
+        self.word_list
+            .as_ref()
+            .map_or_else(|| default_word_list().len(), |list| list.len())
+    }
+
+    /// Bits of entropy of a passphrase generated with this configuration
+    pub fn entropy_bits(&self) -> f64 {
+        let mut bits = f64::from(self.words) * (self.word_list_len() as f64).log2();
+
+        if self.append_digit {
+            bits += (DEFAULT_CHARSETS.digits.len() as f64).log2();
+        }
+
+        if self.append_symbol {
+            bits += (DEFAULT_CHARSETS.symbols.len() as f64).log2();
+        }
+
+        bits
+    }
+
+    /// Validates the configuration
+    pub fn validate(&self) -> Result<(), PasswordConfigError> {
+        if self.words == 0 {
+            return Err(PasswordConfigError::ZeroWords);
+        }
+
+        let available = self.word_list_len();
+        if available < 2 {
+            return Err(PasswordConfigError::WordListTooSmall { available });
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads a word list from a file, one word per line
+///
+/// Lines in the EFF diceware format (`11111\tabacus`) are accepted, only the
+/// last column is used. Empty lines and duplicates are skipped.
+pub fn load_word_list<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
+    let content = fs::read_to_string(path)?;
+    let mut words: Vec<String> = content
+        .lines()
+        .filter_map(|line| line.split_whitespace().last())
+        .map(str::to_string)
+        .collect();
+
+    words.sort();
+    words.dedup();
+
+    Ok(words)
+}
+
+/// Generates a passphrase based on the given configuration
+pub fn generate_passphrase(config: &PassphraseConfig) -> String {
+    generate_passphrase_with_rng(config, &mut rng())
+}
+
+/// Generates a passphrase based on the given configuration using the provided RNG
+pub fn generate_passphrase_with_rng<R: Rng + CryptoRng + ?Sized>(
+    config: &PassphraseConfig,
+    rng: &mut R,
+) -> String {
+    let mut passphrase = (0..config.words)
+        .map(|_| {
+            let word = match &config.word_list {
+                Some(list) => list[rng.random_range(0..list.len())].as_str(),
+                None => {
+                    let words = default_word_list();
+                    words[rng.random_range(0..words.len())]
+                }
+            };
+            if config.capitalize {
+                capitalize(word)
+            } else {
+                word.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(&config.separator);
+
+    if config.append_digit {
+        let digits: Vec<char> = DEFAULT_CHARSETS.digits.chars().collect();
+        passphrase.push(digits[rng.random_range(0..digits.len())]);
+    }
+
+    if config.append_symbol {
+        let symbols: Vec<char> = DEFAULT_CHARSETS.symbols.chars().collect();
+        passphrase.push(symbols[rng.random_range(0..symbols.len())]);
+    }
+
+    passphrase
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_wordlist() {
+        assert_eq!(default_word_list().len(), 2048);
+        assert_eq!(default_word_list().len(), DEFAULT_WORDLIST.lines().count());
+    }
+
+    #[test]
+    fn test_generate_passphrase() {
+        let config = PassphraseConfig::new(5).unwrap();
+        let passphrase = generate_passphrase(&config);
+
+        assert_eq!(passphrase.split('-').count(), 5);
+        for word in passphrase.split('-') {
+            assert!(default_word_list().contains(&word));
+        }
+    }
+
+    #[test]
+    fn test_generate_passphrase_options() {
+        let config = PassphraseConfig::new(4)
+            .unwrap()
+            .with_separator(" ")
+            .with_capitalize(true)
+            .with_digit(true)
+            .with_symbol(true);
+
+        for _ in 0..100 {
+            let passphrase = generate_passphrase(&config);
+            let chars: Vec<char> = passphrase.chars().collect();
+
+            assert_eq!(passphrase.split(' ').count(), 4);
+            assert!(passphrase
+                .split(' ')
+                .all(|w| w.chars().next().unwrap().is_ascii_uppercase()));
+            assert!(chars[chars.len() - 2].is_ascii_digit());
+            assert!(DEFAULT_CHARSETS.symbols.contains(chars[chars.len() - 1]));
+        }
+    }
+
+    #[test]
+    fn test_custom_word_list() {
+        let config = PassphraseConfig::new(3)
+            .unwrap()
+            .with_word_list(vec!["foo".to_string(), "bar".to_string()]);
+        let passphrase = generate_passphrase(&config);
+
+        assert!(passphrase.split('-').all(|w| w == "foo" || w == "bar"));
+        assert!((config.entropy_bits() - 3.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_entropy_bits() {
+        let config = PassphraseConfig::new(6).unwrap();
+        assert!((config.entropy_bits() - 66.0).abs() < f64::EPSILON);
+
+        let config = config.with_digit(true);
+        assert!(config.entropy_bits() > 69.0);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(PassphraseConfig::new(0).is_err());
+        assert!(PassphraseConfig::new(4).unwrap().validate().is_ok());
+
+        let config = PassphraseConfig::new(4)
+            .unwrap()
+            .with_word_list(vec!["foo".to_string()]);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_load_word_list_eff_format() {
+        let path = std::env::temp_dir().join("pwgen2_test_eff_wordlist.txt");
+        fs::write(&path, "11111\tabacus\n11112\tabdomen\n\n11113\tabacus\n").unwrap();
+
+        let words = load_word_list(&path).unwrap();
+        assert_eq!(words, vec!["abacus".to_string(), "abdomen".to_string()]);
+
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,485 @@
+use crate::pwgen::{
+    config::PasswordConfig, config::PasswordConfigError, AMBIGUOUS_CHARS, DEFAULT_CHARSETS,
+};
+use rand::{rng, CryptoRng, Rng};
+use std::{fmt, str::FromStr};
+
+const VOWELS: &str = "aeiou";
+
+/// A single position of a [`Pattern`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Token {
+    /// `a`: lowercase letter
+    Lowercase,
+    /// `A`: uppercase letter
+    Uppercase,
+    /// `9`: digit
+    Digit,
+    /// `s` or `!`: symbol
+    Symbol,
+    /// `c`: lowercase consonant
+    Consonant,
+    /// `C`: uppercase consonant
+    UpperConsonant,
+    /// `v`: lowercase vowel
+    Vowel,
+    /// `V`: uppercase vowel
+    UpperVowel,
+    /// `*`: any character from all the sets
+    Any,
+    /// `[...]`: custom character class
+    Class(Vec<char>),
+    /// Any other character, or a `\` escaped one
+    Literal(char),
+}
+
+/// Parsed password template, e.g. `Aaaa-9999-!!` or `Cvcc-9{4}-[xyz]`
+///
+/// | placeholder | characters                       |
+/// |-------------|----------------------------------|
+/// | `a`         | lowercase letter                 |
+/// | `A`         | uppercase letter                 |
+/// | `9`         | digit                            |
+/// | `s`, `!`    | symbol                           |
+/// | `c`, `C`    | lowercase / uppercase consonant  |
+/// | `v`, `V`    | lowercase / uppercase vowel      |
+/// | `*`         | any of the above sets            |
+/// | `[...]`     | custom class, ranges like `a-f`  |
+/// | `\x`        | literal `x`                      |
+///
+/// `{n}` after a placeholder repeats it `n` times, any other character is
+/// copied as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    tokens: Vec<Token>,
+}
+
+impl Pattern {
+    /// Parses a pattern template
+    pub fn parse(template: &str) -> Result<Self, PasswordConfigError> {
+        let chars: Vec<char> = template.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let token = match chars[i] {
+                'a' => Token::Lowercase,
+                'A' => Token::Uppercase,
+                '9' => Token::Digit,
+                's' | '!' => Token::Symbol,
+                'c' => Token::Consonant,
+                'C' => Token::UpperConsonant,
+                'v' => Token::Vowel,
+                'V' => Token::UpperVowel,
+                '*' => Token::Any,
+                '\\' => {
+                    i += 1;
+                    let c = chars
+                        .get(i)
+                        .ok_or(PasswordConfigError::DanglingEscape { position: i - 1 })?;
+                    Token::Literal(*c)
+                }
+                '[' => {
+                    let start = i;
+                    let mut end = start + 1;
+                    while end < chars.len() && chars[end] != ']' {
+                        if chars[end] == '\\' {
+                            end += 1;
+                        }
+                        end += 1;
+                    }
+                    if end >= chars.len() {
+                        return Err(PasswordConfigError::UnclosedCharacterClass {
+                            position: start,
+                        });
+                    }
+                    i = end;
+                    Token::Class(parse_class(&chars[start + 1..end], start)?)
+                }
+                '{' => return Err(PasswordConfigError::InvalidRepetition { position: i }),
+                c => Token::Literal(c),
+            };
+            i += 1;
+
+            let mut count = 1;
+            if chars.get(i) == Some(&'{') {
+                let start = i;
+                let end = chars[start..]
+                    .iter()
+                    .position(|c| *c == '}')
+                    .map(|offset| start + offset)
+                    .ok_or(PasswordConfigError::InvalidRepetition { position: start })?;
+                count = chars[start + 1..end]
+                    .iter()
+                    .collect::<String>()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|n| *n > 0)
+                    .ok_or(PasswordConfigError::InvalidRepetition { position: start })?;
+                i = end + 1;
+            }
+
+            if tokens
+                .len()
+                .checked_add(count)
+                .is_none_or(|len| len > usize::from(u8::MAX))
+            {
+                return Err(PasswordConfigError::PatternTooLong);
+            }
+            tokens.extend(std::iter::repeat_n(token, count));
+        }
+
+        if tokens.is_empty() {
+            return Err(PasswordConfigError::EmptyPattern);
+        }
+
+        Ok(Self { tokens })
+    }
+
+    /// Number of characters produced by the pattern
+    pub fn len(&self) -> u8 {
+        // parse() caps the number of tokens to u8::MAX
+        u8::try_from(self.tokens.len()).unwrap_or(u8::MAX)
+    }
+
+    /// Returns `true` if the pattern has no tokens
+    pub const fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Tokens of the pattern, one per generated character
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = PasswordConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            match token {
+                Token::Lowercase => write!(f, "a")?,
+                Token::Uppercase => write!(f, "A")?,
+                Token::Digit => write!(f, "9")?,
+                Token::Symbol => write!(f, "!")?,
+                Token::Consonant => write!(f, "c")?,
+                Token::UpperConsonant => write!(f, "C")?,
+                Token::Vowel => write!(f, "v")?,
+                Token::UpperVowel => write!(f, "V")?,
+                Token::Any => write!(f, "*")?,
+                Token::Class(chars) => {
+                    write!(f, "[")?;
+                    for c in chars {
+                        if matches!(c, ']' | '\\' | '-') {
+                            write!(f, "\\")?;
+                        }
+                        write!(f, "{}", c)?;
+                    }
+                    write!(f, "]")?;
+                }
+                Token::Literal(c) => {
+                    if "aA9s!cCvV*\\[{".contains(*c) {
+                        write!(f, "\\")?;
+                    }
+                    write!(f, "{}", c)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_class(body: &[char], position: usize) -> Result<Vec<char>, PasswordConfigError> {
+    let mut chars = Vec::new();
+    let mut i = 0;
+
+    while i < body.len() {
+        if body[i] == '\\' && i + 1 < body.len() {
+            i += 1;
+        }
+        let c = body[i];
+
+        if body.get(i + 1) == Some(&'-') && i + 2 < body.len() {
+            let end = body[i + 2];
+            if end < c {
+                return Err(PasswordConfigError::InvalidCharacterRange {
+                    position,
+                    start: c,
+                    end,
+                });
+            }
+            chars.extend(c..=end);
+            i += 3;
+        } else {
+            chars.push(c);
+            i += 1;
+        }
+    }
+
+    chars.sort_unstable();
+    chars.dedup();
+
+    if chars.is_empty() {
+        return Err(PasswordConfigError::EmptyCharacterClass { position });
+    }
+
+    Ok(chars)
+}
+
+/// Characters available for every placeholder of a pattern
+#[derive(Debug, Clone)]
+pub(crate) struct Pools {
+    lowercase: Vec<char>,
+    uppercase: Vec<char>,
+    digits: Vec<char>,
+    symbols: Vec<char>,
+    vowels: Vec<char>,
+    consonants: Vec<char>,
+    upper_vowels: Vec<char>,
+    upper_consonants: Vec<char>,
+    any: Vec<char>,
+}
+
+impl Pools {
+    pub(crate) fn new(config: &PasswordConfig) -> Self {
+        let filter = |set: &str| -> Vec<char> {
+            set.chars()
+                .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
+                .collect()
+        };
+        let split = |set: &[char], vowels: bool| -> Vec<char> {
+            set.iter()
+                .copied()
+                .filter(|c| VOWELS.contains(c.to_ascii_lowercase()) == vowels)
+                .collect()
+        };
+
+        let lowercase = filter(DEFAULT_CHARSETS.lowercase);
+        let uppercase = filter(DEFAULT_CHARSETS.uppercase);
+        let digits = filter(DEFAULT_CHARSETS.digits);
+        let symbols: Vec<char> = config
+            .charset
+            .as_deref()
+            .filter(|charset| !charset.is_empty())
+            .unwrap_or(DEFAULT_CHARSETS.symbols)
+            .chars()
+            .collect();
+
+        Self {
+            vowels: split(&lowercase, true),
+            consonants: split(&lowercase, false),
+            upper_vowels: split(&uppercase, true),
+            upper_consonants: split(&uppercase, false),
+            any: [&lowercase, &uppercase, &digits, &symbols]
+                .into_iter()
+                .flatten()
+                .copied()
+                .collect(),
+            lowercase,
+            uppercase,
+            digits,
+            symbols,
+        }
+    }
+
+    /// Characters for the token, `None` for literals
+    fn get<'a>(&'a self, token: &'a Token) -> Option<&'a [char]> {
+        match token {
+            Token::Literal(_) => None,
+            Token::Lowercase => Some(&self.lowercase),
+            Token::Uppercase => Some(&self.uppercase),
+            Token::Digit => Some(&self.digits),
+            Token::Symbol => Some(&self.symbols),
+            Token::Consonant => Some(&self.consonants),
+            Token::UpperConsonant => Some(&self.upper_consonants),
+            Token::Vowel => Some(&self.vowels),
+            Token::UpperVowel => Some(&self.upper_vowels),
+            Token::Any => Some(&self.any),
+            Token::Class(chars) => Some(chars),
+        }
+    }
+}
+
+impl Pattern {
+    /// Bits of entropy of a password generated from this pattern with the given configuration
+    pub fn entropy_bits(&self, config: &PasswordConfig) -> f64 {
+        let pools = Pools::new(config);
+        self.tokens
+            .iter()
+            .filter_map(|token| pools.get(token))
+            .map(|set| (set.len() as f64).log2())
+            .sum()
+    }
+
+    /// Generates a password matching the pattern from pools built for it
+    pub(crate) fn generate_with_pools<R: Rng + CryptoRng + ?Sized>(
+        &self,
+        pools: &Pools,
+        rng: &mut R,
+    ) -> String {
+        self.tokens
+            .iter()
+            .map(|token| match (token, pools.get(token)) {
+                (Token::Literal(c), _) => *c,
+                (_, Some(set)) => set[rng.random_range(0..set.len())],
+                (_, None) => unreachable!("only literals have no pool"),
+            })
+            .collect()
+    }
+}
+
+/// Generates a password matching the pattern
+///
+/// Symbols are taken from the custom charset of the configuration when not empty,
+/// and ambiguous characters are skipped when `avoid_ambiguous` is enabled,
+/// except in literals and custom classes.
+pub fn generate_from_pattern(pattern: &Pattern, config: &PasswordConfig) -> String {
+    generate_from_pattern_with_rng(pattern, config, &mut rng())
+}
+
+/// Generates a password matching the pattern using the provided RNG
+pub fn generate_from_pattern_with_rng<R: Rng + CryptoRng + ?Sized>(
+    pattern: &Pattern,
+    config: &PasswordConfig,
+    rng: &mut R,
+) -> String {
+    pattern.generate_with_pools(&Pools::new(config), rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pattern = Pattern::parse("Aaaa-9999-!!").unwrap();
+
+        assert_eq!(pattern.len(), 12);
+        assert_eq!(pattern.tokens()[0], Token::Uppercase);
+        assert_eq!(pattern.tokens()[4], Token::Literal('-'));
+        assert_eq!(pattern.tokens()[11], Token::Symbol);
+    }
+
+    #[test]
+    fn test_parse_repetition_and_class() {
+        let pattern: Pattern = "C9{3}[a-c\\-]\\a".parse().unwrap();
+        let escaped = Pattern::parse("[\\]x]").unwrap();
+
+        assert_eq!(escaped.tokens(), &[Token::Class(vec![']', 'x'])]);
+
+        assert_eq!(pattern.len(), 6);
+        assert_eq!(pattern.tokens()[3], Token::Digit);
+        assert_eq!(pattern.tokens()[4], Token::Class(vec!['-', 'a', 'b', 'c']));
+        assert_eq!(pattern.tokens()[5], Token::Literal('a'));
+        assert_eq!(pattern.to_string(), "C999[\\-abc]\\a");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(
+            Pattern::parse(""),
+            Err(PasswordConfigError::EmptyPattern)
+        ));
+        assert!(matches!(
+            Pattern::parse("aa[bc"),
+            Err(PasswordConfigError::UnclosedCharacterClass { position: 2 })
+        ));
+        assert!(matches!(
+            Pattern::parse("a[]"),
+            Err(PasswordConfigError::EmptyCharacterClass { position: 1 })
+        ));
+        assert!(matches!(
+            Pattern::parse("[z-a]"),
+            Err(PasswordConfigError::InvalidCharacterRange { .. })
+        ));
+        assert!(matches!(
+            Pattern::parse("a{x}"),
+            Err(PasswordConfigError::InvalidRepetition { position: 1 })
+        ));
+        assert!(matches!(
+            Pattern::parse("a{0}"),
+            Err(PasswordConfigError::InvalidRepetition { position: 1 })
+        ));
+        assert!(matches!(
+            Pattern::parse("{2}"),
+            Err(PasswordConfigError::InvalidRepetition { position: 0 })
+        ));
+        assert!(matches!(
+            Pattern::parse("a\\"),
+            Err(PasswordConfigError::DanglingEscape { position: 1 })
+        ));
+        assert!(matches!(
+            Pattern::parse("a{300}"),
+            Err(PasswordConfigError::PatternTooLong)
+        ));
+        assert!(matches!(
+            Pattern::parse("aa{18446744073709551615}"),
+            Err(PasswordConfigError::PatternTooLong)
+        ));
+        assert!(matches!(
+            Pattern::parse("a{18446744073709551616}"),
+            Err(PasswordConfigError::InvalidRepetition { position: 1 })
+        ));
+    }
+
+    #[test]
+    fn test_generate_from_pattern() {
+        let config = PasswordConfig::default();
+        let pattern = Pattern::parse("Aaaa-9999-!!").unwrap();
+
+        for _ in 0..1000 {
+            let password = generate_from_pattern(&pattern, &config);
+            let chars: Vec<char> = password.chars().collect();
+
+            assert_eq!(chars.len(), 12);
+            assert!(chars[0].is_ascii_uppercase());
+            assert!(chars[1..4].iter().all(|c| c.is_ascii_lowercase()));
+            assert_eq!(chars[4], '-');
+            assert!(chars[5..9].iter().all(|c| c.is_ascii_digit()));
+            assert_eq!(chars[9], '-');
+            assert!(chars[10..]
+                .iter()
+                .all(|c| DEFAULT_CHARSETS.symbols.contains(*c)));
+            assert!(!password.chars().any(|c| AMBIGUOUS_CHARS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_from_pattern_vowels_and_consonants() {
+        let config = PasswordConfig::default();
+        let pattern = Pattern::parse("CvcV").unwrap();
+
+        for _ in 0..1000 {
+            let chars: Vec<char> = generate_from_pattern(&pattern, &config).chars().collect();
+
+            assert!(!VOWELS.contains(chars[0].to_ascii_lowercase()));
+            assert!(VOWELS.contains(chars[1]));
+            assert!(!VOWELS.contains(chars[2]));
+            assert!(VOWELS.contains(chars[3].to_ascii_lowercase()));
+        }
+    }
+
+    #[test]
+    fn test_generate_from_pattern_custom_charset() {
+        let config = PasswordConfig::custom(18, "~".to_string()).unwrap();
+        let pattern = Pattern::parse("a!").unwrap();
+
+        assert!(generate_from_pattern(&pattern, &config).ends_with('~'));
+    }
+
+    #[test]
+    fn test_pattern_entropy_bits() {
+        let config = PasswordConfig::default().with_avoid_ambiguous(false);
+        let pattern = Pattern::parse("9{4}-[ab]").unwrap();
+
+        let expected = 10f64.powi(4).log2() + 1.0;
+        assert!((pattern.entropy_bits(&config) - expected).abs() < 1e-9);
+    }
+}
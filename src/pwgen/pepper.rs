@@ -0,0 +1,167 @@
+use anyhow::{anyhow, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::{env, fmt, fs, path::Path, str::FromStr};
+
+/// Text encoding of the HMAC that replaces the password
+///
+/// The binary HMAC can't be hashed as is, bcrypt stops at the first NUL byte.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PepperEncoding {
+    /// Standard base64 with padding, 44 characters, the OWASP pre-hashing recommendation
+    #[default]
+    Base64,
+    /// Lowercase hex, 64 characters
+    Hex,
+}
+
+impl FromStr for PepperEncoding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "base64" => Ok(Self::Base64),
+            "hex" => Ok(Self::Hex),
+            _ => Err(anyhow!("Unknown pepper encoding '{}'", s)),
+        }
+    }
+}
+
+/// A server-side secret, passwords are replaced by their HMAC-SHA256 with it before hashing
+///
+/// A stolen database is then useless without the key, which stays out of the user table.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Pepper {
+    key: Vec<u8>,
+    encoding: PepperEncoding,
+}
+
+impl Pepper {
+    pub fn new(key: impl Into<Vec<u8>>) -> Result<Self> {
+        let key = key.into();
+        if key.is_empty() {
+            return Err(anyhow!("The pepper key is empty"));
+        }
+
+        Ok(Self {
+            key,
+            encoding: PepperEncoding::default(),
+        })
+    }
+
+    /// Reads the key from a file, a trailing newline is not part of it
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let mut key = fs::read(path)
+            .with_context(|| format!("Failed to read the pepper key from {}", path.display()))?;
+        if key.ends_with(b"\n") {
+            key.pop();
+            if key.ends_with(b"\r") {
+                key.pop();
+            }
+        }
+
+        Self::new(key).with_context(|| format!("Invalid pepper key in {}", path.display()))
+    }
+
+    /// Reads the key from an environment variable
+    pub fn from_env(var: &str) -> Result<Self> {
+        let key = env::var_os(var)
+            .ok_or_else(|| anyhow!("The pepper environment variable {} is not set", var))?;
+
+        Self::new(key.into_encoded_bytes())
+    }
+
+    pub const fn with_encoding(mut self, encoding: PepperEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Returns the encoded HMAC-SHA256 of a password, the value to hash instead of it
+    pub fn apply(&self, password: &str) -> String {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts any key length");
+        mac.update(password.as_bytes());
+        let digest = mac.finalize().into_bytes();
+
+        match self.encoding {
+            PepperEncoding::Base64 => STANDARD.encode(digest),
+            PepperEncoding::Hex => digest.iter().map(|b| format!("{:02x}", b)).collect(),
+        }
+    }
+}
+
+/// The key is secret, only its length is shown
+impl fmt::Debug for Pepper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pepper")
+            .field("key", &format_args!("<{} bytes>", self.key.len()))
+            .field("encoding", &self.encoding)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        // Computed with Python's `hmac`
+        let pepper = Pepper::new("pepper").unwrap();
+        assert_eq!(
+            pepper.apply("password"),
+            "mVOblvchLJebWnl1XWut4MPNxkILllQ8GpSfmrhZvqE="
+        );
+        assert_eq!(
+            pepper.apply("pässword"),
+            "vjBKJW89SxEChCyGumLNeyTHdE+vu397mbhfVIlX6+s="
+        );
+        assert_eq!(
+            pepper.with_encoding(PepperEncoding::Hex).apply("password"),
+            "99539b96f7212c979b5a79755d6bade0c3cdc6420b96543c1a949f9ab859bea1"
+        );
+    }
+
+    #[test]
+    fn test_new_empty() {
+        assert!(Pepper::new("").is_err());
+    }
+
+    #[test]
+    fn test_from_file() -> Result<()> {
+        let path = env::temp_dir().join(format!("pwgen2-pepper-{}", std::process::id()));
+
+        fs::write(&path, "pepper\r\n")?;
+        assert_eq!(Pepper::from_file(&path)?, Pepper::new("pepper")?);
+        fs::write(&path, "pepper\n\n")?;
+        assert_eq!(Pepper::from_file(&path)?, Pepper::new("pepper\n")?);
+        fs::write(&path, "\n")?;
+        assert!(Pepper::from_file(&path).is_err());
+        fs::remove_file(&path)?;
+        assert!(Pepper::from_file(&path).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_env() {
+        assert!(Pepper::from_env("PWGEN2_TEST_PEPPER_UNSET").is_err());
+    }
+
+    #[test]
+    fn test_debug_hides_key() {
+        let debug = format!("{:?}", Pepper::new("secret").unwrap());
+        assert!(!debug.contains("secret"));
+        assert!(debug.contains("6 bytes"));
+    }
+
+    #[test]
+    fn test_encoding_from_str() {
+        assert_eq!(
+            "hex".parse::<PepperEncoding>().unwrap(),
+            PepperEncoding::Hex
+        );
+        assert!("base32".parse::<PepperEncoding>().is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use rand::random;
+use sha2::{Digest, Sha256};
+
+const PREFIX: &str = "SCRAM-SHA-256$";
+
+/// Default iterations, the `scram_iterations` default of PostgreSQL
+pub const DEFAULT_ITERATIONS: u32 = 4096;
+
+/// Salt length of the verifiers written by PostgreSQL
+pub const SALT_LEN: usize = 16;
+
+/// Returns a random salt of `SALT_LEN` bytes
+pub fn gensalt() -> [u8; SALT_LEN] {
+    random()
+}
+
+/// Returns the `SCRAM-SHA-256$<iterations>:<salt>$<StoredKey>:<ServerKey>` verifier
+///
+/// PostgreSQL normalizes non-ASCII passwords with SASLprep, they are used as is here.
+pub fn hash(password: &[u8], salt: &[u8], iterations: u32) -> String {
+    let mut salted_password = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(password, salt, iterations, &mut salted_password);
+
+    let client_key = hmac_sha256(&salted_password, b"Client Key");
+    let stored_key = Sha256::digest(client_key);
+    let server_key = hmac_sha256(&salted_password, b"Server Key");
+
+    format!(
+        "{}{}:{}${}:{}",
+        PREFIX,
+        iterations,
+        STANDARD.encode(salt),
+        STANDARD.encode(stored_key),
+        STANDARD.encode(server_key)
+    )
+}
+
+/// Returns the iterations and the salt of a verifier
+pub fn parse(hashed: &str) -> Result<(u32, Vec<u8>)> {
+    let invalid = || anyhow!("Invalid SCRAM-SHA-256 verifier");
+
+    let rest = hashed.strip_prefix(PREFIX).ok_or_else(invalid)?;
+    let (settings, keys) = rest.split_once('$').ok_or_else(invalid)?;
+    let (iterations, salt) = settings.split_once(':').ok_or_else(invalid)?;
+    if keys.split(':').count() != 2 {
+        return Err(invalid());
+    }
+
+    let iterations = iterations.parse().map_err(|_| invalid())?;
+    let salt = STANDARD.decode(salt).map_err(|_| invalid())?;
+    if iterations == 0 || salt.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok((iterations, salt))
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Computed with Python's `hashlib.pbkdf2_hmac` and `hmac`
+    const VERIFIER: &str = "SCRAM-SHA-256$4096:c2FsdHNhbHRzYWx0c2FsdA==$\
+        CozjiHjNmiMjBgH9gZ7qn0QWud6nrVP6E72IBh477bQ=:\
+        VKers2x8MllK1Rh7LZLqtj6KOTzoFWJpIaokMX3blS0=";
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(b"password", b"saltsaltsaltsalt", 4096), VERIFIER);
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(VERIFIER).unwrap(),
+            (4096, b"saltsaltsaltsalt".to_vec())
+        );
+        assert!(parse("SCRAM-SHA-256$4096:c2FsdA==").is_err());
+        assert!(parse("SCRAM-SHA-256$x:c2FsdA==$a:b").is_err());
+        assert!(parse("SCRAM-SHA-256$4096:!!$a:b").is_err());
+        assert!(parse("md5d2d9c2c0e6c8d2d1e0b7d3c0f1a2b3c4").is_err());
+    }
+
+    #[test]
+    fn test_gensalt() {
+        assert_ne!(gensalt(), gensalt());
+    }
+}
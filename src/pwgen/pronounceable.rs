@@ -0,0 +1,357 @@
+use crate::pwgen::{
+    config::{PasswordConfig, PasswordConfigError},
+    AMBIGUOUS_CHARS, DEFAULT_CHARSETS,
+};
+use rand::{rng, CryptoRng, Rng};
+
+const CONSONANT: u8 = 0x01;
+const VOWEL: u8 = 0x02;
+const DIPHTHONG: u8 = 0x04;
+const NOT_FIRST: u8 = 0x08;
+
+/// Phonemes used to build pronounceable passwords, same table as the classic pwgen
+const ELEMENTS: &[(&str, u8)] = &[
+    ("a", VOWEL),
+    ("ae", VOWEL | DIPHTHONG),
+    ("ah", VOWEL | DIPHTHONG),
+    ("ai", VOWEL | DIPHTHONG),
+    ("b", CONSONANT),
+    ("c", CONSONANT),
+    ("ch", CONSONANT | DIPHTHONG),
+    ("d", CONSONANT),
+    ("e", VOWEL),
+    ("ee", VOWEL | DIPHTHONG),
+    ("ei", VOWEL | DIPHTHONG),
+    ("f", CONSONANT),
+    ("g", CONSONANT),
+    ("gh", CONSONANT | DIPHTHONG | NOT_FIRST),
+    ("h", CONSONANT),
+    ("i", VOWEL),
+    ("ie", VOWEL | DIPHTHONG),
+    ("j", CONSONANT),
+    ("k", CONSONANT),
+    ("l", CONSONANT),
+    ("m", CONSONANT),
+    ("n", CONSONANT),
+    ("ng", CONSONANT | DIPHTHONG | NOT_FIRST),
+    ("o", VOWEL),
+    ("oh", VOWEL | DIPHTHONG),
+    ("oo", VOWEL | DIPHTHONG),
+    ("p", CONSONANT),
+    ("ph", CONSONANT | DIPHTHONG),
+    ("qu", CONSONANT | DIPHTHONG),
+    ("r", CONSONANT),
+    ("s", CONSONANT),
+    ("sh", CONSONANT | DIPHTHONG),
+    ("t", CONSONANT),
+    ("th", CONSONANT | DIPHTHONG),
+    ("u", VOWEL),
+    ("v", CONSONANT),
+    ("w", CONSONANT),
+    ("x", CONSONANT),
+    ("y", CONSONANT),
+    ("z", CONSONANT),
+];
+
+/// Pronounceable password generator built once from a validated configuration
+///
+/// Letters come from the phonemes, in lowercase, uppercase or both depending on
+/// `include_lowercase` and `include_uppercase`. `include_digits` and
+/// `include_symbols` guarantee at least one character of the set.
+#[derive(Debug, Clone)]
+pub struct PronounceableGenerator {
+    config: PasswordConfig,
+    digits: Vec<char>,
+    symbols: Vec<char>,
+}
+
+impl PronounceableGenerator {
+    /// Validates the configuration, letters must be enabled and the password
+    /// long enough for the phonemes around the required digit and symbol
+    pub fn new(config: PasswordConfig) -> Result<Self, PasswordConfigError> {
+        config.validate()?;
+        if !config.include_lowercase && !config.include_uppercase {
+            return Err(PasswordConfigError::NoLettersEnabled);
+        }
+
+        let digits: Vec<char> = if config.include_digits {
+            DEFAULT_CHARSETS
+                .digits
+                .chars()
+                .filter(|c| !config.avoid_ambiguous || !AMBIGUOUS_CHARS.contains(*c))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let symbols: Vec<char> = if config.include_symbols {
+            config
+                .charset
+                .as_deref()
+                .unwrap_or(DEFAULT_CHARSETS.symbols)
+                .chars()
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // A digit or a symbol only follows the second phoneme of a syllable
+        let min_length = 1 + 2 * (u8::from(!digits.is_empty()) + u8::from(!symbols.is_empty()));
+        if config.length < min_length {
+            return Err(PasswordConfigError::PronounceableTooShort {
+                length: config.length,
+                min_length,
+            });
+        }
+
+        Ok(Self {
+            config,
+            digits,
+            symbols,
+        })
+    }
+
+    /// The configuration the generator was built from
+    pub const fn config(&self) -> &PasswordConfig {
+        &self.config
+    }
+
+    /// Generates a password with the thread RNG
+    pub fn generate(&self) -> String {
+        self.generate_with_rng(&mut rng())
+    }
+
+    /// Generates a password using the provided RNG
+    pub fn generate_with_rng<R: Rng + CryptoRng + ?Sized>(&self, rng: &mut R) -> String {
+        let config = &self.config;
+        let (digits, symbols) = (&self.digits, &self.symbols);
+        let length = usize::from(config.length);
+        let is_ambiguous = |c: char| config.avoid_ambiguous && AMBIGUOUS_CHARS.contains(c);
+        // Without lowercase every letter is uppercase, with both some are capitalized
+        let mixed_case = config.include_lowercase && config.include_uppercase;
+
+        loop {
+            let mut password = String::with_capacity(length);
+            let mut count = 0;
+            let mut needs_uppercase = mixed_case;
+            let mut needs_digit = !digits.is_empty();
+            let mut needs_symbol = !symbols.is_empty();
+
+            let mut prev = 0;
+            let mut first = true;
+            let mut should_be = random_kind(rng);
+
+            while count < length {
+                let (element, flags) = ELEMENTS[rng.random_range(0..ELEMENTS.len())];
+                let element = if config.include_lowercase {
+                    element.to_string()
+                } else {
+                    element.to_ascii_uppercase()
+                };
+
+                if flags & should_be == 0
+                    || (first && flags & NOT_FIRST != 0)
+                    || (prev & VOWEL != 0 && flags & VOWEL != 0 && flags & DIPHTHONG != 0)
+                    || element.len() > length - count
+                    || element.chars().any(is_ambiguous)
+                {
+                    continue;
+                }
+
+                let start = password.len();
+                password.push_str(&element);
+
+                if mixed_case && (first || flags & CONSONANT != 0) && rng.random_range(0..10) < 2 {
+                    let upper = element.chars().next().unwrap_or('a').to_ascii_uppercase();
+                    if !is_ambiguous(upper) {
+                        password.replace_range(start..=start, &upper.to_string());
+                        needs_uppercase = false;
+                    }
+                }
+
+                count += element.len();
+                if count >= length {
+                    break;
+                }
+
+                if !digits.is_empty() && !first && rng.random_range(0..10) < 3 {
+                    password.push(digits[rng.random_range(0..digits.len())]);
+                    count += 1;
+                    needs_digit = false;
+                    first = true;
+                    prev = 0;
+                    should_be = random_kind(rng);
+                    continue;
+                }
+
+                if !symbols.is_empty() && !first && rng.random_range(0..10) < 2 {
+                    password.push(symbols[rng.random_range(0..symbols.len())]);
+                    count += 1;
+                    needs_symbol = false;
+                }
+
+                should_be = if should_be == CONSONANT {
+                    VOWEL
+                } else if prev & VOWEL != 0 || flags & DIPHTHONG != 0 || rng.random_range(0..10) > 3
+                {
+                    CONSONANT
+                } else {
+                    VOWEL
+                };
+
+                prev = flags;
+                first = false;
+            }
+
+            if !needs_uppercase && !needs_digit && !needs_symbol {
+                return password;
+            }
+        }
+    }
+}
+
+impl Iterator for PronounceableGenerator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        Some(self.generate())
+    }
+}
+
+/// Generates a pronounceable password based on the given configuration
+pub fn generate_pronounceable(config: &PasswordConfig) -> Result<String, PasswordConfigError> {
+    generate_pronounceable_with_rng(config, &mut rng())
+}
+
+/// Generates a pronounceable password based on the given configuration using the provided RNG
+pub fn generate_pronounceable_with_rng<R: Rng + CryptoRng + ?Sized>(
+    config: &PasswordConfig,
+    rng: &mut R,
+) -> Result<String, PasswordConfigError> {
+    Ok(PronounceableGenerator::new(config.clone())?.generate_with_rng(rng))
+}
+
+fn random_kind<R: Rng + ?Sized>(rng: &mut R) -> u8 {
+    if rng.random_bool(0.5) {
+        VOWEL
+    } else {
+        CONSONANT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generate_pronounceable() {
+        let config = PasswordConfig::new(12).unwrap();
+
+        for _ in 0..1000 {
+            let password = generate_pronounceable(&config).unwrap();
+
+            assert_eq!(password.len(), 12);
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(password.chars().any(|c| c.is_ascii_digit()));
+            assert!(password
+                .chars()
+                .any(|c| DEFAULT_CHARSETS.symbols.contains(c)));
+            assert!(
+                !password.chars().any(|c| AMBIGUOUS_CHARS.contains(c)),
+                "Password contained ambiguous characters: {}",
+                password
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_pronounceable_lowercase_only() {
+        let config = PasswordConfig::new(10)
+            .unwrap()
+            .with_uppercase(false)
+            .with_digits(false)
+            .with_symbols(false);
+
+        for _ in 0..1000 {
+            let password = generate_pronounceable(&config).unwrap();
+
+            assert_eq!(password.len(), 10);
+            assert!(password.chars().all(|c| c.is_ascii_lowercase()));
+        }
+    }
+
+    #[test]
+    fn test_generate_pronounceable_custom_charset() {
+        let config = PasswordConfig::custom(12, "~€".to_string()).unwrap();
+
+        for _ in 0..100 {
+            let password = generate_pronounceable(&config).unwrap();
+            assert_eq!(password.chars().count(), 12);
+            assert!(password.contains('~') || password.contains('€'));
+        }
+    }
+
+    #[test]
+    fn test_generate_pronounceable_uppercase_only() {
+        let config = PasswordConfig::new(12).unwrap().with_lowercase(false);
+        let generator = PronounceableGenerator::new(config).unwrap();
+
+        for password in generator.take(1000) {
+            assert_eq!(password.len(), 12);
+            assert!(!password.chars().any(|c| c.is_ascii_lowercase()));
+            assert!(password.chars().any(|c| c.is_ascii_uppercase()));
+            assert!(!password.chars().any(|c| AMBIGUOUS_CHARS.contains(c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_pronounceable_short() {
+        let letters = PasswordConfig::new(1)
+            .unwrap()
+            .with_uppercase(false)
+            .with_digits(false)
+            .with_symbols(false);
+        let password = generate_pronounceable(&letters).unwrap();
+        assert!(password.len() == 1 && password.chars().all(|c| c.is_ascii_lowercase()));
+
+        let digits = PasswordConfig::new(3).unwrap().with_symbols(false);
+        for password in PronounceableGenerator::new(digits).unwrap().take(100) {
+            assert_eq!(password.len(), 3);
+            assert!(password.ends_with(|c: char| c.is_ascii_digit()));
+        }
+
+        let password = generate_pronounceable(&PasswordConfig::new(5).unwrap()).unwrap();
+        assert_eq!(password.len(), 5);
+
+        assert!(matches!(
+            generate_pronounceable(&PasswordConfig::new(4).unwrap()),
+            Err(PasswordConfigError::PronounceableTooShort {
+                length: 4,
+                min_length: 5
+            })
+        ));
+        let config = PasswordConfig::new(2)
+            .unwrap()
+            .with_uppercase(false)
+            .with_symbols(false);
+        assert!(matches!(
+            generate_pronounceable(&config),
+            Err(PasswordConfigError::PronounceableTooShort {
+                length: 2,
+                min_length: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_generate_pronounceable_without_letters() {
+        let config = PasswordConfig::new(12)
+            .unwrap()
+            .with_lowercase(false)
+            .with_uppercase(false);
+
+        assert!(matches!(
+            PronounceableGenerator::new(config),
+            Err(PasswordConfigError::NoLettersEnabled)
+        ));
+    }
+}
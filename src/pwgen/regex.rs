@@ -0,0 +1,570 @@
+use crate::pwgen::config::PasswordConfigError;
+use rand::{rng, CryptoRng, Rng};
+use regex_syntax::hir::{Class, Hir, HirKind, Look};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+/// Generated characters are limited to printable ASCII (space to `~`)
+const FIRST_CHAR: u8 = b' ';
+const ALPHABET_LEN: usize = 95;
+
+/// Limits to keep the automaton small enough to be built on the fly
+const MAX_NFA_STATES: usize = 100_000;
+const MAX_DFA_STATES: usize = 4_096;
+
+/// Set of printable ASCII characters, bit `n` is `FIRST_CHAR + n`
+type CharSet = u128;
+
+/// Restricted regular expression used to generate passwords
+///
+/// Supports literals, character classes (`[a-z]`, `\d`, `\w`, `.`), groups,
+/// alternation and quantifiers; only printable ASCII characters are generated.
+/// Strings are sampled uniformly among all the matching strings of at most
+/// `max_length` characters, unbounded quantifiers (`*`, `+`, `{n,}`) are
+/// capped by `max_length`.
+#[derive(Debug, Clone)]
+pub struct RegexPattern {
+    source: String,
+    max_length: u8,
+    dfa: Arc<Dfa>,
+}
+
+#[derive(Debug)]
+struct Dfa {
+    /// Distinct next states of every state and the number of characters leading to them
+    transitions: Vec<Vec<(usize, CharSet)>>,
+    /// `log_counts[state][n]` is ln of the number of accepted strings of length `n` from `state`
+    log_counts: Vec<Vec<f64>>,
+}
+
+impl RegexPattern {
+    /// Parses a regular expression, generated strings are at most `max_length` characters long
+    pub fn parse(pattern: &str, max_length: u8) -> Result<Self, PasswordConfigError> {
+        if max_length == 0 {
+            return Err(PasswordConfigError::ZeroLength);
+        }
+
+        let hir = regex_syntax::Parser::new()
+            .parse(pattern)
+            .map_err(|e| PasswordConfigError::InvalidRegex(e.to_string()))?;
+
+        let mut nfa = Nfa::default();
+        let (start, end) = nfa.compile(&hir, u32::from(max_length), true, true)?;
+        let dfa = Dfa::build(&nfa, start, end, usize::from(max_length))?;
+
+        if dfa.log_counts[0][1..].iter().all(|c| c.is_infinite()) {
+            return Err(PasswordConfigError::RegexNoMatch { max_length });
+        }
+
+        Ok(Self {
+            source: pattern.to_string(),
+            max_length,
+            dfa: Arc::new(dfa),
+        })
+    }
+
+    /// The regular expression as given to [`RegexPattern::parse`]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Maximum length of the generated strings
+    pub const fn max_length(&self) -> u8 {
+        self.max_length
+    }
+
+    /// Bits of entropy of a string generated from this expression
+    pub fn entropy_bits(&self) -> f64 {
+        log_sum_exp(&self.dfa.log_counts[0][1..]) / std::f64::consts::LN_2
+    }
+}
+
+/// Generates a random string matching the regular expression
+///
+/// Every matching non-empty string of at most `max_length` characters has the
+/// same probability of being picked.
+pub fn generate_from_regex(pattern: &RegexPattern) -> String {
+    generate_from_regex_with_rng(pattern, &mut rng())
+}
+
+/// Generates a random string matching the regular expression using the provided RNG
+pub fn generate_from_regex_with_rng<R: Rng + CryptoRng + ?Sized>(
+    pattern: &RegexPattern,
+    rng: &mut R,
+) -> String {
+    let dfa = &pattern.dfa;
+
+    // Pick the length first, weighted by the number of strings of each length
+    let lengths = &dfa.log_counts[0];
+    let mut remaining = pick_weighted(rng, lengths.iter().copied().enumerate().skip(1));
+
+    let mut password = String::with_capacity(remaining);
+    let mut state = 0;
+
+    while remaining > 0 {
+        let next = pick_weighted(
+            rng,
+            dfa.transitions[state]
+                .iter()
+                .enumerate()
+                .map(|(i, (to, set))| {
+                    (
+                        i,
+                        f64::from(set.count_ones()).ln() + dfa.log_counts[*to][remaining - 1],
+                    )
+                }),
+        );
+
+        let (to, set) = dfa.transitions[state][next];
+        let nth = rng.random_range(0..set.count_ones());
+        password.push(nth_char(set, nth));
+
+        state = to;
+        remaining -= 1;
+    }
+
+    password
+}
+
+/// Picks an item with probability proportional to `exp(weight)`
+fn pick_weighted<R: Rng + ?Sized, I: Iterator<Item = (usize, f64)> + Clone>(
+    rng: &mut R,
+    items: I,
+) -> usize {
+    let max = items
+        .clone()
+        .map(|(_, w)| w)
+        .fold(f64::NEG_INFINITY, f64::max);
+    let total: f64 = items.clone().map(|(_, w)| (w - max).exp()).sum();
+
+    let mut target = rng.random_range(0.0..total);
+    let mut last = 0;
+    for (item, weight) in items {
+        if weight.is_infinite() {
+            continue;
+        }
+        let weight = (weight - max).exp();
+        if target < weight {
+            return item;
+        }
+        target -= weight;
+        last = item;
+    }
+
+    // Floating point rounding, fall back to the last possible item
+    last
+}
+
+fn nth_char(set: CharSet, nth: u32) -> char {
+    let mut seen = 0;
+    for bit in 0..ALPHABET_LEN {
+        if set & (1 << bit) != 0 {
+            if seen == nth {
+                return char::from(FIRST_CHAR + bit as u8);
+            }
+            seen += 1;
+        }
+    }
+    unreachable!("character set has less than {} characters", nth + 1)
+}
+
+fn log_sum_exp(values: &[f64]) -> f64 {
+    let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    if max.is_infinite() {
+        return max;
+    }
+    max + values.iter().map(|v| (v - max).exp()).sum::<f64>().ln()
+}
+
+fn char_bit(c: char) -> Option<CharSet> {
+    let code = u32::from(c);
+    let first = u32::from(FIRST_CHAR);
+    if (first..first + ALPHABET_LEN as u32).contains(&code) {
+        Some(1 << (code - first))
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Default)]
+struct NfaState {
+    epsilon: Vec<usize>,
+    transition: Option<(CharSet, usize)>,
+}
+
+/// Thompson NFA over the printable ASCII alphabet
+#[derive(Debug, Default)]
+struct Nfa {
+    states: Vec<NfaState>,
+}
+
+impl Nfa {
+    fn add(&mut self) -> Result<usize, PasswordConfigError> {
+        if self.states.len() >= MAX_NFA_STATES {
+            return Err(PasswordConfigError::RegexTooComplex);
+        }
+        self.states.push(NfaState::default());
+        Ok(self.states.len() - 1)
+    }
+
+    /// Compiles `hir` into a fragment, returns its start and end states.
+    /// `at_start` and `at_end` tell whether nothing can be generated before or
+    /// after the fragment, anchors are only accepted there.
+    fn compile(
+        &mut self,
+        hir: &Hir,
+        max_repeat: u32,
+        at_start: bool,
+        at_end: bool,
+    ) -> Result<(usize, usize), PasswordConfigError> {
+        match hir.kind() {
+            HirKind::Empty => {
+                let state = self.add()?;
+                Ok((state, state))
+            }
+            HirKind::Look(look @ (Look::Start | Look::StartLF)) if !at_start => {
+                Err(PasswordConfigError::RegexUnsupported(format!(
+                    "assertion {:?} after the start of the pattern",
+                    look
+                )))
+            }
+            HirKind::Look(look @ (Look::End | Look::EndLF)) if !at_end => {
+                Err(PasswordConfigError::RegexUnsupported(format!(
+                    "assertion {:?} before the end of the pattern",
+                    look
+                )))
+            }
+            HirKind::Look(Look::Start | Look::End | Look::StartLF | Look::EndLF) => {
+                // Generated strings are always anchored
+                let state = self.add()?;
+                Ok((state, state))
+            }
+            HirKind::Look(look) => Err(PasswordConfigError::RegexUnsupported(format!(
+                "assertion {:?}",
+                look
+            ))),
+            HirKind::Literal(literal) => {
+                let text = std::str::from_utf8(&literal.0).map_err(|_| {
+                    PasswordConfigError::RegexUnsupported("non UTF-8 literal".to_string())
+                })?;
+                let start = self.add()?;
+                let mut end = start;
+                for c in text.chars() {
+                    let set = char_bit(c).ok_or_else(|| {
+                        PasswordConfigError::RegexUnsupported(format!(
+                            "character {:?} outside of printable ASCII",
+                            c
+                        ))
+                    })?;
+                    let next = self.add()?;
+                    self.states[end].transition = Some((set, next));
+                    end = next;
+                }
+                Ok((start, end))
+            }
+            HirKind::Class(class) => {
+                let set = match class {
+                    Class::Unicode(class) => class
+                        .ranges()
+                        .iter()
+                        .flat_map(|range| range.start()..=range.end())
+                        .take_while(|c| u32::from(*c) < 0x80)
+                        .filter_map(char_bit)
+                        .fold(0, |set, bit| set | bit),
+                    Class::Bytes(class) => class
+                        .ranges()
+                        .iter()
+                        .flat_map(|range| range.start()..=range.end())
+                        .filter_map(|b| char_bit(char::from(b)))
+                        .fold(0, |set, bit| set | bit),
+                };
+                if set == 0 {
+                    return Err(PasswordConfigError::RegexUnsupported(
+                        "character class without printable ASCII characters".to_string(),
+                    ));
+                }
+                let start = self.add()?;
+                let end = self.add()?;
+                self.states[start].transition = Some((set, end));
+                Ok((start, end))
+            }
+            HirKind::Capture(capture) => self.compile(&capture.sub, max_repeat, at_start, at_end),
+            HirKind::Concat(hirs) => {
+                let start = self.add()?;
+                let mut end = start;
+                // Zero width expressions like `^^a` don't move away from the edges
+                let zero_width = |hir: &Hir| hir.properties().maximum_len() == Some(0);
+                for (i, hir) in hirs.iter().enumerate() {
+                    let (s, e) = self.compile(
+                        hir,
+                        max_repeat,
+                        at_start && hirs[..i].iter().all(zero_width),
+                        at_end && hirs[i + 1..].iter().all(zero_width),
+                    )?;
+                    self.states[end].epsilon.push(s);
+                    end = e;
+                }
+                Ok((start, end))
+            }
+            HirKind::Alternation(hirs) => {
+                let start = self.add()?;
+                let end = self.add()?;
+                for hir in hirs {
+                    let (s, e) = self.compile(hir, max_repeat, at_start, at_end)?;
+                    self.states[start].epsilon.push(s);
+                    self.states[e].epsilon.push(end);
+                }
+                Ok((start, end))
+            }
+            HirKind::Repetition(repetition) => {
+                let start = self.add()?;
+                let end = self.add()?;
+
+                // More than max_repeat repetitions can't fit in the password, unless the
+                // sub expression matches the empty string
+                let matches_empty = repetition.sub.properties().minimum_len() == Some(0);
+                if repetition.min > max_repeat && !matches_empty {
+                    return Ok((start, end));
+                }
+                let min = repetition.min.min(max_repeat);
+                let max = repetition
+                    .max
+                    .unwrap_or(max_repeat)
+                    .min(max_repeat)
+                    .max(min);
+
+                // Only a single repetition keeps the sub expression at the edges
+                let single = max <= 1;
+                let mut current = start;
+                for i in 0..max {
+                    let (s, e) = self.compile(
+                        &repetition.sub,
+                        max_repeat,
+                        at_start && single,
+                        at_end && single,
+                    )?;
+                    if i >= min {
+                        self.states[current].epsilon.push(end);
+                    }
+                    self.states[current].epsilon.push(s);
+                    current = e;
+                }
+                self.states[current].epsilon.push(end);
+                Ok((start, end))
+            }
+        }
+    }
+
+    /// Extends `states` with the states reachable through epsilon transitions,
+    /// `seen` must be all `false` and is left that way
+    fn closure(&self, states: &mut Vec<usize>, seen: &mut [bool]) {
+        let mut stack = std::mem::take(states);
+        while let Some(state) = stack.pop() {
+            if seen[state] {
+                continue;
+            }
+            seen[state] = true;
+            states.push(state);
+            stack.extend(self.states[state].epsilon.iter().copied());
+        }
+        for state in states.iter() {
+            seen[*state] = false;
+        }
+        states.sort_unstable();
+    }
+}
+
+impl Dfa {
+    /// Subset construction, state 0 is the start state and the dead state is dropped
+    fn build(
+        nfa: &Nfa,
+        start: usize,
+        end: usize,
+        max_length: usize,
+    ) -> Result<Self, PasswordConfigError> {
+        let mut seen = vec![false; nfa.states.len()];
+        let mut initial = vec![start];
+        nfa.closure(&mut initial, &mut seen);
+
+        let mut ids: HashMap<Vec<usize>, usize> = HashMap::from([(initial.clone(), 0)]);
+        let mut sets = vec![initial];
+        let mut transitions = Vec::new();
+        let mut accepting = Vec::new();
+
+        let mut current = 0;
+        while current < sets.len() {
+            accepting.push(sets[current].binary_search(&end).is_ok());
+
+            let mut targets: BTreeMap<Vec<usize>, CharSet> = BTreeMap::new();
+            for bit in 0..ALPHABET_LEN {
+                let c: CharSet = 1 << bit;
+                let mut next: Vec<usize> = sets[current]
+                    .iter()
+                    .filter_map(|state| match nfa.states[*state].transition {
+                        Some((set, to)) if set & c != 0 => Some(to),
+                        _ => None,
+                    })
+                    .collect();
+                if next.is_empty() {
+                    continue;
+                }
+                nfa.closure(&mut next, &mut seen);
+                *targets.entry(next).or_default() |= c;
+            }
+
+            let mut state_transitions = Vec::with_capacity(targets.len());
+            for (set, chars) in targets {
+                let id = if let Some(id) = ids.get(&set) {
+                    *id
+                } else {
+                    if sets.len() >= MAX_DFA_STATES {
+                        return Err(PasswordConfigError::RegexTooComplex);
+                    }
+                    ids.insert(set.clone(), sets.len());
+                    sets.push(set);
+                    sets.len() - 1
+                };
+                state_transitions.push((id, chars));
+            }
+            transitions.push(state_transitions);
+            current += 1;
+        }
+
+        let mut log_counts = vec![vec![f64::NEG_INFINITY; max_length + 1]; sets.len()];
+        for (state, accept) in accepting.iter().enumerate() {
+            if *accept {
+                log_counts[state][0] = 0.0;
+            }
+        }
+
+        let mut terms = Vec::new();
+        for length in 1..=max_length {
+            for state in 0..sets.len() {
+                terms.clear();
+                terms.extend(transitions[state].iter().map(|(to, set)| {
+                    f64::from(set.count_ones()).ln() + log_counts[*to][length - 1]
+                }));
+                log_counts[state][length] = log_sum_exp(&terms);
+            }
+        }
+
+        Ok(Self {
+            transitions,
+            log_counts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_generate_from_regex() {
+        let pattern = RegexPattern::parse(r"[A-Z][a-z]{3}-\d{4}-[!@#]{2}", 18).unwrap();
+
+        for _ in 0..1000 {
+            let password = generate_from_regex(&pattern);
+            let chars: Vec<char> = password.chars().collect();
+
+            assert_eq!(chars.len(), 12);
+            assert!(chars[0].is_ascii_uppercase());
+            assert!(chars[1..4].iter().all(|c| c.is_ascii_lowercase()));
+            assert_eq!(chars[4], '-');
+            assert!(chars[5..9].iter().all(|c| c.is_ascii_digit()));
+            assert!(chars[10..].iter().all(|c| "!@#".contains(*c)));
+        }
+    }
+
+    #[test]
+    fn test_generate_from_regex_unbounded() {
+        let pattern = RegexPattern::parse(r"^[a-z0-9]+$", 18).unwrap();
+
+        for _ in 0..100 {
+            let password = generate_from_regex(&pattern);
+            assert!(!password.is_empty() && password.len() <= 18);
+            assert!(password
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+        }
+    }
+
+    #[test]
+    fn test_generate_from_regex_anchors() {
+        for regex in [r"^ab$", r"\Aab\z", r"(?m)^^(ab)$", r"^a$|^b$", r"(^ab$)?"] {
+            let pattern = RegexPattern::parse(regex, 18).unwrap();
+            let password = generate_from_regex(&pattern);
+            assert!(["ab", "a", "b", ""].contains(&password.as_str()), "{regex}");
+        }
+    }
+
+    #[test]
+    fn test_generate_from_regex_is_uniform() {
+        // "ab" is matched by both branches but must not be picked twice as often
+        let pattern = RegexPattern::parse(r"a[bc]|[ab]b", 4).unwrap();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+
+        for _ in 0..6000 {
+            *counts.entry(generate_from_regex(&pattern)).or_default() += 1;
+        }
+
+        assert_eq!(counts.len(), 3);
+        for count in counts.values() {
+            assert!((1600..2400).contains(count), "{:?}", counts);
+        }
+        assert!((pattern.entropy_bits() - 3f64.log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_regex_entropy_bits() {
+        let pattern = RegexPattern::parse(r"\d{6}", 18).unwrap();
+        assert!((pattern.entropy_bits() - 10f64.powi(6).log2()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_regex_errors() {
+        assert!(matches!(
+            RegexPattern::parse(r"[a-z", 18),
+            Err(PasswordConfigError::InvalidRegex(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"\bfoo", 18),
+            Err(PasswordConfigError::RegexUnsupported(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"a^b", 18),
+            Err(PasswordConfigError::RegexUnsupported(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"a$b", 18),
+            Err(PasswordConfigError::RegexUnsupported(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"(?m)a(^b)", 18),
+            Err(PasswordConfigError::RegexUnsupported(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"(^a)+", 18),
+            Err(PasswordConfigError::RegexUnsupported(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"ñ", 18),
+            Err(PasswordConfigError::RegexUnsupported(_))
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"[ab]{20}", 18),
+            Err(PasswordConfigError::RegexNoMatch { max_length: 18 })
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"", 18),
+            Err(PasswordConfigError::RegexNoMatch { .. })
+        ));
+        assert!(matches!(
+            RegexPattern::parse(r"[ab]*a[ab]{20}", 40),
+            Err(PasswordConfigError::RegexTooComplex)
+        ));
+    }
+}
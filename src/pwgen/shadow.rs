@@ -0,0 +1,168 @@
+use crate::pwgen::hash::HashAlgorithm;
+use anyhow::{bail, Result};
+use std::{
+    env, fmt,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Crypt hash algorithms accepted by `chpasswd -e` and `/etc/shadow` with libxcrypt
+pub const ALGORITHMS: [HashAlgorithm; 4] = [
+    HashAlgorithm::Sha512,
+    HashAlgorithm::Sha256,
+    HashAlgorithm::Yescrypt,
+    HashAlgorithm::Bcrypt,
+];
+
+/// Default algorithm, SHA-512 crypt is supported by every glibc
+pub const DEFAULT_ALGORITHM: HashAlgorithm = HashAlgorithm::Sha512;
+
+/// Minimum days between password changes, the `useradd` default
+pub const MIN_DAYS: u32 = 0;
+
+/// Maximum days a password is valid, the `useradd` default
+pub const MAX_DAYS: u32 = 99999;
+
+/// Days of warning before a password expires, the `useradd` default
+pub const WARN_DAYS: u32 = 7;
+
+/// Output formats of local account lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountFormat {
+    /// `user:hash` lines for `chpasswd -e`
+    Chpasswd,
+    /// Full `/etc/shadow` entries with the aging fields
+    Shadow,
+}
+
+impl AccountFormat {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Chpasswd => "chpasswd",
+            Self::Shadow => "shadow",
+        }
+    }
+
+    /// Line of an account, `last_change` is in days since the epoch
+    pub fn line(&self, user: &str, hash: &str, last_change: u64) -> String {
+        match self {
+            Self::Chpasswd => format!("{}:{}", user, hash),
+            Self::Shadow => format!(
+                "{}:{}:{}:{}:{}:{}:::",
+                user, hash, last_change, MIN_DAYS, MAX_DAYS, WARN_DAYS
+            ),
+        }
+    }
+}
+
+impl fmt::Display for AccountFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for AccountFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "chpasswd" => Ok(Self::Chpasswd),
+            "shadow" => Ok(Self::Shadow),
+            _ => Err(format!("Unknown format '{}', use chpasswd or shadow", s)),
+        }
+    }
+}
+
+/// Checks that a username can be stored in `/etc/passwd` and `/etc/shadow`
+pub fn validate_username(user: &str) -> Result<()> {
+    if user.is_empty() {
+        bail!("Username must not be empty");
+    }
+    if user.starts_with('-')
+        || user.contains(':')
+        || user.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        bail!(
+            "Invalid username '{}', it must not start with '-' or contain ':' or spaces",
+            user.escape_debug()
+        );
+    }
+    Ok(())
+}
+
+/// Checks that an algorithm writes a crypt hash `chpasswd -e` accepts
+pub fn validate_algorithm(algorithm: HashAlgorithm) -> Result<()> {
+    if !ALGORITHMS.contains(&algorithm) {
+        bail!(
+            "{} hashes are not crypt hashes, use sha512, sha256, yescrypt or bcrypt",
+            algorithm
+        );
+    }
+    Ok(())
+}
+
+/// Days since the epoch, from `SOURCE_DATE_EPOCH` when set for reproducible images
+pub fn days_since_epoch() -> u64 {
+    epoch_days(env::var("SOURCE_DATE_EPOCH").ok().as_deref())
+}
+
+fn epoch_days(source_date_epoch: Option<&str>) -> u64 {
+    let seconds = source_date_epoch
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        });
+
+    seconds / 86400
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line() {
+        assert_eq!(
+            AccountFormat::Chpasswd.line("alice", "$6$salt$hash", 19000),
+            "alice:$6$salt$hash"
+        );
+        assert_eq!(
+            AccountFormat::Shadow.line("alice", "$6$salt$hash", 19000),
+            "alice:$6$salt$hash:19000:0:99999:7:::"
+        );
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!(
+            "chpasswd".parse::<AccountFormat>(),
+            Ok(AccountFormat::Chpasswd)
+        );
+        assert_eq!("shadow".parse::<AccountFormat>(), Ok(AccountFormat::Shadow));
+        assert!("passwd".parse::<AccountFormat>().is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate_username("alice").is_ok());
+        assert!(validate_username("svc_backup$").is_ok());
+        assert!(validate_username("").is_err());
+        assert!(validate_username("-alice").is_err());
+        assert!(validate_username("al:ice").is_err());
+        assert!(validate_username("al ice").is_err());
+
+        assert!(validate_algorithm(HashAlgorithm::Yescrypt).is_ok());
+        assert!(validate_algorithm(HashAlgorithm::Argon2).is_err());
+    }
+
+    #[test]
+    fn test_epoch_days() {
+        assert_eq!(epoch_days(Some("1700000000")), 19675);
+        assert_eq!(epoch_days(Some("0")), 0);
+        // After 2024-01-01 without or with an invalid SOURCE_DATE_EPOCH
+        assert!(epoch_days(None) >= 19723);
+        assert!(epoch_days(Some("yesterday")) >= 19723);
+    }
+}
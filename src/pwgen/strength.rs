@@ -0,0 +1,1514 @@
+use crate::pwgen::passphrase::default_word_list;
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Embedded list of common passwords, most common first
+pub const COMMON_PASSWORDS: &str = include_str!("wordlists/common_passwords.txt");
+
+/// Embedded list of English words, most frequent first
+///
+/// The frequency is approximated by the order of the words in the GPT-2 byte
+/// pair encoding vocabulary, learned from web text, only dictionary words of
+/// at least 3 letters are kept.
+pub const ENGLISH_WORDS: &str = include_str!("wordlists/english.txt");
+
+/// Embedded list of first names, the ones found in the GPT-2 vocabulary first
+pub const FIRST_NAMES: &str = include_str!("wordlists/first_names.txt");
+
+/// Embedded list of surnames, the ones found in the GPT-2 vocabulary first
+pub const SURNAMES: &str = include_str!("wordlists/surnames.txt");
+
+/// Only the first characters of longer passwords are analysed
+pub const MAX_CHECK_LENGTH: usize = 100;
+
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10_000.0;
+const MIN_SUBMATCH_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_SUBMATCH_GUESSES_MULTI_CHAR: f64 = 50.0;
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+const MIN_YEAR_SPACE: i32 = 20;
+const DATE_MIN_YEAR: u32 = 1000;
+const DATE_MAX_YEAR: u32 = 2050;
+const MAX_SEQUENCE_DELTA: i32 = 5;
+const MAX_L33T_SUBSTITUTIONS: usize = 256;
+
+const L33T_TABLE: &[(char, &[char])] = &[
+    ('a', &['4', '@']),
+    ('b', &['8']),
+    ('c', &['(', '{', '[', '<']),
+    ('e', &['3']),
+    ('g', &['6', '9']),
+    ('i', &['1', '!', '|']),
+    ('l', &['1', '|', '7']),
+    ('o', &['0']),
+    ('s', &['$', '5']),
+    ('t', &['+', '7']),
+    ('x', &['%']),
+    ('z', &['2']),
+];
+
+/// Word lists used for dictionary matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dictionary {
+    /// Common passwords, ranked by popularity
+    Passwords,
+    /// English words, ranked by frequency
+    English,
+    /// First names, ranked by frequency
+    FirstNames,
+    /// Surnames, ranked by frequency
+    Surnames,
+    /// The embedded passphrase word list, unranked
+    Words,
+}
+
+/// Keyboard layouts used for spatial matches
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keyboard {
+    Qwerty,
+    Keypad,
+}
+
+/// Kind of weakness found in a part of the password
+#[derive(Debug, Clone, PartialEq)]
+pub enum MatchPattern {
+    Dictionary {
+        dictionary: Dictionary,
+        word: String,
+        rank: usize,
+        reversed: bool,
+        l33t: bool,
+    },
+    Spatial {
+        keyboard: Keyboard,
+        turns: usize,
+        shifted: usize,
+    },
+    Repeat {
+        base: String,
+        count: usize,
+        base_guesses: f64,
+    },
+    Sequence {
+        ascending: bool,
+    },
+    Year {
+        year: i32,
+    },
+    Date {
+        year: i32,
+        month: u8,
+        day: u8,
+        separator: Option<char>,
+    },
+    Bruteforce,
+}
+
+impl MatchPattern {
+    /// Short name of the pattern, used in the JSON output
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Self::Dictionary { .. } => "dictionary",
+            Self::Spatial { .. } => "spatial",
+            Self::Repeat { .. } => "repeat",
+            Self::Sequence { .. } => "sequence",
+            Self::Year { .. } => "year",
+            Self::Date { .. } => "date",
+            Self::Bruteforce => "bruteforce",
+        }
+    }
+}
+
+/// Part of the password matched by a pattern, `start` and `end` are inclusive char indexes
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    pub start: usize,
+    pub end: usize,
+    pub token: String,
+    pub pattern: MatchPattern,
+    pub guesses: f64,
+}
+
+/// Warning and suggestions to make a password stronger
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Feedback {
+    pub warning: Option<&'static str>,
+    pub suggestions: Vec<&'static str>,
+}
+
+/// Result of the strength estimation of a password
+#[derive(Debug, Clone, PartialEq)]
+pub struct Strength {
+    /// 0 (too guessable) to 4 (very unguessable)
+    pub score: u8,
+
+    /// Estimated number of guesses needed to find the password
+    pub guesses: f64,
+
+    /// Matches that explain the password with the fewest guesses
+    pub sequence: Vec<Match>,
+
+    pub feedback: Feedback,
+}
+
+impl Strength {
+    pub fn guesses_log10(&self) -> f64 {
+        self.guesses.log10()
+    }
+}
+
+/// Estimates how hard a password is to guess
+///
+/// The password is split in the most guessable sequence of dictionary words,
+/// keyboard walks, repeats, sequences, dates and brute-forced characters, the
+/// same model as zxcvbn.
+pub fn estimate_strength(password: &str) -> Strength {
+    let chars: Vec<char> = password.chars().take(MAX_CHECK_LENGTH).collect();
+    let (guesses, sequence) = most_guessable_sequence(&chars, omnimatch(&chars));
+    let score = score(guesses);
+    let feedback = feedback(score, &sequence);
+
+    Strength {
+        score,
+        guesses,
+        sequence,
+        feedback,
+    }
+}
+
+const fn score(guesses: f64) -> u8 {
+    // The delta gives a margin for matches right on a threshold
+    const DELTA: f64 = 5.0;
+
+    if guesses < 1e3 + DELTA {
+        0
+    } else if guesses < 1e6 + DELTA {
+        1
+    } else if guesses < 1e8 + DELTA {
+        2
+    } else if guesses < 1e10 + DELTA {
+        3
+    } else {
+        4
+    }
+}
+
+fn omnimatch(chars: &[char]) -> Vec<Match> {
+    let mut matches = dictionary_matches(chars);
+    matches.extend(reversed_dictionary_matches(chars));
+    matches.extend(l33t_matches(chars));
+    matches.extend(spatial_matches(chars));
+    matches.extend(repeat_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(year_matches(chars));
+    matches.extend(date_matches(chars));
+    matches
+}
+
+fn token(chars: &[char], start: usize, end: usize) -> String {
+    chars[start..=end].iter().collect()
+}
+
+// Dictionary matching
+
+struct Dictionaries {
+    lists: Vec<(Dictionary, HashMap<&'static str, usize>)>,
+    max_word_len: usize,
+}
+
+fn dictionaries() -> &'static Dictionaries {
+    static DICTIONARIES: OnceLock<Dictionaries> = OnceLock::new();
+
+    DICTIONARIES.get_or_init(|| {
+        // The first occurrence of a word gives its rank
+        let ranked = |list: &'static str| -> HashMap<&'static str, usize> {
+            let mut ranks = HashMap::new();
+            for (index, word) in list.lines().enumerate() {
+                ranks.entry(word).or_insert(index + 1);
+            }
+            ranks
+        };
+
+        // The word list has no frequency order, every word costs the size of the list
+        let words_len = default_word_list().len();
+        let words: HashMap<&str, usize> = default_word_list()
+            .iter()
+            .map(|word| (*word, words_len))
+            .collect();
+
+        let lists = vec![
+            (Dictionary::Passwords, ranked(COMMON_PASSWORDS)),
+            (Dictionary::English, ranked(ENGLISH_WORDS)),
+            (Dictionary::FirstNames, ranked(FIRST_NAMES)),
+            (Dictionary::Surnames, ranked(SURNAMES)),
+            (Dictionary::Words, words),
+        ];
+        let max_word_len = lists
+            .iter()
+            .flat_map(|(_, list)| list.keys())
+            .map(|word| word.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        Dictionaries {
+            lists,
+            max_word_len,
+        }
+    })
+}
+
+fn dictionary_matches(chars: &[char]) -> Vec<Match> {
+    let lower: Vec<char> = chars.iter().map(char::to_ascii_lowercase).collect();
+    let dictionaries = dictionaries();
+    let mut matches = Vec::new();
+
+    for start in 0..chars.len() {
+        let last = (start + dictionaries.max_word_len).min(chars.len());
+        for end in start..last {
+            let word: String = lower[start..=end].iter().collect();
+            for (dictionary, list) in &dictionaries.lists {
+                if let Some(rank) = list.get(word.as_str()) {
+                    let token = token(chars, start, end);
+                    let guesses = *rank as f64 * uppercase_variations(&token);
+                    matches.push(Match {
+                        start,
+                        end,
+                        token,
+                        pattern: MatchPattern::Dictionary {
+                            dictionary: *dictionary,
+                            word: word.clone(),
+                            rank: *rank,
+                            reversed: false,
+                            l33t: false,
+                        },
+                        guesses,
+                    });
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+fn reversed_dictionary_matches(chars: &[char]) -> Vec<Match> {
+    let reversed: Vec<char> = chars.iter().rev().copied().collect();
+    let len = chars.len();
+
+    dictionary_matches(&reversed)
+        .into_iter()
+        .filter(|m| m.token.chars().count() > 1)
+        .map(|m| {
+            let (start, end) = (len - 1 - m.end, len - 1 - m.start);
+            let pattern = match m.pattern {
+                MatchPattern::Dictionary {
+                    dictionary,
+                    word,
+                    rank,
+                    ..
+                } => MatchPattern::Dictionary {
+                    dictionary,
+                    word,
+                    rank,
+                    reversed: true,
+                    l33t: false,
+                },
+                pattern => pattern,
+            };
+            Match {
+                start,
+                end,
+                token: token(chars, start, end),
+                pattern,
+                guesses: m.guesses * 2.0,
+            }
+        })
+        .collect()
+}
+
+fn l33t_letters(c: char) -> Vec<char> {
+    L33T_TABLE
+        .iter()
+        .filter(|(_, subs)| subs.contains(&c))
+        .map(|(letter, _)| *letter)
+        .collect()
+}
+
+/// Dictionary, word, rank and the substitutions applied
+type L33tWord = (Dictionary, String, usize, Vec<(char, char)>);
+
+fn l33t_matches(chars: &[char]) -> Vec<Match> {
+    let dictionaries = dictionaries();
+    let mut matches = Vec::new();
+
+    for start in 0..chars.len() {
+        let last = (start + dictionaries.max_word_len).min(chars.len());
+        for end in (start + 1)..last {
+            let token_chars = &chars[start..=end];
+            let options: Vec<(usize, Vec<char>)> = token_chars
+                .iter()
+                .enumerate()
+                .map(|(index, c)| (index, l33t_letters(*c)))
+                .filter(|(_, letters)| !letters.is_empty())
+                .collect();
+
+            let combinations = options.iter().try_fold(1usize, |total, (_, letters)| {
+                total.checked_mul(letters.len())
+            });
+            if options.is_empty() || combinations.is_none_or(|total| total > MAX_L33T_SUBSTITUTIONS)
+            {
+                continue;
+            }
+
+            let lower: Vec<char> = token_chars.iter().map(char::to_ascii_lowercase).collect();
+            let mut found: Vec<L33tWord> = Vec::new();
+
+            let mut choice = vec![0; options.len()];
+            loop {
+                let mut word = lower.clone();
+                let mut subs = Vec::with_capacity(options.len());
+                for ((index, letters), pick) in options.iter().zip(&choice) {
+                    word[*index] = letters[*pick];
+                    subs.push((token_chars[*index], letters[*pick]));
+                }
+                let word: String = word.into_iter().collect();
+
+                for (dictionary, list) in &dictionaries.lists {
+                    if let Some(rank) = list.get(word.as_str()) {
+                        if !found.iter().any(|(d, w, ..)| d == dictionary && *w == word) {
+                            found.push((*dictionary, word.clone(), *rank, subs.clone()));
+                        }
+                    }
+                }
+
+                // Next combination, like an odometer
+                let mut position = 0;
+                while position < choice.len() {
+                    choice[position] += 1;
+                    if choice[position] < options[position].1.len() {
+                        break;
+                    }
+                    choice[position] = 0;
+                    position += 1;
+                }
+                if position == choice.len() {
+                    break;
+                }
+            }
+
+            for (dictionary, word, rank, subs) in found {
+                let token = token(chars, start, end);
+                let guesses =
+                    rank as f64 * uppercase_variations(&token) * l33t_variations(&token, &subs);
+                matches.push(Match {
+                    start,
+                    end,
+                    token,
+                    pattern: MatchPattern::Dictionary {
+                        dictionary,
+                        word,
+                        rank,
+                        reversed: false,
+                        l33t: true,
+                    },
+                    guesses,
+                });
+            }
+        }
+    }
+
+    matches
+}
+
+/// Number of ways to capitalize a word, the common ones count as two
+fn uppercase_variations(token: &str) -> f64 {
+    let chars: Vec<char> = token.chars().collect();
+    let upper = chars.iter().filter(|c| c.is_ascii_uppercase()).count();
+    let lower = chars.iter().filter(|c| c.is_ascii_lowercase()).count();
+
+    if upper == 0 {
+        return 1.0;
+    }
+
+    let start_upper = upper == 1 && chars[0].is_ascii_uppercase() && chars.len() > 1;
+    let end_upper = upper == 1 && chars[chars.len() - 1].is_ascii_uppercase() && chars.len() > 1;
+    if start_upper || end_upper || lower == 0 {
+        return 2.0;
+    }
+
+    (1..=upper.min(lower))
+        .map(|i| binomial(upper + lower, i))
+        .sum()
+}
+
+/// Number of ways to apply the substitutions found in a l33t word
+fn l33t_variations(token: &str, subs: &[(char, char)]) -> f64 {
+    let lower: Vec<char> = token.chars().map(|c| c.to_ascii_lowercase()).collect();
+    let mut seen = Vec::new();
+    let mut variations = 1.0;
+
+    for (subbed, letter) in subs {
+        if seen.contains(&(*subbed, *letter)) {
+            continue;
+        }
+        seen.push((*subbed, *letter));
+
+        let subbed_count = lower.iter().filter(|c| *c == subbed).count();
+        let unsubbed_count = lower.iter().filter(|c| *c == letter).count();
+
+        if subbed_count == 0 || unsubbed_count == 0 {
+            variations *= 2.0;
+        } else {
+            variations *= (1..=subbed_count.min(unsubbed_count))
+                .map(|i| binomial(subbed_count + unsubbed_count, i))
+                .sum::<f64>();
+        }
+    }
+
+    variations
+}
+
+// Spatial matching
+
+/// A key and its shifted character
+type Key = (char, char);
+
+struct Graph {
+    keyboard: Keyboard,
+    rows: Vec<Vec<Option<Key>>>,
+    positions: HashMap<char, (usize, usize, bool)>,
+    directions: &'static [(isize, isize)],
+    starting_positions: f64,
+    average_degree: f64,
+}
+
+/// Rows of slanted keyboards are shifted by half a key, so keys have 6 neighbours
+const SLANTED_DIRECTIONS: &[(isize, isize)] = &[(0, -1), (-1, 0), (-1, 1), (0, 1), (1, 0), (1, -1)];
+
+const ALIGNED_DIRECTIONS: &[(isize, isize)] = &[
+    (0, -1),
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+    (1, 0),
+    (1, -1),
+];
+
+impl Graph {
+    fn new(
+        keyboard: Keyboard,
+        rows: Vec<Vec<Option<Key>>>,
+        directions: &'static [(isize, isize)],
+    ) -> Self {
+        let mut positions = HashMap::new();
+        for (row, keys) in rows.iter().enumerate() {
+            for (column, key) in keys.iter().enumerate() {
+                if let Some((plain, shifted)) = key {
+                    positions.insert(*plain, (row, column, false));
+                    positions.entry(*shifted).or_insert((row, column, true));
+                }
+            }
+        }
+
+        let mut graph = Self {
+            keyboard,
+            rows,
+            positions,
+            directions,
+            starting_positions: 0.0,
+            average_degree: 0.0,
+        };
+
+        let keys: Vec<(usize, usize)> = graph
+            .rows
+            .iter()
+            .enumerate()
+            .flat_map(|(row, keys)| {
+                keys.iter()
+                    .enumerate()
+                    .filter(|(_, key)| key.is_some())
+                    .map(move |(column, _)| (row, column))
+            })
+            .collect();
+        let degrees: usize = keys
+            .iter()
+            .map(|(row, column)| {
+                (0..directions.len())
+                    .filter(|direction| graph.neighbour(*row, *column, *direction).is_some())
+                    .count()
+            })
+            .sum();
+
+        graph.starting_positions = graph.positions.len() as f64;
+        graph.average_degree = degrees as f64 / keys.len() as f64;
+        graph
+    }
+
+    fn neighbour(&self, row: usize, column: usize, direction: usize) -> Option<Key> {
+        let (dr, dc) = self.directions[direction];
+        let row = row.checked_add_signed(dr)?;
+        let column = column.checked_add_signed(dc)?;
+        self.rows.get(row)?.get(column).copied().flatten()
+    }
+}
+
+fn graphs() -> &'static [Graph; 2] {
+    static GRAPHS: OnceLock<[Graph; 2]> = OnceLock::new();
+
+    GRAPHS.get_or_init(|| {
+        // Rows after the first start one key to the right, `q` is below `1` and `2`
+        let pairs = |offset: usize, row: &str| -> Vec<Option<Key>> {
+            let chars: Vec<char> = row.chars().collect();
+            let keys = chars.chunks(2).map(|key| Some((key[0], key[1])));
+            std::iter::repeat_n(None, offset).chain(keys).collect()
+        };
+        let qwerty = vec![
+            pairs(0, "`~1!2@3#4$5%6^7&8*9(0)-_=+"),
+            pairs(1, "qQwWeErRtTyYuUiIoOpP[{]}\\|"),
+            pairs(1, "aAsSdDfFgGhHjJkKlL;:'\""),
+            pairs(1, "zZxXcCvVbBnNmM,<.>/?"),
+        ];
+
+        let singles = |row: &str| -> Vec<Option<Key>> {
+            row.chars()
+                .map(|c| if c == ' ' { None } else { Some((c, c)) })
+                .collect()
+        };
+        let keypad = vec![
+            singles(" /*-"),
+            singles("789+"),
+            singles("456 "),
+            singles("123 "),
+            singles(" 0. "),
+        ];
+
+        [
+            Graph::new(Keyboard::Qwerty, qwerty, SLANTED_DIRECTIONS),
+            Graph::new(Keyboard::Keypad, keypad, ALIGNED_DIRECTIONS),
+        ]
+    })
+}
+
+fn spatial_matches(chars: &[char]) -> Vec<Match> {
+    graphs()
+        .iter()
+        .flat_map(|graph| spatial_matches_in(chars, graph))
+        .collect()
+}
+
+fn spatial_matches_in(chars: &[char], graph: &Graph) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+
+    while start + 1 < chars.len() {
+        let mut end = start + 1;
+        let mut last_direction = None;
+        let mut turns = 0;
+        let mut shifted = usize::from(
+            graph
+                .positions
+                .get(&chars[start])
+                .is_some_and(|position| position.2),
+        );
+
+        loop {
+            let found = chars.get(end).and_then(|current| {
+                let (row, column, _) = *graph.positions.get(&chars[end - 1])?;
+                (0..graph.directions.len()).find_map(|direction| {
+                    let (plain, upper) = graph.neighbour(row, column, direction)?;
+                    if *current == plain {
+                        Some((direction, false))
+                    } else if *current == upper {
+                        Some((direction, true))
+                    } else {
+                        None
+                    }
+                })
+            });
+
+            match found {
+                Some((direction, is_shifted)) => {
+                    if is_shifted {
+                        shifted += 1;
+                    }
+                    if last_direction != Some(direction) {
+                        turns += 1;
+                        last_direction = Some(direction);
+                    }
+                    end += 1;
+                }
+                None => {
+                    if end - start > 2 {
+                        let token = token(chars, start, end - 1);
+                        let guesses = spatial_guesses(graph, end - start, turns, shifted);
+                        matches.push(Match {
+                            start,
+                            end: end - 1,
+                            token,
+                            pattern: MatchPattern::Spatial {
+                                keyboard: graph.keyboard,
+                                turns,
+                                shifted,
+                            },
+                            guesses,
+                        });
+                    }
+                    start = end;
+                    break;
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+fn spatial_guesses(graph: &Graph, length: usize, turns: usize, shifted: usize) -> f64 {
+    let mut guesses = 0.0;
+    for i in 2..=length {
+        for j in 1..=turns.min(i - 1) {
+            guesses += binomial(i - 1, j - 1)
+                * graph.starting_positions
+                * graph.average_degree.powi(j as i32);
+        }
+    }
+
+    if shifted > 0 {
+        let unshifted = length - shifted;
+        if unshifted == 0 {
+            guesses *= 2.0;
+        } else {
+            guesses *= (1..=shifted.min(unshifted))
+                .map(|i| binomial(shifted + unshifted, i))
+                .sum::<f64>();
+        }
+    }
+
+    guesses
+}
+
+// Repeat matching
+
+fn repeat_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+
+    while start < chars.len() {
+        // Longest repeated run from here, the shortest base wins ties
+        let mut best: Option<(usize, usize)> = None;
+        for base_len in 1..=(chars.len() - start) / 2 {
+            let base = &chars[start..start + base_len];
+            let count = chars[start..]
+                .chunks(base_len)
+                .take_while(|chunk| *chunk == base)
+                .count();
+            if count >= 2 && best.is_none_or(|(len, n)| base_len * count > len * n) {
+                best = Some((base_len, count));
+            }
+        }
+
+        match best {
+            Some((base_len, count)) => {
+                let end = start + base_len * count - 1;
+                let base: String = chars[start..start + base_len].iter().collect();
+                let base_guesses = estimate_strength(&base).guesses;
+                matches.push(Match {
+                    start,
+                    end,
+                    token: token(chars, start, end),
+                    pattern: MatchPattern::Repeat {
+                        base,
+                        count,
+                        base_guesses,
+                    },
+                    guesses: base_guesses * count as f64,
+                });
+                start = end + 1;
+            }
+            None => start += 1,
+        }
+    }
+
+    matches
+}
+
+// Sequence matching
+
+fn sequence_matches(chars: &[char]) -> Vec<Match> {
+    if chars.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut matches = Vec::new();
+    let mut push = |start: usize, end: usize, delta: i32| {
+        if (end - start > 1 || delta.abs() == 1) && delta != 0 && delta.abs() <= MAX_SEQUENCE_DELTA
+        {
+            let token = token(chars, start, end);
+            let first = chars[start];
+            let mut base = if matches!(first, 'a' | 'A' | 'z' | 'Z' | '0' | '1' | '9') {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            if delta < 0 {
+                base *= 2.0;
+            }
+            matches.push(Match {
+                start,
+                end,
+                token,
+                pattern: MatchPattern::Sequence {
+                    ascending: delta > 0,
+                },
+                guesses: base * (end - start + 1) as f64,
+            });
+        }
+    };
+
+    let delta = |k: usize| chars[k] as i32 - chars[k - 1] as i32;
+    let mut start = 0;
+    let mut last_delta = delta(1);
+    for k in 2..chars.len() {
+        let current = delta(k);
+        if current != last_delta {
+            push(start, k - 1, last_delta);
+            start = k - 1;
+            last_delta = current;
+        }
+    }
+    push(start, chars.len() - 1, last_delta);
+
+    matches
+}
+
+// Date matching
+
+fn reference_year() -> i32 {
+    static YEAR: OnceLock<i32> = OnceLock::new();
+
+    *YEAR.get_or_init(|| {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        1970 + i32::try_from(seconds / 31_556_952).unwrap_or(0)
+    })
+}
+
+fn year_space(year: i32) -> f64 {
+    f64::from((year - reference_year()).abs().max(MIN_YEAR_SPACE))
+}
+
+fn year_matches(chars: &[char]) -> Vec<Match> {
+    (0..chars.len().saturating_sub(3))
+        .filter_map(|start| {
+            let digits = &chars[start..start + 4];
+            if !digits.iter().all(char::is_ascii_digit)
+                || !matches!(digits[..2], ['1', '9'] | ['2', '0'])
+            {
+                return None;
+            }
+            let year: i32 = digits.iter().collect::<String>().parse().ok()?;
+            Some(Match {
+                start,
+                end: start + 3,
+                token: token(chars, start, start + 3),
+                pattern: MatchPattern::Year { year },
+                guesses: year_space(year),
+            })
+        })
+        .collect()
+}
+
+fn date_matches(chars: &[char]) -> Vec<Match> {
+    let mut matches = Vec::new();
+
+    // Dates without separator, every way to split the digits
+    for start in 0..chars.len() {
+        for end in (start + 3)..(start + 8).min(chars.len()) {
+            let digits = &chars[start..=end];
+            if !digits.iter().all(char::is_ascii_digit) {
+                continue;
+            }
+            let splits: &[(usize, usize)] = match digits.len() {
+                4 => &[(1, 2), (2, 3)],
+                5 => &[(1, 3), (2, 3)],
+                6 => &[(1, 2), (2, 4), (4, 5)],
+                7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+                8 => &[(2, 4), (4, 6)],
+                _ => continue,
+            };
+
+            let best = splits
+                .iter()
+                .filter_map(|(k, l)| {
+                    let number = |range: &[char]| range.iter().collect::<String>().parse().ok();
+                    let ints = [
+                        number(&digits[..*k])?,
+                        number(&digits[*k..*l])?,
+                        number(&digits[*l..])?,
+                    ];
+                    ints_to_date(ints)
+                })
+                .min_by_key(|(year, ..)| (year - reference_year()).abs());
+
+            if let Some((year, month, day)) = best {
+                matches.push(date_match(chars, start, end, year, month, day, None));
+            }
+        }
+    }
+
+    // Dates with a separator, like 13/05/1990 or 1990-05-13
+    for start in 0..chars.len() {
+        for end in (start + 5)..(start + 10).min(chars.len()) {
+            if let Some((ints, separator)) = split_date(&chars[start..=end]) {
+                if let Some((year, month, day)) = ints_to_date(ints) {
+                    matches.push(date_match(
+                        chars,
+                        start,
+                        end,
+                        year,
+                        month,
+                        day,
+                        Some(separator),
+                    ));
+                }
+            }
+        }
+    }
+
+    // Drop dates inside a longer date
+    let spans: Vec<(usize, usize)> = matches.iter().map(|m| (m.start, m.end)).collect();
+    matches.retain(|m| {
+        !spans.iter().any(|(start, end)| {
+            (*start, *end) != (m.start, m.end) && *start <= m.start && *end >= m.end
+        })
+    });
+
+    matches
+}
+
+fn date_match(
+    chars: &[char],
+    start: usize,
+    end: usize,
+    year: i32,
+    month: u8,
+    day: u8,
+    separator: Option<char>,
+) -> Match {
+    let mut guesses = year_space(year) * 365.0;
+    if separator.is_some() {
+        guesses *= 4.0;
+    }
+
+    Match {
+        start,
+        end,
+        token: token(chars, start, end),
+        pattern: MatchPattern::Date {
+            year,
+            month,
+            day,
+            separator,
+        },
+        guesses,
+    }
+}
+
+/// Splits `d{1,4} sep d{1,2} sep d{1,4}` with the same separator twice
+fn split_date(chars: &[char]) -> Option<([u32; 3], char)> {
+    let is_separator = |c: char| matches!(c, ' ' | '/' | '\\' | '_' | '.' | '-');
+    let first = chars.iter().position(|c| !c.is_ascii_digit())?;
+    let separator = chars[first];
+    let second = first
+        + 1
+        + chars[first + 1..]
+            .iter()
+            .position(|c| !c.is_ascii_digit())?;
+
+    if !is_separator(separator) || chars[second] != separator {
+        return None;
+    }
+
+    let parts = [
+        &chars[..first],
+        &chars[first + 1..second],
+        &chars[second + 1..],
+    ];
+    let valid_lengths = (1..=4).contains(&parts[0].len())
+        && (1..=2).contains(&parts[1].len())
+        && (1..=4).contains(&parts[2].len());
+    if !valid_lengths || !parts[2].iter().all(char::is_ascii_digit) {
+        return None;
+    }
+
+    let number = |part: &[char]| part.iter().collect::<String>().parse().ok();
+    Some((
+        [number(parts[0])?, number(parts[1])?, number(parts[2])?],
+        separator,
+    ))
+}
+
+/// Reads three numbers as a date, the year is either first or last
+fn ints_to_date(ints: [u32; 3]) -> Option<(i32, u8, u8)> {
+    if ints[1] > 31 || ints[1] == 0 {
+        return None;
+    }
+
+    let mut over_12 = 0;
+    let mut over_31 = 0;
+    let mut under_1 = 0;
+    for int in ints {
+        if (99 < int && int < DATE_MIN_YEAR) || int > DATE_MAX_YEAR {
+            return None;
+        }
+        over_31 += usize::from(int > 31);
+        over_12 += usize::from(int > 12);
+        under_1 += usize::from(int == 0);
+    }
+    if over_31 >= 2 || over_12 == 3 || under_1 >= 2 {
+        return None;
+    }
+
+    let splits = [(ints[2], [ints[0], ints[1]]), (ints[0], [ints[1], ints[2]])];
+
+    for (year, rest) in splits {
+        if (DATE_MIN_YEAR..=DATE_MAX_YEAR).contains(&year) {
+            let (month, day) = ints_to_month_day(rest)?;
+            return Some((i32::try_from(year).ok()?, month, day));
+        }
+    }
+
+    splits.into_iter().find_map(|(year, rest)| {
+        let (month, day) = ints_to_month_day(rest)?;
+        let year = match year {
+            y if y > 99 => y,
+            y if y > 50 => 1900 + y,
+            y => 2000 + y,
+        };
+        Some((i32::try_from(year).ok()?, month, day))
+    })
+}
+
+fn ints_to_month_day([a, b]: [u32; 2]) -> Option<(u8, u8)> {
+    [(a, b), (b, a)].into_iter().find_map(|(day, month)| {
+        if (1..=31).contains(&day) && (1..=12).contains(&month) {
+            Some((u8::try_from(month).ok()?, u8::try_from(day).ok()?))
+        } else {
+            None
+        }
+    })
+}
+
+// Scoring
+
+fn bruteforce_match(chars: &[char], start: usize, end: usize) -> Match {
+    let length = end - start + 1;
+    let min_guesses = if length == 1 {
+        MIN_SUBMATCH_GUESSES_SINGLE_CHAR + 1.0
+    } else {
+        MIN_SUBMATCH_GUESSES_MULTI_CHAR + 1.0
+    };
+
+    Match {
+        start,
+        end,
+        token: token(chars, start, end),
+        pattern: MatchPattern::Bruteforce,
+        guesses: BRUTEFORCE_CARDINALITY.powi(length as i32).max(min_guesses),
+    }
+}
+
+/// Matches shorter than the password need a minimum number of guesses
+const fn adjusted_guesses(m: &Match, password_len: usize) -> f64 {
+    let length = m.end - m.start + 1;
+    let min_guesses = if length == password_len {
+        1.0
+    } else if length == 1 {
+        MIN_SUBMATCH_GUESSES_SINGLE_CHAR
+    } else {
+        MIN_SUBMATCH_GUESSES_MULTI_CHAR
+    };
+
+    m.guesses.max(min_guesses)
+}
+
+/// Best match sequence ending at a position for a given number of matches
+#[derive(Clone, Copy)]
+struct Step {
+    index: usize,
+    product: f64,
+    guesses: f64,
+}
+
+/// Finds the sequence of non overlapping matches covering the password with the fewest guesses
+///
+/// Gaps are filled with brute-force matches, longer sequences pay a penalty
+/// for the attacker having to guess how the matches are combined.
+fn most_guessable_sequence(chars: &[char], matches: Vec<Match>) -> (f64, Vec<Match>) {
+    let len = chars.len();
+    if len == 0 {
+        return (1.0, Vec::new());
+    }
+
+    let mut arena: Vec<Match> = matches
+        .into_iter()
+        .map(|mut m| {
+            m.guesses = adjusted_guesses(&m, len);
+            m
+        })
+        .collect();
+    let mut by_end: Vec<Vec<usize>> = vec![Vec::new(); len];
+    for (index, m) in arena.iter().enumerate() {
+        by_end[m.end].push(index);
+    }
+
+    let factorials = {
+        let mut table = vec![1.0; len + 1];
+        for i in 1..=len {
+            table[i] = table[i - 1] * i as f64;
+        }
+        table
+    };
+
+    let mut optimal: Vec<BTreeMap<usize, Step>> = vec![BTreeMap::new(); len];
+
+    let update =
+        |optimal: &mut Vec<BTreeMap<usize, Step>>, arena: &[Match], index: usize, l: usize| {
+            let m = &arena[index];
+            let mut product = m.guesses;
+            if l > 1 {
+                match optimal[m.start - 1].get(&(l - 1)) {
+                    Some(previous) => product *= previous.product,
+                    None => return,
+                }
+            }
+            let guesses = factorials[l].mul_add(
+                product,
+                MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(l as i32 - 1),
+            );
+
+            // Only keep it when no shorter sequence is already as good
+            if optimal[m.end]
+                .range(..=l)
+                .any(|(_, step)| step.guesses <= guesses)
+            {
+                return;
+            }
+            optimal[m.end].insert(
+                l,
+                Step {
+                    index,
+                    product,
+                    guesses,
+                },
+            );
+        };
+
+    for (k, ends) in by_end.iter().enumerate() {
+        for &index in ends {
+            let start = arena[index].start;
+            if start > 0 {
+                let lengths: Vec<usize> = optimal[start - 1].keys().copied().collect();
+                for l in lengths {
+                    update(&mut optimal, &arena, index, l + 1);
+                }
+            } else {
+                update(&mut optimal, &arena, index, 1);
+            }
+        }
+
+        arena.push(bruteforce_match(chars, 0, k));
+        update(&mut optimal, &arena, arena.len() - 1, 1);
+
+        for start in 1..=k {
+            let previous: Vec<(usize, usize)> = optimal[start - 1]
+                .iter()
+                .map(|(l, step)| (*l, step.index))
+                .collect();
+            // Two consecutive brute-force matches are never better than one
+            if previous
+                .iter()
+                .all(|(_, index)| arena[*index].pattern == MatchPattern::Bruteforce)
+            {
+                continue;
+            }
+            arena.push(bruteforce_match(chars, start, k));
+            let index = arena.len() - 1;
+            for (l, previous_index) in previous {
+                if arena[previous_index].pattern != MatchPattern::Bruteforce {
+                    update(&mut optimal, &arena, index, l + 1);
+                }
+            }
+        }
+    }
+
+    let Some((mut l, best)) = optimal[len - 1]
+        .iter()
+        .min_by(|a, b| a.1.guesses.total_cmp(&b.1.guesses))
+        .map(|(l, step)| (*l, *step))
+    else {
+        return (f64::INFINITY, Vec::new());
+    };
+
+    let mut sequence = Vec::with_capacity(l);
+    let mut k = len - 1;
+    loop {
+        let step = optimal[k][&l];
+        let m = arena[step.index].clone();
+        let start = m.start;
+        sequence.push(m);
+        if start == 0 {
+            break;
+        }
+        k = start - 1;
+        l -= 1;
+    }
+    sequence.reverse();
+
+    (best.guesses, sequence)
+}
+
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (1..=k).fold(1.0, |result, i| result * (n - k + i) as f64 / i as f64)
+}
+
+// Feedback
+
+fn feedback(score: u8, sequence: &[Match]) -> Feedback {
+    if sequence.is_empty() {
+        return Feedback {
+            warning: None,
+            suggestions: vec![
+                "Use a few words, avoid common phrases",
+                "No need for symbols, digits, or uppercase letters",
+            ],
+        };
+    }
+
+    if score > 2 {
+        return Feedback::default();
+    }
+
+    let longest = sequence
+        .iter()
+        .reduce(|longest, m| {
+            if m.token.chars().count() > longest.token.chars().count() {
+                m
+            } else {
+                longest
+            }
+        })
+        .unwrap_or(&sequence[0]);
+
+    let match_feedback = match_feedback(longest, sequence.len() == 1);
+    let mut suggestions = vec!["Add another word or two. Uncommon words are better."];
+    suggestions.extend(match_feedback.suggestions);
+
+    Feedback {
+        warning: match_feedback.warning,
+        suggestions,
+    }
+}
+
+fn match_feedback(m: &Match, is_sole_match: bool) -> Feedback {
+    match &m.pattern {
+        MatchPattern::Dictionary {
+            dictionary,
+            rank,
+            reversed,
+            l33t,
+            ..
+        } => {
+            let warning = match dictionary {
+                Dictionary::Passwords if is_sole_match && !l33t && !reversed => {
+                    Some(if *rank <= 10 {
+                        "This is a top-10 common password"
+                    } else if *rank <= 100 {
+                        "This is a top-100 common password"
+                    } else {
+                        "This is a very common password"
+                    })
+                }
+                Dictionary::Passwords if m.guesses.log10() <= 4.0 => {
+                    Some("This is similar to a commonly used password")
+                }
+                Dictionary::English | Dictionary::Words if is_sole_match => {
+                    Some("A word by itself is easy to guess")
+                }
+                Dictionary::FirstNames | Dictionary::Surnames if is_sole_match => {
+                    Some("Names and surnames by themselves are easy to guess")
+                }
+                Dictionary::FirstNames | Dictionary::Surnames => {
+                    Some("Common names and surnames are easy to guess")
+                }
+                _ => None,
+            };
+
+            let mut suggestions = Vec::new();
+            let chars: Vec<char> = m.token.chars().collect();
+            let upper = chars.iter().filter(|c| c.is_ascii_uppercase()).count();
+            if upper == 1 && chars[0].is_ascii_uppercase() && chars.len() > 1 {
+                suggestions.push("Capitalization doesn't help very much");
+            } else if upper > 0 && !chars.iter().any(char::is_ascii_lowercase) {
+                suggestions.push("All-uppercase is almost as easy to guess as all-lowercase");
+            }
+            if *reversed && chars.len() >= 4 {
+                suggestions.push("Reversed words aren't much harder to guess");
+            }
+            if *l33t {
+                suggestions
+                    .push("Predictable substitutions like '@' instead of 'a' don't help very much");
+            }
+
+            Feedback {
+                warning,
+                suggestions,
+            }
+        }
+        MatchPattern::Spatial { turns, .. } => Feedback {
+            warning: Some(if *turns == 1 {
+                "Straight rows of keys are easy to guess"
+            } else {
+                "Short keyboard patterns are easy to guess"
+            }),
+            suggestions: vec!["Use a longer keyboard pattern with more turns"],
+        },
+        MatchPattern::Repeat { base, .. } => Feedback {
+            warning: Some(if base.chars().count() == 1 {
+                "Repeats like \"aaa\" are easy to guess"
+            } else {
+                "Repeats like \"abcabcabc\" are only slightly harder to guess than \"abc\""
+            }),
+            suggestions: vec!["Avoid repeated words and characters"],
+        },
+        MatchPattern::Sequence { .. } => Feedback {
+            warning: Some("Sequences like abc or 6543 are easy to guess"),
+            suggestions: vec!["Avoid sequences"],
+        },
+        MatchPattern::Year { .. } => Feedback {
+            warning: Some("Recent years are easy to guess"),
+            suggestions: vec![
+                "Avoid recent years",
+                "Avoid years that are associated with you",
+            ],
+        },
+        MatchPattern::Date { .. } => Feedback {
+            warning: Some("Dates are often easy to guess"),
+            suggestions: vec!["Avoid dates and years that are associated with you"],
+        },
+        MatchPattern::Bruteforce => Feedback::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(password: &str) -> Vec<&'static str> {
+        estimate_strength(password)
+            .sequence
+            .iter()
+            .map(|m| m.pattern.name())
+            .collect()
+    }
+
+    #[test]
+    fn test_common_password() {
+        let strength = estimate_strength("password");
+
+        // Rank 2, plus one for the single match sequence
+        assert_eq!(strength.score, 0);
+        assert!((strength.guesses - 3.0).abs() < f64::EPSILON);
+        assert_eq!(
+            strength.feedback.warning,
+            Some("This is a top-10 common password")
+        );
+    }
+
+    #[test]
+    fn test_english_words_and_names() {
+        let dictionary = |password: &str| match &estimate_strength(password).sequence[..] {
+            [Match {
+                pattern:
+                    MatchPattern::Dictionary {
+                        dictionary, rank, ..
+                    },
+                ..
+            }] => (*dictionary, *rank),
+            sequence => panic!("unexpected sequence {:?}", sequence),
+        };
+
+        let (battery, battery_rank) = dictionary("battery");
+        let (staple, staple_rank) = dictionary("staple");
+        assert_eq!(
+            (battery, staple),
+            (Dictionary::English, Dictionary::English)
+        );
+        // More frequent words are cheaper to guess
+        assert!(battery_rank < staple_rank);
+        assert_eq!(
+            estimate_strength("battery").feedback.warning,
+            Some("A word by itself is easy to guess")
+        );
+
+        assert_eq!(dictionary("john").0, Dictionary::FirstNames);
+        assert_eq!(dictionary("johnson").0, Dictionary::Surnames);
+        assert_eq!(
+            estimate_strength("Johnson").feedback.warning,
+            Some("Names and surnames by themselves are easy to guess")
+        );
+        assert!(estimate_strength("horsebattery").score <= 2);
+    }
+
+    #[test]
+    fn test_l33t_and_capitalization() {
+        let strength = estimate_strength("P@ssw0rd");
+
+        assert_eq!(strength.score, 0);
+        assert!(strength
+            .feedback
+            .suggestions
+            .contains(&"Capitalization doesn't help very much"));
+
+        let strength = estimate_strength("Sh4d0w");
+        assert!(strength
+            .sequence
+            .iter()
+            .any(|m| matches!(m.pattern, MatchPattern::Dictionary { l33t: true, .. })));
+    }
+
+    #[test]
+    fn test_reversed_word() {
+        let strength = estimate_strength("drowssap");
+
+        assert!(matches!(
+            strength.sequence[0].pattern,
+            MatchPattern::Dictionary { reversed: true, .. }
+        ));
+        assert_eq!(strength.score, 0);
+    }
+
+    #[test]
+    fn test_keyboard_walks() {
+        assert_eq!(patterns("zxcvbnm,./"), vec!["spatial"]);
+        assert_eq!(patterns("#EDC4rfv"), vec!["spatial", "spatial"]);
+        assert_eq!(patterns("1qaz2wsx#EDC"), vec!["dictionary", "spatial"]);
+        assert_eq!(patterns("74123"), vec!["spatial"]);
+        assert!(estimate_strength("qwertyuiop[]").score <= 1);
+    }
+
+    #[test]
+    fn test_repeats() {
+        let strength = estimate_strength("aaaaaaaaaaaa");
+        assert_eq!(patterns("aaaaaaaaaaaa"), vec!["repeat"]);
+        assert_eq!(strength.score, 0);
+        assert_eq!(
+            strength.feedback.warning,
+            Some("Repeats like \"aaa\" are easy to guess")
+        );
+
+        let strength = estimate_strength("xkcdxkcdxkcd");
+        match &strength.sequence[0].pattern {
+            MatchPattern::Repeat { base, count, .. } => {
+                assert_eq!(base, "xkcd");
+                assert_eq!(*count, 3);
+            }
+            pattern => panic!("unexpected pattern {:?}", pattern),
+        }
+    }
+
+    #[test]
+    fn test_sequences() {
+        assert_eq!(patterns("abcdefgh"), vec!["sequence"]);
+        assert_eq!(patterns("97531"), vec!["sequence"]);
+        assert_eq!(
+            estimate_strength("ZYXWV").sequence[0].pattern,
+            MatchPattern::Sequence { ascending: false }
+        );
+    }
+
+    #[test]
+    fn test_dates() {
+        for password in ["13/05/1990", "1990-05-13", "13051990", "5.13.90"] {
+            let strength = estimate_strength(password);
+            assert_eq!(patterns(password), vec!["date"], "{}", password);
+            assert_eq!(
+                strength.feedback.warning,
+                Some("Dates are often easy to guess")
+            );
+        }
+
+        match estimate_strength("13/05/1990").sequence[0].pattern {
+            MatchPattern::Date {
+                year, month, day, ..
+            } => assert_eq!((year, month, day), (1990, 5, 13)),
+            ref pattern => panic!("unexpected pattern {:?}", pattern),
+        }
+
+        assert_eq!(patterns("1987"), vec!["year"]);
+    }
+
+    #[test]
+    fn test_strong_passwords() {
+        for password in [
+            "correct-horse-battery-staple-7",
+            "Lb*vTVcDniz7q6Ki-P",
+            "ywv*zJp&Qbz7x8U6TC",
+        ] {
+            let strength = estimate_strength(password);
+            assert_eq!(strength.score, 4, "{}", password);
+            assert_eq!(strength.feedback, Feedback::default());
+        }
+    }
+
+    #[test]
+    fn test_sequence_covers_password() {
+        let password = "Summer2019!qwerty";
+        let strength = estimate_strength(password);
+
+        let mut next = 0;
+        for m in &strength.sequence {
+            assert_eq!(m.start, next);
+            next = m.end + 1;
+        }
+        assert_eq!(next, password.chars().count());
+        assert_eq!(
+            strength
+                .sequence
+                .iter()
+                .map(|m| m.token.as_str())
+                .collect::<String>(),
+            password
+        );
+    }
+
+    #[test]
+    fn test_empty_and_unicode() {
+        let strength = estimate_strength("");
+        assert_eq!(strength.score, 0);
+        assert!(strength.sequence.is_empty());
+        assert_eq!(strength.feedback.suggestions.len(), 2);
+
+        let strength = estimate_strength("ñandúñandú");
+        assert!(strength.score <= 2);
+    }
+
+    #[test]
+    fn test_score_thresholds() {
+        assert_eq!(score(1.0), 0);
+        assert_eq!(score(1e4), 1);
+        assert_eq!(score(1e7), 2);
+        assert_eq!(score(1e9), 3);
+        assert_eq!(score(1e11), 4);
+    }
+}
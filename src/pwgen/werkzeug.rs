@@ -0,0 +1,127 @@
+use crate::pwgen::hash::Pbkdf2Algorithm;
+use anyhow::{anyhow, Result};
+use rand::{distr::Alphanumeric, Rng};
+
+const PREFIX: &str = "pbkdf2:";
+
+/// Default iterations of `generate_password_hash` in Werkzeug 3.1
+pub const DEFAULT_ITERATIONS: u32 = 1_000_000;
+
+/// Salt length of Werkzeug
+pub const SALT_LEN: usize = 16;
+
+/// A parsed `pbkdf2:<digest>[:<iterations>]$<salt>$<hex key>` hash
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pbkdf2Hash<'a> {
+    pub digest: Pbkdf2Algorithm,
+    pub iterations: u32,
+    pub salt: &'a str,
+    pub key: &'a str,
+}
+
+/// Returns a random alphanumeric salt of `SALT_LEN` characters
+pub fn gensalt() -> String {
+    rand::rng()
+        .sample_iter(Alphanumeric)
+        .take(SALT_LEN)
+        .map(char::from)
+        .collect()
+}
+
+/// Returns the `pbkdf2:sha256:<iterations>$<salt>$<hex key>` hash of `generate_password_hash`
+pub fn hash(password: &[u8], salt: &str, iterations: u32) -> Result<String> {
+    Ok(format!(
+        "{}{}:{}${}${}",
+        PREFIX,
+        Pbkdf2Algorithm::Sha256.name(),
+        iterations,
+        salt,
+        key(password, Pbkdf2Algorithm::Sha256, salt, iterations)?
+    ))
+}
+
+/// Returns the hex key of a password, as stored after the salt
+pub fn key(
+    password: &[u8],
+    digest: Pbkdf2Algorithm,
+    salt: &str,
+    iterations: u32,
+) -> Result<String> {
+    Ok(digest
+        .derive(password, salt.as_bytes(), iterations, digest.output_len())?
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect())
+}
+
+/// Parses a hash, the iterations default to `DEFAULT_ITERATIONS` when missing like Werkzeug does
+pub fn parse(hashed: &str) -> Result<Pbkdf2Hash<'_>> {
+    let invalid = || anyhow!("Invalid Werkzeug pbkdf2 hash");
+
+    let rest = hashed.strip_prefix(PREFIX).ok_or_else(invalid)?;
+    let fields: Vec<&str> = rest.split('$').collect();
+    let [method, salt, key] = fields[..] else {
+        return Err(invalid());
+    };
+
+    let (digest, iterations) = match method.split_once(':') {
+        Some((digest, iterations)) => (digest, iterations.parse().map_err(|_| invalid())?),
+        None => (method, DEFAULT_ITERATIONS),
+    };
+    let digest = digest
+        .parse()
+        .map_err(|_| anyhow!("Unsupported Werkzeug pbkdf2 digest '{}'", digest))?;
+    if iterations == 0 || salt.is_empty() || key.is_empty() {
+        return Err(invalid());
+    }
+
+    Ok(Pbkdf2Hash {
+        digest,
+        iterations,
+        salt,
+        key,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Computed with Python's `hashlib.pbkdf2_hmac`
+    const HASH: &str = "pbkdf2:sha256:1000$saltsaltsaltsalt$\
+        f275fb870144cc807c68f6a325360af3078741ce4d833d2915500abd2bb88d00";
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(hash(b"password", "saltsaltsaltsalt", 1000).unwrap(), HASH);
+        assert_eq!(
+            key(
+                b"password",
+                Pbkdf2Algorithm::Sha512,
+                "saltsaltsaltsalt",
+                1000
+            )
+            .unwrap(),
+            "ef5e6ba88af97573953e9061aaab2e825d37ef34f96d6253598999b4870af210\
+             678ac2a9c1f63b92892fc230eb347a87845e743dbecc0fa1ef909c220d0c38c3"
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        let parsed = parse(HASH).unwrap();
+        assert_eq!(parsed.digest, Pbkdf2Algorithm::Sha256);
+        assert_eq!(parsed.iterations, 1000);
+        assert_eq!(parsed.salt, "saltsaltsaltsalt");
+        assert_eq!(parsed.key.len(), 64);
+
+        let parsed = parse("pbkdf2:sha512$salt$key").unwrap();
+        assert_eq!(parsed.digest, Pbkdf2Algorithm::Sha512);
+        assert_eq!(parsed.iterations, DEFAULT_ITERATIONS);
+
+        assert!(parse("pbkdf2:sha256:1000$salt").is_err());
+        assert!(parse("pbkdf2:sha256:x$salt$key").is_err());
+        assert!(parse("pbkdf2:md5:1000$salt$key").is_err());
+        assert!(parse("scrypt:32768:8:1$salt$key").is_err());
+    }
+}
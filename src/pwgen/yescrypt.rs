@@ -0,0 +1,712 @@
+use anyhow::{anyhow, bail, Result};
+use hmac::{Hmac, Mac};
+use pbkdf2::pbkdf2_hmac;
+use rand::random;
+use sha2::{Digest, Sha256};
+
+use super::CRYPT_ITOA64 as ITOA64;
+
+/// Default cost, same as `mkpasswd -m yescrypt` (N = 4096, r = 32)
+pub const DEFAULT_COST: u8 = 5;
+
+/// Valid costs, as accepted by libxcrypt's `crypt_gensalt`
+pub const MIN_COST: u8 = 1;
+pub const MAX_COST: u8 = 11;
+
+const SALT_LEN: usize = 16;
+const MAX_SALT_LEN: usize = 64;
+const HASH_LEN: usize = 32;
+
+/// Flavor of `$y$j..`: read-write mode with 6 pwxform rounds, 4 gathers,
+/// 2 simple lanes and 12 KiB S-boxes, the only one libxcrypt computes
+const FLAVOR_DEFAULT: u32 = 47;
+
+// pwxform settings
+const PWX_SIMPLE: usize = 2;
+const PWX_GATHER: usize = 4;
+const PWX_ROUNDS: usize = 6;
+const S_WIDTH: usize = 8;
+const PWX_WORDS: usize = PWX_GATHER * PWX_SIMPLE * 2;
+const S_WORDS: usize = 3 * (1 << S_WIDTH) * PWX_SIMPLE * 2;
+const S_MASK: u32 = (((1 << S_WIDTH) - 1) * PWX_SIMPLE * 8) as u32;
+
+/// yescrypt parameters, read from the `$y$` setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Params {
+    n: u64,
+    r: u32,
+    p: u32,
+    t: u32,
+}
+
+/// log2 of N and r of a cost, as chosen by libxcrypt
+const fn cost_params(cost: u8) -> (u32, u32) {
+    if cost <= 2 {
+        (9 + cost as u32, 8)
+    } else {
+        (7 + cost as u32, 32)
+    }
+}
+
+/// Memory used by a hash of the given cost in KiB, 128 * r * N bytes
+pub const fn memory_kib(cost: u8) -> u32 {
+    let (n_log2, r) = cost_params(cost);
+    (1 << n_log2) * r / 8
+}
+
+/// Returns a new `$y$` setting with a random salt for the given cost
+pub fn gensalt(cost: u8) -> Result<String> {
+    if !(MIN_COST..=MAX_COST).contains(&cost) {
+        bail!(
+            "Invalid yescrypt cost {}, it must be between {} and {}",
+            cost,
+            MIN_COST,
+            MAX_COST
+        );
+    }
+
+    let (n_log2, r) = cost_params(cost);
+    let salt: [u8; SALT_LEN] = random();
+
+    let mut setting = String::from("$y$");
+    setting.push_str(&encode64_uint32(FLAVOR_DEFAULT, 0));
+    setting.push_str(&encode64_uint32(n_log2, 1));
+    setting.push_str(&encode64_uint32(r, 1));
+    setting.push('$');
+    setting.push_str(&encode64(&salt));
+
+    Ok(setting)
+}
+
+/// Hashes a password with a `$y$` setting, or a full hash to verify a password
+pub fn hash(password: &[u8], setting: &str) -> Result<String> {
+    let invalid = || anyhow!("Invalid yescrypt setting");
+
+    let (params, rest) = parse(setting)?;
+    let prefix = &setting[..setting.len() - rest.len()];
+
+    // The salt ends at the last `$`, a full hash can be used as setting
+    let salt_str = rest
+        .iter()
+        .rposition(|c| *c == b'$')
+        .map_or(rest, |end| &rest[..end]);
+    let salt = decode64(salt_str).ok_or_else(invalid)?;
+
+    let hash = kdf(password, &salt, params)?;
+
+    Ok(format!(
+        "{}{}${}",
+        prefix,
+        String::from_utf8_lossy(salt_str),
+        encode64(&hash)
+    ))
+}
+
+/// Returns the cost of a `$y$` setting or hash, as passed to `gensalt`
+///
+/// Every cost uses twice the memory of the previous one, starting at 1 MiB, so
+/// settings with a non-standard block size get the cost of their memory usage.
+pub fn cost(setting: &str) -> Result<u8> {
+    let (params, _) = parse(setting)?;
+    let memory = u128::from(params.n) * 128 * u128::from(params.r);
+
+    Ok(memory.ilog2().saturating_sub(19) as u8)
+}
+
+/// Parses the parameters of a `$y$` setting, returns them with the salt and hash part
+fn parse(setting: &str) -> Result<(Params, &[u8])> {
+    let invalid = || anyhow!("Invalid yescrypt setting");
+
+    let rest = setting
+        .as_bytes()
+        .strip_prefix(b"$y$")
+        .ok_or_else(invalid)?;
+    let (flavor, rest) = decode64_uint32(rest, 0).ok_or_else(invalid)?;
+    if flavor != FLAVOR_DEFAULT {
+        bail!("Unsupported yescrypt flavor {}", flavor);
+    }
+
+    let (n_log2, rest) = decode64_uint32(rest, 1).ok_or_else(invalid)?;
+    if n_log2 > 63 {
+        return Err(invalid());
+    }
+    let (r, mut rest) = decode64_uint32(rest, 1).ok_or_else(invalid)?;
+
+    let mut params = Params {
+        n: 1 << n_log2,
+        r,
+        p: 1,
+        t: 0,
+    };
+
+    if rest.first() != Some(&b'$') {
+        let (have, next) = decode64_uint32(rest, 1).ok_or_else(invalid)?;
+        rest = next;
+        if have & 1 != 0 {
+            (params.p, rest) = decode64_uint32(rest, 2).ok_or_else(invalid)?;
+        }
+        if have & 2 != 0 {
+            (params.t, rest) = decode64_uint32(rest, 1).ok_or_else(invalid)?;
+        }
+        if have & !3 != 0 {
+            bail!("Unsupported yescrypt parameters, hash upgrades and ROMs are not implemented");
+        }
+    }
+
+    let rest = rest.strip_prefix(b"$").ok_or_else(invalid)?;
+
+    Ok((params, rest))
+}
+
+fn kdf(password: &[u8], salt: &[u8], params: Params) -> Result<[u8; HASH_LEN]> {
+    let Params { n, r, p, t } = params;
+
+    // Large hashes are computed on a prehash, so cheap attacks on the first
+    // part of the memory don't help
+    let prehashed;
+    let password = if n / u64::from(p) >= 0x100 && n / u64::from(p) * u64::from(r) >= 0x20000 {
+        prehashed = kdf_body(password, salt, n >> 6, r, p, 0, true)?;
+        &prehashed[..]
+    } else {
+        password
+    };
+
+    kdf_body(password, salt, n, r, p, t, false)
+}
+
+fn kdf_body(
+    password: &[u8],
+    salt: &[u8],
+    n: u64,
+    r: u32,
+    p: u32,
+    t: u32,
+    prehash: bool,
+) -> Result<[u8; HASH_LEN]> {
+    if n < 2 || !n.is_power_of_two() || r < 1 || p < 1 || u64::from(r) * u64::from(p) >= 1 << 30 {
+        bail!("Invalid yescrypt parameters");
+    }
+    if n / u64::from(p) <= 1 {
+        bail!("Invalid yescrypt parameters");
+    }
+
+    let r = r as usize;
+    let p = p as usize;
+    let s = 32 * r;
+
+    let v_len = usize::try_from(n)
+        .ok()
+        .and_then(|n| n.checked_mul(s))
+        .ok_or_else(|| anyhow!("yescrypt parameters need too much memory"))?;
+    let mut v: Vec<u32> = Vec::new();
+    v.try_reserve_exact(v_len)
+        .map_err(|_| anyhow!("yescrypt parameters need too much memory"))?;
+    v.resize(v_len, 0);
+
+    let key: &[u8] = if prehash {
+        b"yescrypt-prehash"
+    } else {
+        b"yescrypt"
+    };
+    let mut passwd = hmac_sha256(key, password);
+
+    let mut bytes = vec![0u8; 128 * r * p];
+    pbkdf2_hmac::<Sha256>(&passwd, salt, 1, &mut bytes);
+    passwd.copy_from_slice(&bytes[..32]);
+
+    let mut b: Vec<u32> = bytes
+        .chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect();
+
+    smix(&mut b, r, n, p, t, &mut v, &mut passwd);
+
+    let bytes: Vec<u8> = b.iter().flat_map(|w| w.to_le_bytes()).collect();
+    let mut dk = [0u8; HASH_LEN];
+    pbkdf2_hmac::<Sha256>(&passwd, &bytes, 1, &mut dk);
+
+    if !prehash {
+        // ClientKey and StoredKey, like SCRAM
+        let client_key = hmac_sha256(&dk, b"Client Key");
+        dk.copy_from_slice(&Sha256::digest(client_key));
+    }
+
+    Ok(dk)
+}
+
+/// S-boxes and write position of the pwxform transformation
+struct Pwxform {
+    s: Vec<u32>,
+    s0: usize,
+    s1: usize,
+    s2: usize,
+    w: usize,
+}
+
+fn smix(b: &mut [u32], r: usize, n: u64, p: usize, t: u32, v: &mut [u32], passwd: &mut [u8; 32]) {
+    let s = 32 * r;
+    let mut n_chunk = n / p as u64;
+
+    let mut n_loop_all = if t <= 1 {
+        let n_loop = if t == 1 { n_chunk * 2 } else { n_chunk };
+        n_loop.div_ceil(3)
+    } else {
+        n_chunk * u64::from(t - 1)
+    };
+    let mut n_loop_rw = n_loop_all / p as u64;
+
+    n_chunk &= !1;
+    n_loop_all = (n_loop_all + 1) & !1;
+    n_loop_rw = (n_loop_rw + 1) & !1;
+
+    let mut contexts = Vec::with_capacity(p);
+    let mut v_chunk = 0;
+
+    for i in 0..p {
+        let np = if i < p - 1 { n_chunk } else { n - v_chunk };
+        let bp = &mut b[s * i..s * (i + 1)];
+        let start = s * v_chunk as usize;
+        let vp = &mut v[start..start + s * np as usize];
+
+        // S-boxes are filled with SMix1 on the first 128 bytes of the block
+        let mut ctx = Pwxform {
+            s: vec![0; S_WORDS],
+            s0: 2 * S_WORDS / 3,
+            s1: S_WORDS / 3,
+            s2: 0,
+            w: 0,
+        };
+        smix1(
+            &mut bp[..32],
+            1,
+            (S_WORDS / 32) as u64,
+            false,
+            &mut ctx.s,
+            None,
+        );
+
+        if i == 0 {
+            let key: Vec<u8> = bp[s - 16..].iter().flat_map(|w| w.to_le_bytes()).collect();
+            *passwd = hmac_sha256(&key, passwd);
+        }
+
+        smix1(bp, r, np, true, vp, Some(&mut ctx));
+        smix2(bp, r, p2floor(np), n_loop_rw, true, vp, &mut ctx);
+
+        contexts.push(ctx);
+        v_chunk += n_chunk;
+    }
+
+    for (i, ctx) in contexts.iter_mut().enumerate() {
+        let bp = &mut b[s * i..s * (i + 1)];
+        smix2(bp, r, n, n_loop_all - n_loop_rw, false, v, ctx);
+    }
+}
+
+/// Loads a block, in the word order used by the SIMD implementations
+fn shuffle(b: &[u32]) -> Vec<u32> {
+    let mut x = vec![0; b.len()];
+    for (block, chunk) in x.chunks_exact_mut(16).zip(b.chunks_exact(16)) {
+        for i in 0..16 {
+            block[i] = chunk[i * 5 % 16];
+        }
+    }
+    x
+}
+
+fn unshuffle(x: &[u32], b: &mut [u32]) {
+    for (chunk, block) in b.chunks_exact_mut(16).zip(x.chunks_exact(16)) {
+        for i in 0..16 {
+            chunk[i * 5 % 16] = block[i];
+        }
+    }
+}
+
+fn smix1(b: &mut [u32], r: usize, n: u64, rw: bool, v: &mut [u32], mut ctx: Option<&mut Pwxform>) {
+    let s = 32 * r;
+    let mut x = shuffle(&b[..s]);
+    let mut y = vec![0; s];
+
+    for i in 0..n {
+        let offset = i as usize * s;
+        v[offset..offset + s].copy_from_slice(&x);
+
+        if rw && i > 1 {
+            let j = wrap(integerify(&x, r), i) as usize;
+            xor(&mut x, &v[j * s..(j + 1) * s]);
+        }
+
+        match ctx.as_deref_mut() {
+            Some(ctx) => blockmix_pwxform(&mut x, r, ctx),
+            None => blockmix_salsa8(&mut x, &mut y, r),
+        }
+    }
+
+    unshuffle(&x, &mut b[..s]);
+}
+
+fn smix2(b: &mut [u32], r: usize, n: u64, n_loop: u64, rw: bool, v: &mut [u32], ctx: &mut Pwxform) {
+    if n_loop == 0 {
+        return;
+    }
+
+    let s = 32 * r;
+    let mut x = shuffle(b);
+
+    for _ in 0..n_loop {
+        let j = (integerify(&x, r) & (n - 1)) as usize;
+        let vj = &mut v[j * s..(j + 1) * s];
+        xor(&mut x, vj);
+        if rw {
+            vj.copy_from_slice(&x);
+        }
+
+        blockmix_pwxform(&mut x, r, ctx);
+    }
+
+    unshuffle(&x, b);
+}
+
+fn blockmix_salsa8(b: &mut [u32], y: &mut [u32], r: usize) {
+    let mut x = [0u32; 16];
+    x.copy_from_slice(&b[(2 * r - 1) * 16..2 * r * 16]);
+
+    for i in 0..2 * r {
+        xor(&mut x, &b[i * 16..(i + 1) * 16]);
+        salsa20(&mut x, 8);
+        y[i * 16..(i + 1) * 16].copy_from_slice(&x);
+    }
+
+    for i in 0..r {
+        b[i * 16..(i + 1) * 16].copy_from_slice(&y[i * 2 * 16..(i * 2 + 1) * 16]);
+        b[(i + r) * 16..(i + r + 1) * 16].copy_from_slice(&y[(i * 2 + 1) * 16..(i * 2 + 2) * 16]);
+    }
+}
+
+fn blockmix_pwxform(b: &mut [u32], r: usize, ctx: &mut Pwxform) {
+    let r1 = 2 * r;
+    let mut x = [0u32; PWX_WORDS];
+    x.copy_from_slice(&b[(r1 - 1) * PWX_WORDS..r1 * PWX_WORDS]);
+
+    for i in 0..r1 {
+        let block = &mut b[i * PWX_WORDS..(i + 1) * PWX_WORDS];
+        if r1 > 1 {
+            xor(&mut x, block);
+        }
+        pwxform(&mut x, ctx);
+        block.copy_from_slice(&x);
+    }
+
+    let last = (r1 - 1) * PWX_WORDS;
+    salsa20(&mut b[last..last + 16], 2);
+}
+
+fn pwxform(x: &mut [u32; PWX_WORDS], ctx: &mut Pwxform) {
+    let s = &mut ctx.s;
+    let mut w = ctx.w;
+
+    for round in 0..PWX_ROUNDS {
+        for j in 0..PWX_GATHER {
+            let lane = j * PWX_SIMPLE * 2;
+            let p0 = ctx.s0 + (x[lane] & S_MASK) as usize / 4;
+            let p1 = ctx.s1 + (x[lane + 1] & S_MASK) as usize / 4;
+
+            for k in 0..PWX_SIMPLE {
+                let word = |index: usize| u64::from(s[index + 1]) << 32 | u64::from(s[index]);
+                let s0 = word(p0 + k * 2);
+                let s1 = word(p1 + k * 2);
+
+                let xl = u64::from(x[lane + k * 2]);
+                let xh = u64::from(x[lane + k * 2 + 1]);
+                let value = (xh * xl).wrapping_add(s0) ^ s1;
+
+                x[lane + k * 2] = value as u32;
+                x[lane + k * 2 + 1] = (value >> 32) as u32;
+            }
+
+            if round != 0 && round != PWX_ROUNDS - 1 {
+                let offset = ctx.s2 + w * PWX_SIMPLE * 2;
+                s[offset..offset + PWX_SIMPLE * 2].copy_from_slice(&x[lane..lane + PWX_SIMPLE * 2]);
+                w += 1;
+            }
+        }
+    }
+
+    (ctx.s0, ctx.s1, ctx.s2) = (ctx.s2, ctx.s0, ctx.s1);
+    ctx.w = w & ((1 << S_WIDTH) - 1);
+}
+
+/// Salsa20 core on a block in SIMD word order
+fn salsa20(b: &mut [u32], rounds: usize) {
+    let mut x = [0u32; 16];
+    for i in 0..16 {
+        x[i * 5 % 16] = b[i];
+    }
+
+    let quarter = |x: &mut [u32; 16], a: usize, b: usize, c: usize, d: usize| {
+        x[b] ^= x[a].wrapping_add(x[d]).rotate_left(7);
+        x[c] ^= x[b].wrapping_add(x[a]).rotate_left(9);
+        x[d] ^= x[c].wrapping_add(x[b]).rotate_left(13);
+        x[a] ^= x[d].wrapping_add(x[c]).rotate_left(18);
+    };
+
+    for _ in (0..rounds).step_by(2) {
+        quarter(&mut x, 0, 4, 8, 12);
+        quarter(&mut x, 5, 9, 13, 1);
+        quarter(&mut x, 10, 14, 2, 6);
+        quarter(&mut x, 15, 3, 7, 11);
+
+        quarter(&mut x, 0, 1, 2, 3);
+        quarter(&mut x, 5, 6, 7, 4);
+        quarter(&mut x, 10, 11, 8, 9);
+        quarter(&mut x, 15, 12, 13, 14);
+    }
+
+    for i in 0..16 {
+        b[i] = b[i].wrapping_add(x[i * 5 % 16]);
+    }
+}
+
+fn integerify(x: &[u32], r: usize) -> u64 {
+    let last = (2 * r - 1) * 16;
+    u64::from(x[last + 13]) << 32 | u64::from(x[last])
+}
+
+const fn p2floor(mut x: u64) -> u64 {
+    while x & (x - 1) != 0 {
+        x &= x - 1;
+    }
+    x
+}
+
+const fn wrap(x: u64, i: u64) -> u64 {
+    let n = p2floor(i);
+    (x & (n - 1)) + (i - n)
+}
+
+fn xor(dst: &mut [u32], src: &[u32]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d ^= s;
+    }
+}
+
+fn hmac_sha256(key: &[u8], message: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(message);
+    mac.finalize().into_bytes().into()
+}
+
+/// Encodes bytes in the crypt base64 alphabet, little-endian
+fn encode64(src: &[u8]) -> String {
+    let mut encoded = String::new();
+
+    for chunk in src.chunks(3) {
+        let value = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |value, (i, byte)| value | u32::from(*byte) << (8 * i));
+        for i in 0..(chunk.len() * 8).div_ceil(6) {
+            encoded.push(ITOA64[(value >> (6 * i) & 0x3f) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+fn decode64(src: &[u8]) -> Option<Vec<u8>> {
+    let mut decoded = Vec::new();
+
+    for chunk in src.chunks(4) {
+        if chunk.len() < 2 {
+            return None;
+        }
+        let mut value = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            value |= c2i(*c)? << (6 * i);
+        }
+        let bytes = chunk.len() * 6 / 8;
+        decoded.extend_from_slice(&value.to_le_bytes()[..bytes]);
+        // Unused bits must be zero
+        if value >> (8 * bytes) != 0 {
+            return None;
+        }
+    }
+
+    (decoded.len() <= MAX_SALT_LEN).then_some(decoded)
+}
+
+/// Variable length encoding of the numbers in the setting
+fn encode64_uint32(value: u32, min: u32) -> String {
+    let mut src = value - min;
+    let (mut start, mut end, mut chars, mut bits) = (0u32, 47u32, 1, 0);
+
+    loop {
+        let count = (end + 1 - start) << bits;
+        if src < count {
+            break;
+        }
+        start = end + 1;
+        end = start + (62 - end) / 2;
+        src -= count;
+        chars += 1;
+        bits += 6;
+    }
+
+    let mut encoded = String::new();
+    encoded.push(ITOA64[(start + (src >> bits)) as usize] as char);
+    for _ in 1..chars {
+        bits -= 6;
+        encoded.push(ITOA64[(src >> bits & 0x3f) as usize] as char);
+    }
+
+    encoded
+}
+
+fn decode64_uint32(src: &[u8], min: u32) -> Option<(u32, &[u8])> {
+    let (first, mut rest) = src.split_first()?;
+    let c = c2i(*first)?;
+    let (mut start, mut end, mut chars, mut bits) = (0u32, 47u32, 1, 0);
+    let mut value = min;
+
+    while c > end {
+        value = value.checked_add((end + 1 - start).checked_shl(bits)?)?;
+        start = end + 1;
+        end = start + (62 - end) / 2;
+        chars += 1;
+        bits += 6;
+    }
+    value = value.checked_add((c - start).checked_shl(bits)?)?;
+
+    for _ in 1..chars {
+        let (next, remaining) = rest.split_first()?;
+        bits -= 6;
+        value = value.checked_add(c2i(*next)?.checked_shl(bits)?)?;
+        rest = remaining;
+    }
+
+    Some((value, rest))
+}
+
+fn c2i(c: u8) -> Option<u32> {
+    ITOA64.iter().position(|i| *i == c).map(|i| i as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode64_uint32() {
+        assert_eq!(encode64_uint32(47, 0), "j");
+        assert_eq!(encode64_uint32(12, 1), "9");
+        assert_eq!(encode64_uint32(32, 1), "T");
+
+        for value in [0, 1, 47, 48, 100, 1000, 100_000] {
+            let encoded = encode64_uint32(value, 0);
+            assert_eq!(
+                decode64_uint32(encoded.as_bytes(), 0),
+                Some((value, &b""[..]))
+            );
+        }
+    }
+
+    /// Hashes computed with libxcrypt's `crypt(3)`
+    const VECTORS: [(&str, &str); 8] = [
+        (
+            "password",
+            "$y$j9T$k2XAnEHBqQ1Ct2aMXFKNa/$OVYXzjlkiQpWT/F1CUE0JrvV4phLY8FB.ofDttnrSQ7",
+        ),
+        (
+            "password",
+            "$y$j75$k2XAnEHBqQ1Ct2aMXFKNa/$m4lwJ4nFEuCl0FFCrU4dJtyuhT0Ai2jNWLnkYlySGEB",
+        ),
+        (
+            "",
+            "$y$j85$abcdefghijklmnop$TvGHy9ljT5T6pJJcynHX9eunIsEnm83FY74jRS5nwAA",
+        ),
+        (
+            "pwgen2",
+            "$y$j7T$abcdefghijklmnopqrstu.$Y6T3XZjdYzTCl5dTDskPBtSpnB7ZCv7AV9bl8RCGAl/",
+        ),
+        (
+            "pwgen2",
+            "$y$j75..$k2XAnEHBqQ1Ct2aMXFKNa/$iB7y2fCPJYxymzYnwppmj.jEp8VHpmK.2M8rryp97m.",
+        ),
+        (
+            "pwgen2",
+            "$y$j75/.$k2XAnEHBqQ1Ct2aMXFKNa/$oqvUuW18x316rP/s4HAuiYtgXSIwggZkYWy8G6EgN.2",
+        ),
+        (
+            "pwgen2",
+            "$y$j750./$k2XAnEHBqQ1Ct2aMXFKNa/$Unv1pZL0W.sTkenew0PcfD2y/Fwb.Aoa0yW7h0FftN/",
+        ),
+        (
+            "ñandú",
+            "$y$j8T$k2XAnEHBqQ1Ct2aMXFKNa/$2RMrItTlD339x1G3cUvGUlIZRVKlxd9phe.Z/FIfb2.",
+        ),
+    ];
+
+    #[test]
+    fn test_hash_vectors() {
+        for (password, expected) in VECTORS {
+            assert_eq!(hash(password.as_bytes(), expected).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_hash_setting() {
+        let (password, expected) = VECTORS[1];
+        let setting = &expected[..expected.rfind('$').unwrap()];
+
+        assert_eq!(hash(password.as_bytes(), setting).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_hash_invalid() {
+        assert!(hash(b"password", "$6$salt$").is_err());
+        assert!(hash(b"password", "$y$j9T").is_err());
+        assert!(hash(b"password", "$y$$salt").is_err());
+        // Unused bits of the salt must be zero
+        assert!(hash(b"password", "$y$j7T$abcdefghijklmnopqrstuv").is_err());
+        // Classic scrypt flavor
+        assert!(hash(b"password", "$y$.75$k2XAnEHBqQ1Ct2aMXFKNa/").is_err());
+    }
+
+    #[test]
+    fn test_gensalt() {
+        let setting = gensalt(DEFAULT_COST).unwrap();
+        assert!(setting.starts_with("$y$j9T$"));
+        assert_eq!(setting.len(), 29);
+
+        assert!(gensalt(1).unwrap().starts_with("$y$j75$"));
+        assert!(gensalt(3).unwrap().starts_with("$y$j7T$"));
+        assert!(gensalt(11).unwrap().starts_with("$y$jFT$"));
+        assert!(gensalt(0).is_err());
+        assert!(gensalt(12).is_err());
+    }
+
+    #[test]
+    fn test_encode64() {
+        let salt: [u8; SALT_LEN] = random();
+        let encoded = encode64(&salt);
+
+        assert_eq!(encoded.len(), 22);
+        assert_eq!(decode64(encoded.as_bytes()), Some(salt.to_vec()));
+    }
+
+    #[test]
+    fn test_cost() {
+        assert_eq!(memory_kib(1), 1024);
+        assert_eq!(memory_kib(DEFAULT_COST), 16 * 1024);
+        assert_eq!(memory_kib(MAX_COST), 1024 * 1024);
+
+        for cost in MIN_COST..=MAX_COST {
+            assert_eq!(super::cost(&gensalt(cost).unwrap()).unwrap(), cost);
+        }
+
+        assert_eq!(super::cost(VECTORS[0].1).unwrap(), DEFAULT_COST);
+        assert_eq!(super::cost(VECTORS[1].1).unwrap(), 1);
+        assert!(super::cost("$y$j9T").is_err());
+        assert!(super::cost("$6$salt$hash").is_err());
+    }
+}